@@ -1,8 +1,9 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use lzzzz::{lz4, lz4_hc, lz4f};
+use lzzzz::{lz4, lz4_hc, lz4f, DictionaryTrainer};
 use std::{
-    i32,
-    io::{Read, Write},
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::Path,
 };
 
 fn lz4_compress(level: i32, data: &[u8]) {
@@ -112,6 +113,7 @@ fn lz4f_write_compressor(n: usize, prefs: lz4f::Preferences, data: &[u8]) {
     for _ in 0..n {
         w.write_all(data).unwrap();
     }
+    w.finish().unwrap();
 }
 
 fn lz4f_bufread_compressor(n: usize, prefs: lz4f::Preferences, data: &[u8]) {
@@ -163,4 +165,123 @@ fn lz4f_benchmark(c: &mut Criterion) {
 }
 
 criterion_group!(lz4f_benches, lz4f_benchmark);
-criterion_main!(lz4_benches, lz4_hc_benches, lz4f_benches);
+
+fn lz4f_read_decompressor_file(path: &Path) {
+    let mut buf = Vec::new();
+    let mut r = lz4f::ReadDecompressor::new(BufReader::new(File::open(path).unwrap())).unwrap();
+    r.read_to_end(&mut buf).unwrap();
+}
+
+#[cfg(feature = "memmap2")]
+fn lz4f_decompress_mmap(path: &Path, orig_len: usize) {
+    let mut buf = vec![0u8; orig_len];
+    let mmap = unsafe { memmap2::Mmap::map(&File::open(path).unwrap()).unwrap() };
+    lz4f::decompress_mmap(&mmap, &mut buf).unwrap();
+}
+
+fn lz4f_file_benchmark(c: &mut Criterion) {
+    let data = include_bytes!("lorem-ipsum.txt").repeat(4096);
+    let mut compressed = Vec::new();
+    lz4f::compress_to_vec(&data, &mut compressed, &Default::default()).unwrap();
+
+    let tmp_dir = assert_fs::TempDir::new().unwrap();
+    let path = tmp_dir.path().join("lorem-ipsum.lz4");
+    std::fs::write(&path, &compressed).unwrap();
+
+    c.bench_function("lz4f::ReadDecompressor (BufReader<File>)", |b| {
+        b.iter(|| lz4f_read_decompressor_file(black_box(&path)))
+    });
+
+    #[cfg(feature = "memmap2")]
+    c.bench_function("lz4f::decompress_file", |b| {
+        b.iter(|| lz4f::decompress_file(black_box(&path)).unwrap())
+    });
+
+    #[cfg(feature = "memmap2")]
+    c.bench_function("lz4f::decompress_mmap", |b| {
+        b.iter(|| lz4f_decompress_mmap(black_box(&path), data.len()))
+    });
+}
+
+criterion_group! {
+    name = lz4f_file_benches;
+    config = Criterion::default().sample_size(20);
+    targets = lz4f_file_benchmark
+}
+
+fn json_records(n: usize) -> Vec<String> {
+    (0..n)
+        .map(|i| {
+            format!(
+                r#"{{"id":{},"user":"user{}","email":"user{}@example.com","score":{},"tags":["alpha","beta"],"active":{}}}"#,
+                i,
+                i * 31 % 1000,
+                i * 17 % 1000,
+                i * 7 % 100,
+                i % 3 == 0
+            )
+        })
+        .collect()
+}
+
+fn lz4_compress_records(records: &[String], dict: Option<&[u8]>) -> usize {
+    let mut buf = Vec::new();
+    records
+        .iter()
+        .map(|record| {
+            buf.clear();
+            match dict {
+                Some(dict) => lz4::Compressor::with_dict(dict)
+                    .unwrap()
+                    .next_to_vec(record.as_bytes(), &mut buf, lz4::ACC_LEVEL_DEFAULT)
+                    .unwrap(),
+                None => lz4::compress_to_vec(record.as_bytes(), &mut buf, lz4::ACC_LEVEL_DEFAULT)
+                    .unwrap(),
+            }
+        })
+        .sum()
+}
+
+fn dictionary_benchmark(c: &mut Criterion) {
+    let records = json_records(2000);
+    let (training, records) = records.split_at(1000);
+    let dict = DictionaryTrainer::new().train(training);
+
+    // Criterion only measures time, so the compressed sizes showing
+    // the ratio gain of the dictionary are printed once here.
+    let orig_len = records.iter().map(String::len).sum::<usize>();
+    let plain_len = lz4_compress_records(records, None);
+    let dict_len = lz4_compress_records(records, Some(dict.as_bytes()));
+    println!(
+        "{} JSON records ({} bytes): {} bytes compressed without dictionary (ratio {:.2}), \
+         {} bytes with a {} byte dictionary (ratio {:.2})",
+        records.len(),
+        orig_len,
+        plain_len,
+        orig_len as f64 / plain_len as f64,
+        dict_len,
+        dict.as_bytes().len(),
+        orig_len as f64 / dict_len as f64
+    );
+
+    c.bench_function("DictionaryTrainer::train", |b| {
+        b.iter(|| DictionaryTrainer::new().train(black_box(training)))
+    });
+
+    c.bench_function("lz4::compress (JSON records)", |b| {
+        b.iter(|| lz4_compress_records(black_box(records), None))
+    });
+
+    c.bench_function("lz4::Compressor::with_dict (JSON records)", |b| {
+        b.iter(|| lz4_compress_records(black_box(records), Some(dict.as_bytes())))
+    });
+}
+
+criterion_group!(dictionary_benches, dictionary_benchmark);
+criterion_main!(
+    lz4_benches,
+    lz4_hc_benches,
+    lz4f_benches,
+    lz4f_file_benches,
+    dictionary_benches
+);
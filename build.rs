@@ -1,7 +1,10 @@
 fn main() -> Result<(), cc::Error> {
-    let sources = &["lz4.c", "lz4hc.c", "lz4frame.c", "xxhash.c"][..];
+    // lz4frame.c is compiled through lz4frame_ext.c, which includes it.
+    let sources = &["lz4.c", "lz4hc.c", "xxhash.c"][..];
     let dir = std::path::Path::new("vendor/liblz4");
     cc::Build::new()
         .files(sources.iter().map(|file| dir.join(file)))
+        .file("src/lz4f/lz4frame_ext.c")
+        .include(dir)
         .try_compile("lz4")
 }
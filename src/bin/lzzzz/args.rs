@@ -0,0 +1,209 @@
+use lzzzz::lz4f::{
+    BlockChecksum, BlockMode, BlockSize, ContentChecksum, Preferences, PreferencesBuilder,
+};
+
+pub const USAGE: &str = "\
+Usage: lzzzz [OPTIONS] [INPUT] [OUTPUT]
+       lzzzz inspect [--json] FILE...
+
+Compresses or decompresses INPUT into OUTPUT in the LZ4 frame format.
+INPUT defaults to the standard input. OUTPUT defaults to INPUT with the .lz4
+extension added (compression) or removed (decompression).
+
+Options:
+  -1 .. -12       compression level (default: 1)
+  -z, --compress  force compression
+  -d, --decompress
+                  decompression
+  -t, --test      test the integrity of compressed files
+  -c, --stdout    write to the standard output
+  -f, --force     overwrite the output without prompting
+  -k, --keep      keep the source files (default)
+      --rm        remove the source files after a successful (de)compression
+  -m, --multiple  treat every operand as an input file
+      --list      list the frames of the compressed files (same as inspect)
+      --json      print the frame list in JSON
+  -l              compress in the legacy format
+  -B4 .. -B7      block size: 64 KB, 256 KB, 1 MB or 4 MB (default: 4 MB)
+  -BD             link the blocks (better ratio for small blocks)
+  -BI             independent blocks (default)
+  -BX             enable the block checksums
+      --content-size
+                  store the original size in the frame header (not for stdin)
+      --no-content-size
+                  do not store the original size (default)
+      --frame-crc, --no-frame-crc
+                  enable (default) or disable the content checksum
+  -q, --quiet     suppress warnings; specify twice to suppress errors too
+  -v, --verbose   print the compression statistics
+  -h, --help      print this help
+  -V, --version   print the version";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Auto,
+    Compress,
+    Decompress,
+    Test,
+    List,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Run(Options),
+    Help,
+    Version,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub mode: Mode,
+    pub level: i32,
+    pub block_size: BlockSize,
+    pub block_mode: BlockMode,
+    pub block_checksum: bool,
+    pub content_checksum: bool,
+    pub content_size: bool,
+    pub legacy: bool,
+    pub stdout: bool,
+    pub force: bool,
+    pub multiple: bool,
+    pub remove: bool,
+    pub json: bool,
+    pub verbosity: i32,
+    pub files: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Auto,
+            level: 1,
+            block_size: BlockSize::Max4MB,
+            block_mode: BlockMode::Independent,
+            block_checksum: false,
+            content_checksum: true,
+            content_size: false,
+            legacy: false,
+            stdout: false,
+            force: false,
+            multiple: false,
+            remove: false,
+            json: false,
+            verbosity: 1,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Options {
+    /// Returns the frame preferences, with the content size if it is known.
+    pub fn prefs(&self, content_size: Option<u64>) -> Preferences {
+        let mut builder = PreferencesBuilder::new();
+        builder
+            .compression_level(self.level)
+            .block_size(self.block_size)
+            .block_mode(self.block_mode)
+            .block_checksum(if self.block_checksum {
+                BlockChecksum::Enabled
+            } else {
+                BlockChecksum::Disabled
+            })
+            .content_checksum(if self.content_checksum {
+                ContentChecksum::Enabled
+            } else {
+                ContentChecksum::Disabled
+            });
+        if let Some(size) = content_size.filter(|_| self.content_size) {
+            builder.content_size(size as usize);
+        }
+        builder.build()
+    }
+}
+
+pub fn parse<I>(args: I) -> Result<Command, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut opts = Options::default();
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(String::as_str) == Some("inspect") {
+        args.next();
+        opts.mode = Mode::List;
+    }
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            opts.files.extend(args.by_ref());
+        } else if let Some(long) = arg.strip_prefix("--") {
+            match long {
+                "compress" => opts.mode = Mode::Compress,
+                "decompress" | "uncompress" => opts.mode = Mode::Decompress,
+                "test" => opts.mode = Mode::Test,
+                "list" => opts.mode = Mode::List,
+                "json" => opts.json = true,
+                "stdout" | "to-stdout" => opts.stdout = true,
+                "force" => opts.force = true,
+                "keep" => opts.remove = false,
+                "rm" => opts.remove = true,
+                "multiple" => opts.multiple = true,
+                "content-size" => opts.content_size = true,
+                "no-content-size" => opts.content_size = false,
+                "frame-crc" => opts.content_checksum = true,
+                "no-frame-crc" => opts.content_checksum = false,
+                "best" => opts.level = 12,
+                "quiet" => opts.verbosity -= 1,
+                "verbose" => opts.verbosity += 1,
+                "help" => return Ok(Command::Help),
+                "version" => return Ok(Command::Version),
+                _ => return Err(format!("unknown option: {}", arg)),
+            }
+        } else if arg.len() > 1 && arg.starts_with('-') {
+            if let Some(command) = parse_short(&arg[1..], &mut opts)? {
+                return Ok(command);
+            }
+        } else {
+            opts.files.push(arg);
+        }
+    }
+    Ok(Command::Run(opts))
+}
+
+fn parse_short(flags: &str, opts: &mut Options) -> Result<Option<Command>, String> {
+    let mut chars = flags.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => {
+                let mut level = c.to_digit(10).unwrap();
+                while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    level = level.saturating_mul(10).saturating_add(d);
+                    chars.next();
+                }
+                opts.level = level.min(12) as i32;
+            }
+            'z' => opts.mode = Mode::Compress,
+            'd' => opts.mode = Mode::Decompress,
+            't' => opts.mode = Mode::Test,
+            'c' => opts.stdout = true,
+            'f' => opts.force = true,
+            'k' => opts.remove = false,
+            'm' => opts.multiple = true,
+            'l' => opts.legacy = true,
+            'q' => opts.verbosity -= 1,
+            'v' => opts.verbosity += 1,
+            'h' | 'H' => return Ok(Some(Command::Help)),
+            'V' => return Ok(Some(Command::Version)),
+            'B' => match chars.next() {
+                Some('4') => opts.block_size = BlockSize::Max64KB,
+                Some('5') => opts.block_size = BlockSize::Max256KB,
+                Some('6') => opts.block_size = BlockSize::Max1MB,
+                Some('7') => opts.block_size = BlockSize::Max4MB,
+                Some('D') => opts.block_mode = BlockMode::Linked,
+                Some('I') => opts.block_mode = BlockMode::Independent,
+                Some('X') => opts.block_checksum = true,
+                _ => return Err(format!("invalid block option: -{}", flags)),
+            },
+            _ => return Err(format!("unknown option: -{}", c)),
+        }
+    }
+    Ok(None)
+}
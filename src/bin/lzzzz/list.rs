@@ -0,0 +1,248 @@
+//! The `--list` mode, which prints the frames of compressed files.
+
+use crate::{args::Options, is_stdin, BUF_SIZE, LEGACY_MAGIC, STDIN_MARK};
+use lzzzz::lz4f::{self, BlockMode, BlockSize, ChecksumStatus, FrameSummary, FrameType};
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufReader},
+};
+
+const LZ4F_MAGIC: u32 = 0x184D_2204;
+
+/// Lists the frames of every input. Returns `false` if any input is damaged.
+pub fn run(opts: &Options) -> bool {
+    let files = if opts.files.is_empty() {
+        vec![STDIN_MARK.to_string()]
+    } else {
+        opts.files.clone()
+    };
+
+    let mut ok = true;
+    let mut json = Vec::new();
+    for file in &files {
+        let result = inspect(file);
+        ok &= result
+            .as_ref()
+            .is_ok_and(|frames| frames.iter().all(is_intact));
+        if opts.json {
+            json.push(file_json(file, &result));
+        } else {
+            match result {
+                Ok(frames) => print_frames(file, &frames),
+                Err(err) => eprintln!("lzzzz: {}: {}", file, err),
+            }
+        }
+    }
+    if opts.json {
+        println!("[{}]", json.join(","));
+    }
+    ok
+}
+
+/// Inspects the frames of a file without loading it into memory.
+fn inspect(file: &str) -> io::Result<Vec<FrameSummary>> {
+    if is_stdin(file) {
+        lz4f::inspect_reader(io::stdin().lock())
+    } else {
+        lz4f::inspect_reader(BufReader::with_capacity(BUF_SIZE, File::open(file)?))
+    }
+}
+
+fn is_intact(frame: &FrameSummary) -> bool {
+    frame.error().is_none()
+        && frame.block_checksum() != ChecksumStatus::Invalid
+        && frame.content_checksum() != ChecksumStatus::Invalid
+}
+
+fn print_frames(file: &str, frames: &[FrameSummary]) {
+    let compressed: usize = frames.iter().map(FrameSummary::len).sum();
+    let uncompressed = frames
+        .iter()
+        .map(FrameSummary::uncompressed_size)
+        .sum::<Option<usize>>();
+    println!(
+        "{}: {} frame(s), {} bytes -> {}",
+        file,
+        frames.len(),
+        compressed,
+        size_and_ratio(compressed, uncompressed)
+    );
+
+    for (i, frame) in frames.iter().enumerate() {
+        let info = frame.frame_info();
+        if frame.frame_type() == FrameType::SkippableFrame {
+            println!(
+                "  frame {} at {}: skippable frame (magic {:#010X}), {} bytes",
+                i + 1,
+                frame.offset(),
+                frame.magic(),
+                frame.len()
+            );
+        } else if frame.magic() == LEGACY_MAGIC {
+            // lz4f::inspect does not support legacy frames.
+            println!(
+                "  frame {} at {}: legacy frame, not supported, {} bytes",
+                i + 1,
+                frame.offset(),
+                frame.len()
+            );
+        } else if frame.magic() != LZ4F_MAGIC {
+            println!(
+                "  frame {} at {}: unknown data, {} bytes",
+                i + 1,
+                frame.offset(),
+                frame.len()
+            );
+        } else {
+            println!(
+                "  frame {} at {}: LZ4 frame, {} bytes -> {}",
+                i + 1,
+                frame.offset(),
+                frame.len(),
+                size_and_ratio(frame.len(), frame.uncompressed_size())
+            );
+            println!(
+                "    blocks: {}, block size: {}, block mode: {}",
+                frame.block_count(),
+                block_size_name(info.block_size()),
+                block_mode_name(info.block_mode())
+            );
+            println!(
+                "    content size: {}, dict ID: {}",
+                match info.content_size() {
+                    0 => "-".to_string(),
+                    size => size.to_string(),
+                },
+                info.dict_id()
+            );
+            println!(
+                "    block checksum: {}, content checksum: {}",
+                checksum_name(frame.block_checksum()),
+                checksum_name(frame.content_checksum())
+            );
+        }
+        if let Some(err) = frame.error() {
+            println!("    error: {}", err);
+        }
+    }
+}
+
+fn size_and_ratio(compressed: usize, uncompressed: Option<usize>) -> String {
+    match uncompressed {
+        Some(0) => "0 bytes".to_string(),
+        Some(len) => format!(
+            "{} bytes ({:.2}%)",
+            len,
+            compressed as f64 * 100.0 / len as f64
+        ),
+        None => "unknown size".to_string(),
+    }
+}
+
+fn block_size_name(block_size: BlockSize) -> &'static str {
+    match block_size {
+        BlockSize::Max64KB => "64KB",
+        BlockSize::Max256KB => "256KB",
+        BlockSize::Max1MB => "1MB",
+        BlockSize::Max4MB => "4MB",
+        _ => "default",
+    }
+}
+
+fn block_mode_name(block_mode: BlockMode) -> &'static str {
+    match block_mode {
+        BlockMode::Linked => "linked",
+        BlockMode::Independent => "independent",
+    }
+}
+
+fn checksum_name(status: ChecksumStatus) -> &'static str {
+    match status {
+        ChecksumStatus::Disabled => "disabled",
+        ChecksumStatus::Valid => "valid",
+        ChecksumStatus::Invalid => "invalid",
+        ChecksumStatus::Unverified => "unverified",
+    }
+}
+
+fn file_json(file: &str, result: &io::Result<Vec<FrameSummary>>) -> String {
+    match result {
+        Ok(frames) => format!(
+            r#"{{"file":{},"frames":[{}]}}"#,
+            json_string(file),
+            frames.iter().map(frame_json).collect::<Vec<_>>().join(",")
+        ),
+        Err(err) => format!(
+            r#"{{"file":{},"error":{}}}"#,
+            json_string(file),
+            json_string(&err.to_string())
+        ),
+    }
+}
+
+fn frame_json(frame: &FrameSummary) -> String {
+    let info = frame.frame_info();
+    let mut json = format!(
+        r#"{{"offset":{},"length":{},"magic":{},"#,
+        frame.offset(),
+        frame.len(),
+        frame.magic()
+    );
+    if frame.frame_type() == FrameType::SkippableFrame {
+        json.push_str(r#""type":"skippable","#);
+    } else if frame.magic() == LEGACY_MAGIC {
+        json.push_str(r#""type":"legacy","#);
+    } else if frame.magic() != LZ4F_MAGIC {
+        json.push_str(r#""type":"unknown","#);
+    } else {
+        let _ = write!(
+            json,
+            concat!(
+                r#""type":"frame","block_size":"{}","block_mode":"{}","#,
+                r#""content_size":{},"dict_id":{},"block_count":{},"uncompressed_size":{},"#,
+                r#""block_checksum":"{}","content_checksum":"{}","#
+            ),
+            block_size_name(info.block_size()),
+            block_mode_name(info.block_mode()),
+            json_number(Some(info.content_size()).filter(|size| *size > 0)),
+            info.dict_id(),
+            frame.block_count(),
+            json_number(frame.uncompressed_size()),
+            checksum_name(frame.block_checksum()),
+            checksum_name(frame.content_checksum())
+        );
+    }
+    let _ = write!(
+        json,
+        r#""error":{}}}"#,
+        frame
+            .error()
+            .map_or_else(|| "null".to_string(), |err| json_string(&err.to_string()))
+    );
+    json
+}
+
+fn json_number(n: Option<usize>) -> String {
+    n.map_or_else(|| "null".to_string(), |n| n.to_string())
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
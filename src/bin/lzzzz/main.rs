@@ -0,0 +1,352 @@
+//! A command-line tool compatible with the core options of the reference `lz4` CLI.
+
+mod args;
+mod list;
+
+use args::{Command, Mode, Options};
+use lzzzz::{lz4, lz4_hc, lz4f};
+use std::{
+    env, fs,
+    io::{self, prelude::*, BufReader, BufWriter, IsTerminal},
+    path::Path,
+    process,
+};
+
+const LZ4_EXTENSION: &str = ".lz4";
+const STDIN_MARK: &str = "stdin";
+const STDOUT_MARK: &str = "stdout";
+const LEGACY_MAGIC: u32 = 0x184C_2102;
+const LEGACY_BLOCK_SIZE: usize = 8 << 20;
+const BUF_SIZE: usize = 64 * 1024;
+
+fn main() {
+    let opts = match args::parse(env::args().skip(1)) {
+        Ok(Command::Run(opts)) => opts,
+        Ok(Command::Help) => {
+            println!("{}", args::USAGE);
+            return;
+        }
+        Ok(Command::Version) => {
+            println!(
+                "lzzzz {} (liblz4 {})",
+                env!("CARGO_PKG_VERSION"),
+                lzzzz::version_string()
+            );
+            return;
+        }
+        Err(err) => {
+            eprintln!("lzzzz: {}\n\n{}", err, args::USAGE);
+            process::exit(1);
+        }
+    };
+
+    if opts.mode == Mode::List {
+        if !list::run(&opts) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut failed = false;
+    for (input, output) in jobs(&opts) {
+        if let Err(err) = process_file(&opts, &input, output.as_deref()) {
+            if opts.verbosity >= 0 {
+                eprintln!("lzzzz: {}: {}", input, err);
+            }
+            failed = true;
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Pairs each input with its output. `None` means the output name is derived from the input.
+fn jobs(opts: &Options) -> Vec<(String, Option<String>)> {
+    let stdout = || opts.stdout.then(|| STDOUT_MARK.to_string());
+    match (&opts.files[..], opts.multiple) {
+        ([], _) => vec![(STDIN_MARK.to_string(), Some(STDOUT_MARK.to_string()))],
+        (files, true) => files.iter().map(|file| (file.clone(), stdout())).collect(),
+        ([input], false) => {
+            let output = if is_stdin(input) {
+                Some(STDOUT_MARK.to_string())
+            } else {
+                stdout()
+            };
+            vec![(input.clone(), output)]
+        }
+        ([input, output, ..], false) => {
+            let output = if opts.stdout { STDOUT_MARK } else { output };
+            vec![(input.clone(), Some(output.to_string()))]
+        }
+    }
+}
+
+fn is_stdin(name: &str) -> bool {
+    name == "-" || name == STDIN_MARK
+}
+
+fn is_stdout(name: &str) -> bool {
+    name == "-" || name == STDOUT_MARK
+}
+
+fn mode(opts: &Options, input: &str) -> Mode {
+    match opts.mode {
+        Mode::Auto if input.ends_with(LZ4_EXTENSION) => Mode::Decompress,
+        Mode::Auto => Mode::Compress,
+        mode => mode,
+    }
+}
+
+fn output_name(mode: Mode, input: &str) -> io::Result<String> {
+    match mode {
+        Mode::Decompress => input
+            .strip_suffix(LZ4_EXTENSION)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| invalid_input("unknown suffix; use -c or specify the output")),
+        _ => Ok(format!("{}{}", input, LZ4_EXTENSION)),
+    }
+}
+
+fn process_file(opts: &Options, input: &str, output: Option<&str>) -> io::Result<()> {
+    let mode = mode(opts, input);
+    if mode == Mode::Compress && opts.content_size && !opts.legacy && is_stdin(input) {
+        return Err(invalid_input(
+            "the content size of the standard input is unknown; use --no-content-size",
+        ));
+    }
+    let (reader, content_size): (Box<dyn Read>, _) = if is_stdin(input) {
+        (Box::new(io::stdin().lock()), None)
+    } else {
+        let file = fs::File::open(input)?;
+        let len = file.metadata()?.len();
+        (Box::new(file), Some(len).filter(|len| *len > 0))
+    };
+    let mut reader = Counter::new(reader);
+
+    if mode == Mode::Test {
+        decompress(&mut reader, &mut io::sink())?;
+        return report(opts, input, mode, reader.count, 0);
+    }
+
+    let output = match output {
+        Some(output) => output.to_string(),
+        None => output_name(mode, input)?,
+    };
+    let written = if is_stdout(&output) {
+        let stdout = io::stdout();
+        if mode == Mode::Compress && stdout.is_terminal() && !opts.force {
+            return Err(invalid_input(
+                "refusing to write compressed data to the terminal; use -f to force",
+            ));
+        }
+        let mut writer = Counter::new(BufWriter::with_capacity(BUF_SIZE, stdout.lock()));
+        run(opts, mode, &mut reader, &mut writer, content_size)?;
+        writer.flush()?;
+        writer.count
+    } else {
+        if Path::new(&output).exists() && !opts.force {
+            return Err(invalid_input(&format!(
+                "{} already exists; use -f to overwrite",
+                output
+            )));
+        }
+        let file = fs::File::create(&output)?;
+        let mut writer = Counter::new(BufWriter::with_capacity(BUF_SIZE, file));
+        let result =
+            run(opts, mode, &mut reader, &mut writer, content_size).and_then(|_| writer.flush());
+        if let Err(err) = result {
+            drop(writer);
+            let _ = fs::remove_file(&output);
+            return Err(err);
+        }
+        writer.count
+    };
+    let read = reader.count;
+    // Close the input first, as an open file cannot be removed on every platform.
+    drop(reader);
+    if opts.remove && !is_stdin(input) && !is_stdout(&output) {
+        fs::remove_file(input)?;
+    }
+    report(opts, input, mode, read, written)
+}
+
+fn run<R: Read, W: Write>(
+    opts: &Options,
+    mode: Mode,
+    reader: &mut R,
+    writer: &mut W,
+    content_size: Option<u64>,
+) -> io::Result<()> {
+    if mode == Mode::Compress {
+        if opts.legacy {
+            compress_legacy(opts.level, reader, writer)
+        } else {
+            compress(&opts.prefs(content_size), reader, writer)
+        }
+    } else {
+        decompress(reader, writer).map(|_| ())
+    }
+}
+
+fn report(opts: &Options, input: &str, mode: Mode, read: u64, written: u64) -> io::Result<()> {
+    if opts.verbosity >= 2 {
+        match mode {
+            Mode::Compress => eprintln!(
+                "{}: compressed {} bytes into {} bytes ==> {:.2}%",
+                input,
+                read,
+                written,
+                if read == 0 {
+                    0.0
+                } else {
+                    written as f64 * 100.0 / read as f64
+                }
+            ),
+            Mode::Decompress => eprintln!("{}: decoded {} bytes", input, written),
+            _ => eprintln!("{}: OK", input),
+        }
+    }
+    Ok(())
+}
+
+fn compress<R: Read, W: Write>(
+    prefs: &lz4f::Preferences,
+    reader: &mut R,
+    writer: &mut W,
+) -> io::Result<()> {
+    let mut w = lz4f::WriteCompressor::new(writer, *prefs)?;
+    let result = io::copy(reader, &mut w);
+    // Finish the compressor even on a read error, which is reported first.
+    let finished = w.finish();
+    result?;
+    finished?;
+    Ok(())
+}
+
+fn compress_legacy<R: Read, W: Write>(
+    level: i32,
+    reader: &mut R,
+    writer: &mut W,
+) -> io::Result<()> {
+    writer.write_all(&LEGACY_MAGIC.to_le_bytes())?;
+    let mut src = Vec::with_capacity(LEGACY_BLOCK_SIZE);
+    let mut dst = Vec::new();
+    loop {
+        src.clear();
+        reader
+            .by_ref()
+            .take(LEGACY_BLOCK_SIZE as u64)
+            .read_to_end(&mut src)?;
+        if src.is_empty() {
+            return Ok(());
+        }
+        dst.clear();
+        if level < 3 {
+            lz4::compress_to_vec(&src, &mut dst, lz4::ACC_LEVEL_DEFAULT)?;
+        } else {
+            lz4_hc::compress_to_vec(&src, &mut dst, level)?;
+        }
+        writer.write_all(&(dst.len() as u32).to_le_bytes())?;
+        writer.write_all(&dst)?;
+    }
+}
+
+/// Decompresses a sequence of LZ4 frames, skippable frames and legacy frames.
+fn decompress<R: Read, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<u64> {
+    let mut reader = BufReader::with_capacity(BUF_SIZE, reader);
+    let mut total = 0;
+    let mut magic = read_u32(&mut reader)?;
+    while let Some(word) = magic {
+        if word == LEGACY_MAGIC {
+            let (len, next) = decompress_legacy(&mut reader, writer)?;
+            total += len;
+            magic = next;
+        } else {
+            let bytes = word.to_le_bytes();
+            let mut r = lz4f::BufReadDecompressor::new((&bytes[..]).chain(&mut reader))?;
+            r.set_single_frame(true);
+            total += io::copy(&mut r, writer)?;
+            magic = read_u32(&mut reader)?;
+        }
+    }
+    Ok(total)
+}
+
+/// Decompresses the blocks of a legacy frame.
+///
+/// Returns the decompressed length and the magic number of the next frame, if any.
+fn decompress_legacy<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+) -> io::Result<(u64, Option<u32>)> {
+    let max_len = lz4::max_compressed_size(LEGACY_BLOCK_SIZE);
+    let mut src = Vec::new();
+    let mut dst = vec![0; LEGACY_BLOCK_SIZE];
+    let mut total = 0;
+    loop {
+        let len = match read_u32(reader)? {
+            None => return Ok((total, None)),
+            Some(len) if len as usize > max_len => return Ok((total, Some(len))),
+            Some(len) => len as usize,
+        };
+        src.resize(len, 0);
+        reader.read_exact(&mut src)?;
+        let len = lz4::decompress(&src, &mut dst)?;
+        writer.write_all(&dst[..len])?;
+        total += len as u64;
+    }
+}
+
+/// Reads a little-endian `u32`, or returns `None` at the end of the stream.
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<Option<u32>> {
+    let mut buf = [0; 4];
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) if len == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => len += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Some(u32::from_le_bytes(buf)))
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+/// Counts the bytes passing through a reader or a writer.
+struct Counter<T> {
+    inner: T,
+    count: u64,
+}
+
+impl<T> Counter<T> {
+    fn new(inner: T) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.count += len as u64;
+        Ok(len)
+    }
+}
+
+impl<W: Write> Write for Counter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.count += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -1,7 +1,7 @@
 #![allow(unsafe_code)]
 
-use super::binding;
-use std::ffi::CStr;
+use super::{binding, Error, ErrorKind, Result};
+use std::{ffi::CStr, os::raw::c_void, ptr::NonNull};
 
 /// Returns the version number of liblz4.
 ///
@@ -28,3 +28,42 @@ pub fn version_string() -> &'static str {
             .unwrap()
     }
 }
+
+/// Computes the 32-bit xxHash of `data` with seed 0, as used by LZ4F checksums.
+pub(crate) fn xxh32(data: &[u8]) -> u32 {
+    unsafe { binding::XXH32(data.as_ptr() as *const c_void, data.len(), 0) as u32 }
+}
+
+/// Incremental 32-bit xxHash with seed 0.
+pub(crate) struct Xxh32(NonNull<binding::XXH32State>);
+
+unsafe impl Send for Xxh32 {}
+
+impl Xxh32 {
+    pub fn new() -> Result<Self> {
+        unsafe {
+            let state = NonNull::new(binding::XXH32_createState())
+                .ok_or_else(|| Error::new(ErrorKind::InitializationFailed))?;
+            binding::XXH32_reset(state.as_ptr(), 0);
+            Ok(Self(state))
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        unsafe {
+            binding::XXH32_update(self.0.as_ptr(), data.as_ptr() as *const c_void, data.len());
+        }
+    }
+
+    pub fn digest(&self) -> u32 {
+        unsafe { binding::XXH32_digest(self.0.as_ptr()) as u32 }
+    }
+}
+
+impl Drop for Xxh32 {
+    fn drop(&mut self) {
+        unsafe {
+            binding::XXH32_freeState(self.0.as_ptr());
+        }
+    }
+}
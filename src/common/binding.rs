@@ -1,7 +1,18 @@
-use std::os::raw::{c_char, c_int};
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+#[repr(C)]
+pub struct XXH32State {
+    _private: [u8; 0],
+}
 
 #[link(name = "lz4")]
 extern "C" {
     pub fn LZ4_versionNumber() -> c_int;
     pub fn LZ4_versionString() -> *const c_char;
+    pub fn XXH32(input: *const c_void, length: usize, seed: c_uint) -> c_uint;
+    pub fn XXH32_createState() -> *mut XXH32State;
+    pub fn XXH32_freeState(state: *mut XXH32State) -> c_int;
+    pub fn XXH32_reset(state: *mut XXH32State, seed: c_uint) -> c_int;
+    pub fn XXH32_update(state: *mut XXH32State, input: *const c_void, length: usize) -> c_int;
+    pub fn XXH32_digest(state: *const XXH32State) -> c_uint;
 }
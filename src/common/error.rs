@@ -16,6 +16,18 @@ pub enum ErrorKind {
     CompressedDataIncomplete,
     /// Dictionary data was not consistent during the streaming decompression.
     DictionaryChangedDuringDecompression,
+    /// Destination buffer was not consistent during the stable-destination decompression.
+    DestinationChangedDuringDecompression,
+    /// The decompressed data exceeded the configured limits.
+    DecompressionLimitExceeded,
+    /// The frame required a dictionary ID which was not registered.
+    DictionaryNotFound,
+    /// The dictionary file was malformed or its checksum did not match.
+    DictionaryInvalid,
+    /// The frame was compressed with a different dictionary.
+    DictionaryMismatch,
+    /// The preset string had an unknown item or an invalid value.
+    PresetInvalid,
 }
 
 impl fmt::Display for ErrorKind {
@@ -43,7 +55,7 @@ impl Error {
 
 impl convert::From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        Self::new(io::ErrorKind::Other, err)
+        Self::other(err)
     }
 }
 
@@ -1,9 +1,17 @@
 mod api;
 mod binding;
 mod error;
+mod profile;
+mod stats;
+mod trainer;
 
 pub use api::{version_number, version_string};
+pub(crate) use api::{xxh32, Xxh32};
 pub use error::{Error, ErrorKind, Result};
+pub use profile::{compress_block, compress_block_to_vec, Profile};
+pub use stats::Stats;
+pub(crate) use stats::StatsCallback;
+pub use trainer::{DictionaryTrainer, TrainedDictionary};
 
 pub(crate) const DEFAULT_BUF_SIZE: usize = 8 * 1024;
 pub(crate) const DICTIONARY_SIZE: usize = 64 * 1024;
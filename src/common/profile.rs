@@ -0,0 +1,181 @@
+use crate::{lz4, lz4_hc, Result};
+
+/// Acceleration factor of `Profile::Fastest`, the largest one which liblz4 distinguishes.
+const ACC_LEVEL_FASTEST: i32 = 65537;
+
+/// Compression profile shared by the `lz4`, `lz4_hc` and `lz4f` modules.
+///
+/// The `lz4` module takes an acceleration factor, while `lz4_hc` and `lz4f`
+/// take compression levels on different scales.
+/// `Profile` describes the trade-off once and maps it onto each of them.
+///
+/// | Profile              | Backend  | Parameter                                      |
+/// |----------------------|----------|------------------------------------------------|
+/// | `Fastest`            | `lz4`    | Acceleration 65537                             |
+/// | `Fast(acc)`          | `lz4`    | Acceleration `acc`                             |
+/// | `Balanced`           | `lz4`    | [`lz4::ACC_LEVEL_DEFAULT`]                     |
+/// | `High(level)`        | `lz4_hc` | `level`, clamped to `CLEVEL_MIN..=CLEVEL_MAX`  |
+/// | `Max`                | `lz4_hc` | [`lz4_hc::CLEVEL_MAX`]                         |
+/// | `FavorDecompression` | `lz4_hc` | [`lz4_hc::CLEVEL_OPT_MIN`], favoring decompression speed |
+///
+/// [`lz4::ACC_LEVEL_DEFAULT`]: lz4/constant.ACC_LEVEL_DEFAULT.html
+/// [`lz4_hc::CLEVEL_MAX`]: lz4_hc/constant.CLEVEL_MAX.html
+/// [`lz4_hc::CLEVEL_OPT_MIN`]: lz4_hc/constant.CLEVEL_OPT_MIN.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, lz4f, Profile};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+///
+/// let mut buf = Vec::new();
+/// lzzzz::compress_block_to_vec(data, &mut buf, Profile::Max)?;
+///
+/// let mut decomp = vec![0; data.len()];
+/// lz4::decompress(&buf, &mut decomp)?;
+/// assert_eq!(&decomp[..], &data[..]);
+///
+/// let prefs = lz4f::PreferencesBuilder::new()
+///     .profile(Profile::Max)
+///     .build();
+/// assert_eq!(prefs.compression_level(), lz4f::CLEVEL_MAX);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Profile {
+    /// The fastest compression with the lowest ratio.
+    Fastest,
+    /// LZ4 compression with the acceleration factor.
+    ///
+    /// Factors lower than 1 are interpreted as 1.
+    Fast(i32),
+    /// LZ4 compression with the default acceleration factor.
+    #[default]
+    Balanced,
+    /// LZ4_HC compression with the compression level.
+    High(i32),
+    /// The highest compression ratio.
+    Max,
+    /// LZ4_HC compression producing data which decompresses faster.
+    FavorDecompression,
+}
+
+impl Profile {
+    /// Returns `true` if the profile uses LZ4_HC.
+    pub const fn is_high_compression(self) -> bool {
+        self.hc_level().is_some()
+    }
+
+    /// Returns the acceleration factor for the `lz4` module, or `None` for the LZ4_HC profiles.
+    pub const fn acceleration(self) -> Option<i32> {
+        match self {
+            Self::Fastest => Some(ACC_LEVEL_FASTEST),
+            Self::Fast(acc) if acc > lz4::ACC_LEVEL_DEFAULT => Some(acc),
+            Self::Fast(_) | Self::Balanced => Some(lz4::ACC_LEVEL_DEFAULT),
+            _ => None,
+        }
+    }
+
+    /// Returns the compression level for the `lz4_hc` module, or `None` for the LZ4 profiles.
+    pub const fn hc_level(self) -> Option<i32> {
+        match self {
+            Self::High(level) if level < lz4_hc::CLEVEL_MIN => Some(lz4_hc::CLEVEL_MIN),
+            Self::High(level) if level > lz4_hc::CLEVEL_MAX => Some(lz4_hc::CLEVEL_MAX),
+            Self::High(level) => Some(level),
+            Self::Max => Some(lz4_hc::CLEVEL_MAX),
+            Self::FavorDecompression => Some(lz4_hc::CLEVEL_OPT_MIN),
+            _ => None,
+        }
+    }
+
+    /// Returns the compression level for the `lz4f` module.
+    ///
+    /// LZ4F interprets a negative level `n` as the acceleration factor `1 - n`.
+    pub const fn lz4f_level(self) -> i32 {
+        match (self.hc_level(), self.acceleration()) {
+            (Some(level), _) => level,
+            (None, Some(acc)) => 1 - acc,
+            (None, None) => 0,
+        }
+    }
+
+    /// Returns `true` if the profile favors the decompression speed.
+    pub const fn favor_dec_speed(self) -> bool {
+        matches!(self, Self::FavorDecompression)
+    }
+}
+
+/// Performs block compression with `lz4` or `lz4_hc`, depending on `profile`.
+///
+/// The compressed data can be decompressed with [`lz4::decompress`].
+/// If `dst.len()` is smaller than `lz4::max_compressed_size(src.len())`,
+/// this function may fail.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// [`lz4::decompress`]: lz4/fn.decompress.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, Profile};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut buf = [0u8; 256];
+///
+/// let len = lzzzz::compress_block(data, &mut buf, Profile::High(9))?;
+/// let compressed = &buf[..len];
+///
+/// # let mut buf = [0u8; 256];
+/// # let len = lz4::decompress(compressed, &mut buf[..data.len()])?;
+/// # assert_eq!(&buf[..len], &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compress_block(src: &[u8], dst: &mut [u8], profile: Profile) -> Result<usize> {
+    match profile.hc_level() {
+        Some(level) if profile.favor_dec_speed() => {
+            lz4_hc::compress_favor_dec_speed(src, dst, level)
+        }
+        Some(level) => lz4_hc::compress(src, dst, level),
+        None => lz4::compress(src, dst, acceleration(profile)),
+    }
+}
+
+/// Appends compressed data to `Vec<u8>`, using `lz4` or `lz4_hc` depending on `profile`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, Profile};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut buf = Vec::new();
+///
+/// lzzzz::compress_block_to_vec(data, &mut buf, Profile::Fast(10))?;
+///
+/// # let compressed = &buf;
+/// # let mut buf = [0u8; 256];
+/// # let len = lz4::decompress(compressed, &mut buf[..data.len()])?;
+/// # assert_eq!(&buf[..len], &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compress_block_to_vec(src: &[u8], dst: &mut Vec<u8>, profile: Profile) -> Result<usize> {
+    match profile.hc_level() {
+        Some(level) if profile.favor_dec_speed() => {
+            let orig_len = dst.len();
+            dst.resize(orig_len + lz4::max_compressed_size(src.len()), 0);
+            let result = lz4_hc::compress_favor_dec_speed(src, &mut dst[orig_len..], level);
+            dst.truncate(orig_len + result.as_ref().map_or(0, |len| *len));
+            result
+        }
+        Some(level) => lz4_hc::compress_to_vec(src, dst, level),
+        None => lz4::compress_to_vec(src, dst, acceleration(profile)),
+    }
+}
+
+fn acceleration(profile: Profile) -> i32 {
+    profile.acceleration().unwrap_or(lz4::ACC_LEVEL_DEFAULT)
+}
@@ -0,0 +1,45 @@
+/// Byte counts of a compressed stream or a part of it.
+///
+/// Passed to the frame and block callbacks of the streaming APIs.
+///
+/// The callbacks must be `Send + 'static`, because the `lz4f` compressors have
+/// no lifetime parameter which a borrowing callback could be tied to, and all
+/// the adapters take the same bound. Use a channel or an `Arc` to collect the
+/// statistics from a callback.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Stats {
+    uncompressed_len: u64,
+    compressed_len: u64,
+}
+
+impl Stats {
+    pub(crate) const fn new(uncompressed_len: u64, compressed_len: u64) -> Self {
+        Self {
+            uncompressed_len,
+            compressed_len,
+        }
+    }
+
+    /// Returns the number of uncompressed bytes.
+    pub const fn uncompressed_len(&self) -> u64 {
+        self.uncompressed_len
+    }
+
+    /// Returns the number of compressed bytes.
+    pub const fn compressed_len(&self) -> u64 {
+        self.compressed_len
+    }
+
+    /// Returns the compression ratio, the uncompressed size divided by the compressed size.
+    ///
+    /// Returns `0.0` if there is no compressed data.
+    pub fn ratio(&self) -> f64 {
+        if self.compressed_len == 0 {
+            0.0
+        } else {
+            self.uncompressed_len as f64 / self.compressed_len as f64
+        }
+    }
+}
+
+pub(crate) type StatsCallback = Box<dyn FnMut(&Stats) + Send>;
@@ -0,0 +1,289 @@
+use super::{xxh32, DICTIONARY_SIZE};
+use crate::lz4f;
+use std::cmp;
+
+const HASH_LOG: u32 = 20;
+const NO_DMER: u32 = u32::MAX;
+
+/// A dictionary built by [`DictionaryTrainer`].
+///
+/// The data can be passed to [`lz4::Compressor::with_dict`], [`lz4_hc::Compressor::with_dict`]
+/// and the decompressors as it is. For LZ4F, [`lz4f_dictionary`] builds
+/// a [`lz4f::Dictionary`] which writes [`dict_id`] to the frame header.
+///
+/// [`DictionaryTrainer`]: struct.DictionaryTrainer.html
+/// [`lz4::Compressor::with_dict`]: lz4/struct.Compressor.html#method.with_dict
+/// [`lz4_hc::Compressor::with_dict`]: lz4_hc/struct.Compressor.html#method.with_dict
+/// [`lz4f_dictionary`]: #method.lz4f_dictionary
+/// [`lz4f::Dictionary`]: lz4f/struct.Dictionary.html
+/// [`dict_id`]: #method.dict_id
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrainedDictionary {
+    data: Vec<u8>,
+    dict_id: u32,
+}
+
+impl TrainedDictionary {
+    /// Returns the dictionary data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the dictionary ID. It is never `0`.
+    pub const fn dict_id(&self) -> u32 {
+        self.dict_id
+    }
+
+    /// Builds a [`lz4f::Dictionary`] with the dictionary ID.
+    ///
+    /// [`lz4f::Dictionary`]: lz4f/struct.Dictionary.html
+    pub fn lz4f_dictionary(&self) -> lz4f::Result<lz4f::Dictionary> {
+        lz4f::Dictionary::with_id(&self.data, self.dict_id)
+    }
+
+    /// Returns the dictionary data, consuming the dictionary.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl AsRef<[u8]> for TrainedDictionary {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Builds a dictionary from sample data.
+///
+/// The trainer selects the segments of the samples which contain the most frequent
+/// byte sequences, in the manner of the COVER algorithm of Zstandard.
+/// It works best with many small samples which share their structure,
+/// like records of the same format.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, DictionaryTrainer};
+///
+/// let samples = (0..1000)
+///     .map(|i| format!(r#"{{"id":{},"name":"user{}","active":true}}"#, i, i * 7))
+///     .collect::<Vec<_>>();
+///
+/// let dict = DictionaryTrainer::new().max_size(4096).train(&samples);
+/// assert!(dict.as_bytes().len() <= 4096);
+///
+/// let mut comp = lz4::Compressor::with_dict(dict.as_bytes())?;
+/// let mut buf = Vec::new();
+/// comp.next_to_vec(samples[0].as_bytes(), &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DictionaryTrainer {
+    max_size: usize,
+    segment_size: usize,
+    dmer_size: usize,
+    dict_id: u32,
+}
+
+impl Default for DictionaryTrainer {
+    fn default() -> Self {
+        Self {
+            max_size: DICTIONARY_SIZE,
+            segment_size: 128,
+            dmer_size: 8,
+            dict_id: 0,
+        }
+    }
+}
+
+impl DictionaryTrainer {
+    /// Creates a new `DictionaryTrainer` with the default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of the dictionary.
+    ///
+    /// LZ4 only uses the last 64 KiB of a dictionary, so larger values are clamped to 64 KiB.
+    pub fn max_size(&mut self, max_size: usize) -> &mut Self {
+        self.max_size = cmp::min(max_size, DICTIONARY_SIZE);
+        self
+    }
+
+    /// Sets the size of the segments copied from the samples. The default is `128`.
+    pub fn segment_size(&mut self, segment_size: usize) -> &mut Self {
+        self.segment_size = cmp::max(segment_size, 1);
+        self
+    }
+
+    /// Sets the length of the byte sequences counted by the trainer,
+    /// between `4` and `8`. The default is `8`.
+    pub fn dmer_size(&mut self, dmer_size: usize) -> &mut Self {
+        self.dmer_size = dmer_size.clamp(4, 8);
+        self
+    }
+
+    /// Sets the dictionary ID.
+    ///
+    /// If the ID is `0` (default), it is derived from the checksum of the dictionary data.
+    pub fn dict_id(&mut self, dict_id: u32) -> &mut Self {
+        self.dict_id = dict_id;
+        self
+    }
+
+    /// Builds a dictionary from `samples`.
+    pub fn train<S: AsRef<[u8]>>(&self, samples: &[S]) -> TrainedDictionary {
+        let data = Cover::new(samples, self.dmer_size).select(self.max_size, self.segment_size);
+        let dict_id = match (self.dict_id, xxh32(&data)) {
+            (0, 0) => 1,
+            (0, hash) => hash,
+            (id, _) => id,
+        };
+        TrainedDictionary { data, dict_id }
+    }
+}
+
+struct Cover {
+    corpus: Vec<u8>,
+    dmers: Vec<u32>,
+    freqs: Vec<u32>,
+    active: Vec<u32>,
+    dmer_size: usize,
+}
+
+impl Cover {
+    fn new<S: AsRef<[u8]>>(samples: &[S], dmer_size: usize) -> Self {
+        let corpus = samples
+            .iter()
+            .flat_map(|sample| sample.as_ref())
+            .copied()
+            .collect::<Vec<_>>();
+
+        // Byte sequences crossing the sample boundaries are not counted.
+        let mut dmers = vec![NO_DMER; corpus.len()];
+        let mut freqs = vec![0; 1 << HASH_LOG];
+        let mut offset = 0;
+        for sample in samples {
+            let sample = sample.as_ref();
+            for (pos, dmer) in sample.windows(dmer_size).enumerate() {
+                let hash = hash(dmer);
+                dmers[offset + pos] = hash;
+                freqs[hash as usize] += 1;
+            }
+            offset += sample.len();
+        }
+
+        Self {
+            corpus,
+            dmers,
+            freqs,
+            active: vec![0; 1 << HASH_LOG],
+            dmer_size,
+        }
+    }
+
+    fn select(&mut self, max_size: usize, segment_size: usize) -> Vec<u8> {
+        let segment_size = cmp::max(segment_size, self.dmer_size);
+        let mut epochs = cmp::max(1, max_size / segment_size / 4);
+        if self.corpus.len() / epochs < segment_size * 10 {
+            epochs = cmp::max(1, self.corpus.len() / (segment_size * 10));
+        }
+        let epoch_size = self.corpus.len() / epochs;
+
+        // The segments are placed from the end, so the best ones are
+        // the closest to the data and the cheapest to refer to.
+        let mut dict = vec![0; max_size];
+        let mut tail = max_size;
+        let mut epoch = 0;
+        let mut zero_run = 0;
+        while tail > 0 && zero_run < epochs {
+            let begin = epoch * epoch_size;
+            let end = if epoch + 1 == epochs {
+                self.corpus.len()
+            } else {
+                begin + epoch_size
+            };
+            epoch = (epoch + 1) % epochs;
+
+            let (begin, end) = match self.select_segment(begin, end, segment_size) {
+                Some(segment) => segment,
+                None => {
+                    zero_run += 1;
+                    continue;
+                }
+            };
+            zero_run = 0;
+
+            for pos in begin..=end - self.dmer_size {
+                if self.dmers[pos] != NO_DMER {
+                    self.freqs[self.dmers[pos] as usize] = 0;
+                }
+            }
+            let len = cmp::min(end - begin, tail);
+            tail -= len;
+            dict[tail..tail + len].copy_from_slice(&self.corpus[begin..begin + len]);
+        }
+        dict.drain(..tail);
+        dict
+    }
+
+    /// Returns the byte range of the segment with the highest score in `begin..end`.
+    fn select_segment(
+        &mut self,
+        begin: usize,
+        end: usize,
+        segment_size: usize,
+    ) -> Option<(usize, usize)> {
+        let window_len = segment_size - self.dmer_size + 1;
+        let mut score = 0u64;
+        let mut best = (0, begin, begin);
+        let mut window_begin = begin;
+        for pos in begin..end {
+            let dmer = self.dmers[pos];
+            if dmer != NO_DMER {
+                if self.active[dmer as usize] == 0 {
+                    score += u64::from(self.freqs[dmer as usize]);
+                }
+                self.active[dmer as usize] += 1;
+            }
+            if pos + 1 - window_begin > window_len {
+                self.remove(window_begin, &mut score);
+                window_begin += 1;
+            }
+            if score > best.0 {
+                best = (score, window_begin, pos);
+            }
+        }
+        for pos in window_begin..end {
+            self.remove(pos, &mut score);
+        }
+
+        let (score, first, last) = best;
+        if score == 0 {
+            return None;
+        }
+        let counted = |pos: &usize| {
+            let dmer = self.dmers[*pos];
+            dmer != NO_DMER && self.freqs[dmer as usize] > 0
+        };
+        let first = (first..=last).find(counted)?;
+        let last = (first..=last).rev().find(counted)?;
+        Some((first, last + self.dmer_size))
+    }
+
+    fn remove(&mut self, pos: usize, score: &mut u64) {
+        let dmer = self.dmers[pos];
+        if dmer != NO_DMER {
+            self.active[dmer as usize] -= 1;
+            if self.active[dmer as usize] == 0 {
+                *score -= u64::from(self.freqs[dmer as usize]);
+            }
+        }
+    }
+}
+
+fn hash(dmer: &[u8]) -> u32 {
+    let mut buf = [0; 8];
+    buf[..dmer.len()].copy_from_slice(dmer);
+    (u64::from_le_bytes(buf).wrapping_mul(0xCF1B_BCDC_B7A5_6463) >> (64 - HASH_LOG)) as u32
+}
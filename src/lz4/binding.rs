@@ -34,11 +34,13 @@ extern "C" {
         dst_capacity: c_int,
         acceleration: c_int,
     ) -> c_int;
-    pub fn LZ4_compress_destSize(
+    pub fn LZ4_compress_destSize_extState(
+        state: *mut c_void,
         src: *const c_char,
         dst: *mut c_char,
         src_size: *mut c_int,
         target_dst_size: c_int,
+        acceleration: c_int,
     ) -> c_int;
     pub fn LZ4_decompress_safe(
         src: *const c_char,
@@ -57,17 +57,21 @@ pub fn compress_fast_ext_state_fast_reset(
     }
 }
 
-pub fn compress_dest_size(
+pub fn compress_dest_size_ext_state(
+    state: &mut [u8],
     src: &[u8],
     dst: &mut [u8],
+    acceleration: i32,
 ) -> Result<(usize, usize)> {
     let mut src_size: c_int = src.len() as c_int;
     let result = unsafe {
-        binding::LZ4_compress_destSize(
+        binding::LZ4_compress_destSize_extState(
+            state.as_mut_ptr() as *mut c_void,
             src.as_ptr() as *const c_char,
             dst.as_mut_ptr() as *mut c_char,
             &mut src_size as *mut c_int,
             dst.len() as c_int,
+            acceleration as c_int,
         )
     };
     if result == 0 {
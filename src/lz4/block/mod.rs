@@ -1,5 +1,6 @@
 mod api;
 
+use super::ACC_LEVEL_DEFAULT;
 use crate::{Error, ErrorKind, Result};
 use api::ExtState;
 use std::cmp;
@@ -100,6 +101,131 @@ pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, acc: i32) -> Result<usize>
     }
 }
 
+/// Result of [`compress_or_store`].
+///
+/// [`compress_or_store`]: fn.compress_or_store.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BlockEncoding {
+    /// The destination buffer holds an LZ4 block of the given length.
+    Compressed(usize),
+    /// The destination buffer holds a copy of the source, which did not compress.
+    Stored(usize),
+}
+
+impl BlockEncoding {
+    /// Returns the number of bytes written into the destination buffer.
+    pub const fn len(&self) -> usize {
+        match self {
+            Self::Compressed(len) | Self::Stored(len) => *len,
+        }
+    }
+
+    /// Returns `true` if nothing was written.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the source was stored uncompressed.
+    pub const fn is_stored(&self) -> bool {
+        matches!(self, Self::Stored(_))
+    }
+}
+
+const SAMPLE_LEN: usize = 1024;
+const SAMPLE_COUNT: usize = 8;
+
+/// Estimates whether `src` is incompressible, e.g. random or encrypted data.
+///
+/// The estimate compresses a few samples spread over `src`,
+/// which costs a fraction of compressing the whole data.
+/// Inputs shorter than 32 KiB are never reported as incompressible,
+/// because a trial compression of them is cheap enough.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+///
+/// let text = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+/// assert!(!lz4::is_incompressible(&text));
+///
+/// let mut state = 1u32;
+/// let noise = (0..65536)
+///     .map(|_| {
+///         state ^= state << 13;
+///         state ^= state >> 17;
+///         state ^= state << 5;
+///         state as u8
+///     })
+///     .collect::<Vec<_>>();
+/// assert!(lz4::is_incompressible(&noise));
+/// ```
+pub fn is_incompressible(src: &[u8]) -> bool {
+    if src.len() < SAMPLE_LEN * SAMPLE_COUNT * 4 {
+        return false;
+    }
+    let mut buf = [0; max_compressed_size(SAMPLE_LEN)];
+    let step = (src.len() - SAMPLE_LEN) / (SAMPLE_COUNT - 1);
+    let compressed_len: usize = (0..SAMPLE_COUNT)
+        .map(|i| {
+            let sample = &src[i * step..i * step + SAMPLE_LEN];
+            compress(sample, &mut buf, ACC_LEVEL_DEFAULT).unwrap_or(buf.len())
+        })
+        .sum();
+    // Less than 3% saving is not worth the compression.
+    compressed_len * 32 >= SAMPLE_LEN * SAMPLE_COUNT * 31
+}
+
+/// Performs LZ4 block compression, or stores `src` as is if it does not compress.
+///
+/// Incompressible data detected by [`is_incompressible`] is stored without
+/// a trial compression. Otherwise, `src` is compressed and stored only if
+/// the compressed block would not be smaller.
+///
+/// LZ4 blocks have no marker for stored data, so the caller must keep the
+/// returned [`BlockEncoding`] to decode the block.
+/// `dst.len()` must be at least `src.len()`.
+///
+/// [`is_incompressible`]: fn.is_incompressible.html
+/// [`BlockEncoding`]: enum.BlockEncoding.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4::{self, BlockEncoding};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut buf = [0u8; 256];
+///
+/// let mut decomp = [0u8; 256];
+/// match lz4::compress_or_store(data, &mut buf, lz4::ACC_LEVEL_DEFAULT)? {
+///     BlockEncoding::Compressed(len) => {
+///         lz4::decompress(&buf[..len], &mut decomp[..data.len()])?;
+///     }
+///     BlockEncoding::Stored(len) => {
+///         decomp[..len].copy_from_slice(&buf[..len]);
+///     }
+/// }
+/// assert_eq!(&decomp[..data.len()], &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compress_or_store(src: &[u8], dst: &mut [u8], acc: i32) -> Result<BlockEncoding> {
+    if src.is_empty() {
+        return Ok(BlockEncoding::Compressed(0));
+    }
+    if !is_incompressible(src) {
+        let limit = cmp::min(dst.len(), src.len() - 1);
+        if let Ok(len) = compress(src, &mut dst[..limit], acc) {
+            return Ok(BlockEncoding::Compressed(len));
+        }
+    }
+    if dst.len() < src.len() {
+        return Err(Error::new(ErrorKind::CompressionFailed));
+    }
+    dst[..src.len()].copy_from_slice(src);
+    Ok(BlockEncoding::Stored(src.len()))
+}
+
 /// Compress data to fill `dst`.
 ///
 /// This function either compresses the entire `src` buffer into `dst` if it's
@@ -141,7 +267,19 @@ pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, acc: i32) -> Result<usize>
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn compress_fill(src: &[u8], dst: &mut [u8]) -> Result<(usize, usize)> {
-    api::compress_dest_size(src, dst)
+    compress_fill_with_acc(src, dst, ACC_LEVEL_DEFAULT)
+}
+
+pub(crate) fn compress_fill_with_acc(
+    src: &[u8],
+    dst: &mut [u8],
+    acc: i32,
+) -> Result<(usize, usize)> {
+    let acc = cmp::min(acc, 33_554_431);
+    ExtState::with(|state, _| {
+        // LZ4_compress_destSize_extState initializes the state and cleans it on exit.
+        api::compress_dest_size_ext_state(&mut state.borrow_mut(), src, dst, acc)
+    })
 }
 
 /// Decompresses an LZ4 block.
@@ -0,0 +1,289 @@
+use crate::{lz4, Result};
+use std::{
+    cmp,
+    collections::VecDeque,
+    fmt,
+    ops::{Bound, Range, RangeBounds},
+};
+
+const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+/// In-memory byte buffer stored as independently compressed LZ4 blocks.
+///
+/// The contents are split into blocks of a fixed size, each of which is
+/// compressed with [`compress_or_store`]. Reading or overwriting a range
+/// only decompresses and recompresses the blocks it overlaps.
+///
+/// An optional LRU cache keeps recently used blocks decompressed
+/// to speed up repeated reads of the same region.
+///
+/// [`compress_or_store`]: fn.compress_or_store.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4::CompressedVec;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+///
+/// let mut vec = CompressedVec::with_block_size(4096);
+/// vec.set_cache_capacity(2);
+/// vec.extend_from_slice(&data)?;
+/// assert_eq!(vec.len(), data.len());
+/// assert!(vec.compressed_len() < data.len());
+///
+/// vec.write_at(4, b"QUICK")?;
+/// assert_eq!(vec.get(4..19)?, b"QUICK brown fox");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct CompressedVec {
+    // A block is stored uncompressed iff its length equals the length of the block data.
+    blocks: Vec<Box<[u8]>>,
+    block_size: usize,
+    len: usize,
+    acc: i32,
+    cache: VecDeque<(usize, Vec<u8>)>,
+    cache_capacity: usize,
+}
+
+impl CompressedVec {
+    /// Creates a new empty `CompressedVec` with the default block size (64 KiB).
+    pub fn new() -> Self {
+        Self::with_block_size(DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Creates a new empty `CompressedVec` with the given block size.
+    ///
+    /// The block size must be greater than zero.
+    pub fn with_block_size(block_size: usize) -> Self {
+        Self {
+            blocks: Vec::new(),
+            block_size: cmp::max(block_size, 1),
+            len: 0,
+            acc: lz4::ACC_LEVEL_DEFAULT,
+            cache: VecDeque::new(),
+            cache_capacity: 0,
+        }
+    }
+
+    /// Sets the acceleration factor used for the subsequent writes.
+    /// The default is [`ACC_LEVEL_DEFAULT`].
+    ///
+    /// [`ACC_LEVEL_DEFAULT`]: constant.ACC_LEVEL_DEFAULT.html
+    pub fn set_acceleration(&mut self, acc: i32) {
+        self.acc = acc;
+    }
+
+    /// Sets the number of decompressed blocks to cache. The default is 0 (no cache).
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache_capacity = capacity;
+        self.cache.truncate(capacity);
+    }
+
+    /// Returns the block size.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the number of uncompressed bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the buffer contains no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the total size of the compressed blocks in bytes.
+    ///
+    /// The cache is not included.
+    pub fn compressed_len(&self) -> usize {
+        self.blocks.iter().map(|block| block.len()).sum()
+    }
+
+    /// Removes all contents.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.cache.clear();
+        self.len = 0;
+    }
+
+    /// Returns a copy of the bytes in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn get<R: RangeBounds<usize>>(&mut self, range: R) -> Result<Vec<u8>> {
+        let range = self.range(range);
+        let mut buf = vec![0; range.len()];
+        self.read_at(range.start, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Copies the bytes from `offset` into `buf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset + buf.len()` exceeds the length.
+    pub fn read_at(&mut self, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let range = self.range(offset..offset + buf.len());
+        let mut pos = range.start;
+        while pos < range.end {
+            let index = pos / self.block_size;
+            let start = pos - index * self.block_size;
+            let len = cmp::min(self.block_size - start, range.end - pos);
+            let out = &mut buf[pos - offset..][..len];
+            self.with_block(index, |block| {
+                out.copy_from_slice(&block[start..start + len]);
+            })?;
+            pos += len;
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the whole contents.
+    pub fn to_vec(&mut self) -> Result<Vec<u8>> {
+        self.get(..)
+    }
+
+    /// Appends `data` to the end.
+    ///
+    /// Only the last block is recompressed if it is not full.
+    pub fn extend_from_slice(&mut self, mut data: &[u8]) -> Result<()> {
+        let partial = self.len % self.block_size;
+        if partial > 0 && !data.is_empty() {
+            let len = cmp::min(self.block_size - partial, data.len());
+            self.modify_block(self.blocks.len() - 1, |block| {
+                block.extend_from_slice(&data[..len]);
+            })?;
+            self.len += len;
+            data = &data[len..];
+        }
+        for chunk in data.chunks(self.block_size) {
+            let block = self.compress_block(chunk)?;
+            self.blocks.push(block);
+            self.len += chunk.len();
+        }
+        Ok(())
+    }
+
+    /// Overwrites the bytes from `offset` with `data`.
+    ///
+    /// Only the blocks overlapping the range are recompressed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset + data.len()` exceeds the length.
+    pub fn write_at(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let range = self.range(offset..offset + data.len());
+        let mut pos = range.start;
+        while pos < range.end {
+            let index = pos / self.block_size;
+            let start = pos - index * self.block_size;
+            let len = cmp::min(self.block_size - start, range.end - pos);
+            let src = &data[pos - offset..][..len];
+            self.modify_block(index, |block| {
+                block[start..start + len].copy_from_slice(src);
+            })?;
+            pos += len;
+        }
+        Ok(())
+    }
+
+    fn range<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n + 1,
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len,
+        };
+        assert!(
+            start <= end && end <= self.len,
+            "range {}..{} out of bounds for length {}",
+            start,
+            end,
+            self.len
+        );
+        start..end
+    }
+
+    fn block_len(&self, index: usize) -> usize {
+        cmp::min(self.block_size, self.len - index * self.block_size)
+    }
+
+    fn compress_block(&self, src: &[u8]) -> Result<Box<[u8]>> {
+        let mut buf = vec![0; src.len()];
+        let len = lz4::compress_or_store(src, &mut buf, self.acc)?.len();
+        buf.truncate(len);
+        Ok(buf.into_boxed_slice())
+    }
+
+    fn decompress_block(&self, index: usize) -> Result<Vec<u8>> {
+        let block = &self.blocks[index];
+        let len = self.block_len(index);
+        if block.len() == len {
+            return Ok(block.to_vec());
+        }
+        let mut buf = vec![0; len];
+        lz4::decompress(block, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Takes the decompressed block out of the cache, or decompresses it.
+    fn take_block(&mut self, index: usize) -> Result<Vec<u8>> {
+        match self.cache.iter().position(|(i, _)| *i == index) {
+            Some(pos) => Ok(self.cache.remove(pos).unwrap().1),
+            None => self.decompress_block(index),
+        }
+    }
+
+    fn cache_block(&mut self, index: usize, block: Vec<u8>) {
+        if self.cache_capacity > 0 {
+            self.cache.truncate(self.cache_capacity - 1);
+            self.cache.push_front((index, block));
+        }
+    }
+
+    fn with_block<F>(&mut self, index: usize, f: F) -> Result<()>
+    where
+        F: FnOnce(&[u8]),
+    {
+        let block = self.take_block(index)?;
+        f(&block);
+        self.cache_block(index, block);
+        Ok(())
+    }
+
+    fn modify_block<F>(&mut self, index: usize, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        let mut block = self.take_block(index)?;
+        f(&mut block);
+        self.blocks[index] = self.compress_block(&block)?;
+        self.cache_block(index, block);
+        Ok(())
+    }
+}
+
+impl Default for CompressedVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CompressedVec {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("CompressedVec")
+            .field("len", &self.len)
+            .field("block_size", &self.block_size)
+            .field("compressed_len", &self.compressed_len())
+            .finish()
+    }
+}
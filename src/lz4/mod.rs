@@ -28,9 +28,13 @@
 
 mod binding;
 mod block;
+mod compressed_vec;
+mod packet;
 mod stream;
 
 pub use block::*;
+pub use compressed_vec::*;
+pub use packet::*;
 pub use stream::*;
 
 /// Predefined acceleration level (1).
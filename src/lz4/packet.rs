@@ -0,0 +1,414 @@
+use crate::{common::DICTIONARY_SIZE, lz4, Error, ErrorKind, Result};
+use std::{cmp, collections::VecDeque};
+
+/// The size of the packet header in bytes.
+///
+/// The header consists of the sequence number (`u32`), the decompressed length (`u16`),
+/// both in little-endian, and the flags (`u8`).
+pub const PACKET_HEADER_SIZE: usize = 7;
+
+/// The smallest MTU accepted by [`PacketCompressor`].
+///
+/// [`PacketCompressor`]: struct.PacketCompressor.html
+pub const PACKET_MTU_MIN: usize = 32;
+
+/// The packet references the data of the previous packets.
+const FLAG_LINKED: u8 = 0x01;
+
+const DEFAULT_WINDOW: usize = 64;
+
+/// Reliability mode of a packet stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PacketMode {
+    /// Each packet is compressed independently and can be decoded on its own.
+    ///
+    /// Suited to transports which may drop packets.
+    Independent,
+    /// Each packet references the data of the previous packets,
+    /// which improves the compression ratio of small packets.
+    ///
+    /// The packets following a lost one cannot be decoded until
+    /// [`PacketCompressor::reset`] is called. Suited to reliable transports.
+    ///
+    /// [`PacketCompressor::reset`]: struct.PacketCompressor.html#method.reset
+    Linked,
+}
+
+/// Packet compressor for MTU-bounded transports.
+///
+/// Splits a byte stream into packets, each of which fits the MTU and starts with
+/// a [`PACKET_HEADER_SIZE`]-byte header carrying the sequence number,
+/// the decompressed length and the flags.
+/// A packet carries at most 65535 bytes of uncompressed data.
+///
+/// [`PACKET_HEADER_SIZE`]: constant.PACKET_HEADER_SIZE.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4::{PacketCompressor, PacketDecompressor, PacketMode};
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+///
+/// let mut comp = PacketCompressor::new(64, PacketMode::Linked)?;
+/// let packets = comp.packets(&data)?;
+/// assert!(packets.iter().all(|packet| packet.len() <= 64));
+///
+/// let mut decomp = PacketDecompressor::new();
+/// let mut buf = Vec::new();
+/// for packet in &packets {
+///     decomp.push(packet, &mut buf, |_| ())?;
+/// }
+/// assert_eq!(buf, data);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct PacketCompressor {
+    comp: lz4::Compressor<'static>,
+    mode: PacketMode,
+    mtu: usize,
+    acc: i32,
+    seq: u32,
+}
+
+impl PacketCompressor {
+    /// Creates a new `PacketCompressor`.
+    ///
+    /// An MTU smaller than [`PACKET_MTU_MIN`] is interpreted as `PACKET_MTU_MIN`.
+    ///
+    /// [`PACKET_MTU_MIN`]: constant.PACKET_MTU_MIN.html
+    pub fn new(mtu: usize, mode: PacketMode) -> Result<Self> {
+        Ok(Self {
+            comp: lz4::Compressor::new()?,
+            mode,
+            mtu: cmp::max(mtu, PACKET_MTU_MIN),
+            acc: lz4::ACC_LEVEL_DEFAULT,
+            seq: 0,
+        })
+    }
+
+    /// Sets the acceleration factor. The default is [`ACC_LEVEL_DEFAULT`].
+    ///
+    /// [`ACC_LEVEL_DEFAULT`]: constant.ACC_LEVEL_DEFAULT.html
+    pub fn set_acceleration(&mut self, acc: i32) {
+        self.acc = acc;
+    }
+
+    /// Returns the MTU.
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Returns the reliability mode.
+    pub fn mode(&self) -> PacketMode {
+        self.mode
+    }
+
+    /// Returns the sequence number of the next packet.
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Drops the history so that the next packet can be decoded on its own.
+    ///
+    /// In `PacketMode::Linked`, this lets the receiver recover from a lost packet.
+    pub fn reset(&mut self) -> Result<()> {
+        self.comp = lz4::Compressor::new()?;
+        Ok(())
+    }
+
+    /// Compresses the beginning of `src` into a packet.
+    ///
+    /// At most `mtu` bytes are written into `dst`. If `src` is empty, no packet is produced.
+    ///
+    /// Returns a pair `(read, wrote)` giving the number of bytes read from `src`
+    /// and the number of bytes written to `dst`.
+    pub fn next(&mut self, src: &[u8], dst: &mut [u8]) -> Result<(usize, usize)> {
+        if src.is_empty() {
+            return Ok((0, 0));
+        }
+        let len = cmp::min(dst.len(), self.mtu);
+        if len < PACKET_MTU_MIN {
+            return Err(Error::new(ErrorKind::CompressionFailed));
+        }
+
+        let src = &src[..cmp::min(src.len(), u16::MAX as usize)];
+        let (header, payload) = dst[..len].split_at_mut(PACKET_HEADER_SIZE);
+        let (read, wrote, flags) = match self.mode {
+            PacketMode::Independent => {
+                let (read, wrote) = lz4::compress_fill_with_acc(src, payload, self.acc)?;
+                (read, wrote, 0)
+            }
+            PacketMode::Linked => {
+                let flags = if self.comp.total_in() > 0 {
+                    FLAG_LINKED
+                } else {
+                    0
+                };
+                let (read, wrote) = self.comp.next_fill(src, payload, self.acc)?;
+                (read, wrote, flags)
+            }
+        };
+
+        header[..4].copy_from_slice(&self.seq.to_le_bytes());
+        header[4..6].copy_from_slice(&(read as u16).to_le_bytes());
+        header[6] = flags;
+        self.seq = self.seq.wrapping_add(1);
+        Ok((read, PACKET_HEADER_SIZE + wrote))
+    }
+
+    /// Splits `src` into packets.
+    pub fn packets(&mut self, src: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut packets = Vec::new();
+        let mut buf = vec![0; self.mtu];
+        let mut src = src;
+        while !src.is_empty() {
+            let (read, wrote) = self.next(src, &mut buf)?;
+            packets.push(buf[..wrote].to_vec());
+            src = &src[read..];
+        }
+        Ok(packets)
+    }
+}
+
+/// An event reported by [`PacketDecompressor`].
+///
+/// [`PacketDecompressor`]: struct.PacketDecompressor.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PacketEvent {
+    /// The data of the packet `seq` (`len` bytes) was appended to the output.
+    Received { seq: u32, len: usize },
+    /// `count` packets starting from `seq` were lost,
+    /// or could not be decoded because the history they reference was lost.
+    Lost { seq: u32, count: u32 },
+}
+
+/// Packet decompressor for the packets produced by [`PacketCompressor`].
+///
+/// Packets may be pushed in any order. They are buffered and delivered in order
+/// of the sequence number, which starts from zero.
+/// If a packet is still missing when the buffered packets exceed the window,
+/// or when [`skip_missing`] is called, it is reported as lost.
+/// Late and duplicate packets are ignored.
+///
+/// [`PacketCompressor`]: struct.PacketCompressor.html
+/// [`skip_missing`]: struct.PacketDecompressor.html#method.skip_missing
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4::{PacketCompressor, PacketDecompressor, PacketEvent, PacketMode};
+///
+/// let data = (0..1000u32).flat_map(u32::to_le_bytes).collect::<Vec<_>>();
+///
+/// let mut comp = PacketCompressor::new(64, PacketMode::Independent)?;
+/// let mut packets = comp.packets(&data)?;
+/// packets.swap(0, 1);
+/// packets.remove(2);
+///
+/// let mut decomp = PacketDecompressor::new();
+/// let mut buf = Vec::new();
+/// let mut events = Vec::new();
+/// for packet in &packets {
+///     decomp.push(packet, &mut buf, |event| events.push(*event))?;
+/// }
+/// decomp.skip_missing(&mut buf, |event| events.push(*event))?;
+///
+/// assert!(events.contains(&PacketEvent::Lost { seq: 2, count: 1 }));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct PacketDecompressor {
+    next_seq: u32,
+    pending: VecDeque<Option<Vec<u8>>>,
+    window: usize,
+    history: Vec<u8>,
+    linkable: bool,
+}
+
+impl PacketDecompressor {
+    /// Creates a new `PacketDecompressor`.
+    pub fn new() -> Self {
+        Self {
+            next_seq: 0,
+            pending: VecDeque::new(),
+            window: DEFAULT_WINDOW,
+            history: Vec::new(),
+            linkable: false,
+        }
+    }
+
+    /// Sets the number of packets to wait for a missing one. The default is 64.
+    ///
+    /// The window must be greater than zero.
+    pub fn set_window(&mut self, window: usize) {
+        self.window = cmp::max(window, 1);
+    }
+
+    /// Returns the sequence number of the next packet to be delivered.
+    pub fn next_seq(&self) -> u32 {
+        self.next_seq
+    }
+
+    /// Returns the number of packets waiting for a missing one.
+    pub fn pending(&self) -> usize {
+        self.pending
+            .iter()
+            .filter(|packet| packet.is_some())
+            .count()
+    }
+
+    /// Pushes a packet and appends the data of the packets which can be delivered
+    /// to `dst`.
+    ///
+    /// Each delivered or lost packet is passed to `report`.
+    pub fn push<F>(&mut self, packet: &[u8], dst: &mut Vec<u8>, mut report: F) -> Result<()>
+    where
+        F: FnMut(&PacketEvent),
+    {
+        let (seq, _, _) = parse_header(packet)?;
+        let distance = seq.wrapping_sub(self.next_seq);
+        if distance > i32::MAX as u32 {
+            return Ok(());
+        }
+
+        let mut distance = distance as usize;
+        if distance >= self.window {
+            self.advance(distance - self.window + 1, dst, &mut report)?;
+            distance = seq.wrapping_sub(self.next_seq) as usize;
+        }
+        if self.pending.len() <= distance {
+            self.pending.resize(distance + 1, None);
+        }
+        if self.pending[distance].is_none() {
+            self.pending[distance] = Some(packet.to_vec());
+        }
+        self.advance(0, dst, &mut report)
+    }
+
+    /// Gives up the missing packets before the first buffered one
+    /// and delivers the buffered packets.
+    ///
+    /// Call this when the missing packets are not expected to arrive, e.g. on a timeout.
+    pub fn skip_missing<F>(&mut self, dst: &mut Vec<u8>, mut report: F) -> Result<()>
+    where
+        F: FnMut(&PacketEvent),
+    {
+        let skip = self.pending.iter().position(Option::is_some).unwrap_or(0);
+        self.advance(skip, dst, &mut report)
+    }
+
+    /// Delivers the buffered packets in order, giving up the first `skip` ones if missing.
+    fn advance<F>(&mut self, mut skip: usize, dst: &mut Vec<u8>, report: &mut F) -> Result<()>
+    where
+        F: FnMut(&PacketEvent),
+    {
+        let mut lost_seq = self.next_seq;
+        let mut lost = 0;
+        let result = loop {
+            let seq = self.next_seq;
+            let packet = match self.pending.front() {
+                Some(Some(_)) => self.pending.pop_front().flatten(),
+                Some(None) if skip > 0 => self.pending.pop_front().flatten(),
+                None if skip > 0 => {
+                    // Nothing is buffered beyond this point.
+                    if lost == 0 {
+                        lost_seq = seq;
+                    }
+                    lost += skip as u32;
+                    self.next_seq = seq.wrapping_add(skip as u32);
+                    self.linkable = false;
+                    break Ok(());
+                }
+                _ => break Ok(()),
+            };
+
+            skip = skip.saturating_sub(1);
+            self.next_seq = seq.wrapping_add(1);
+            let decoded = match packet {
+                Some(packet) => self.decode(&packet, dst),
+                None => Ok(None),
+            };
+            match decoded {
+                Ok(Some(len)) => {
+                    if lost > 0 {
+                        report(&PacketEvent::Lost {
+                            seq: lost_seq,
+                            count: lost,
+                        });
+                        lost = 0;
+                    }
+                    report(&PacketEvent::Received { seq, len });
+                }
+                Ok(None) => {
+                    if lost == 0 {
+                        lost_seq = seq;
+                    }
+                    lost += 1;
+                    self.linkable = false;
+                }
+                Err(err) => break Err(err),
+            }
+        };
+        if lost > 0 {
+            report(&PacketEvent::Lost {
+                seq: lost_seq,
+                count: lost,
+            });
+        }
+        result
+    }
+
+    /// Returns `None` if the packet references the lost history.
+    fn decode(&mut self, packet: &[u8], dst: &mut Vec<u8>) -> Result<Option<usize>> {
+        let (_, len, linked) = parse_header(packet)?;
+        if linked && !self.linkable {
+            return Ok(None);
+        }
+
+        let dict = if linked {
+            &self.history[self.history.len().saturating_sub(DICTIONARY_SIZE)..]
+        } else {
+            &[]
+        };
+        let orig_len = dst.len();
+        dst.resize(orig_len + len, 0);
+        let payload = &packet[PACKET_HEADER_SIZE..];
+        match lz4::decompress_with_dict(payload, &mut dst[orig_len..], dict) {
+            Ok(n) if n == len => (),
+            _ => {
+                dst.truncate(orig_len);
+                self.linkable = false;
+                return Err(Error::new(ErrorKind::DecompressionFailed));
+            }
+        }
+
+        if !linked {
+            self.history.clear();
+        }
+        self.history.extend_from_slice(&dst[orig_len..]);
+        if self.history.len() > DICTIONARY_SIZE * 2 {
+            self.history.drain(..self.history.len() - DICTIONARY_SIZE);
+        }
+        self.linkable = true;
+        Ok(Some(len))
+    }
+}
+
+impl Default for PacketDecompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the sequence number, the decompressed length and whether the packet is linked.
+fn parse_header(packet: &[u8]) -> Result<(u32, usize, bool)> {
+    if packet.len() < PACKET_HEADER_SIZE {
+        return Err(Error::new(ErrorKind::CompressedDataIncomplete));
+    }
+    let seq = u32::from_le_bytes([packet[0], packet[1], packet[2], packet[3]]);
+    let len = u16::from_le_bytes([packet[4], packet[5]]) as usize;
+    let flags = packet[6];
+    if flags & !FLAG_LINKED != 0 {
+        return Err(Error::new(ErrorKind::FrameHeaderInvalid));
+    }
+    Ok((seq, len, flags & FLAG_LINKED != 0))
+}
@@ -66,6 +66,54 @@ impl CompressionContext {
         }
     }
 
+    /// Compresses the longest prefix of `src` which fits in `dst`, ignoring the history.
+    ///
+    /// The stream is used as the scratch state and left initialized,
+    /// so the caller restores it or continues without the history.
+    pub fn next_dest_size(
+        &mut self,
+        src: &[u8],
+        dst: &mut [u8],
+        acceleration: i32,
+    ) -> (usize, usize) {
+        // Unlike the other functions, this one does not clamp the acceleration.
+        let acceleration = acceleration.clamp(1, 65537);
+        let mut src_size = src.len() as c_int;
+        let dst_len = unsafe {
+            binding::LZ4_compress_destSize_extState(
+                self.get_ptr() as *mut c_void,
+                src.as_ptr() as *const c_char,
+                dst.as_mut_ptr() as *mut c_char,
+                &mut src_size as *mut c_int,
+                dst.len() as c_int,
+                acceleration as c_int,
+            )
+        };
+        if dst_len > 0 {
+            (src_size as usize, dst_len as usize)
+        } else {
+            (0, 0)
+        }
+    }
+
+    pub fn state(&mut self) -> Box<LZ4Stream> {
+        unsafe { Box::new(self.get_ptr().read()) }
+    }
+
+    pub fn set_state(&mut self, state: &LZ4Stream) {
+        // LZ4Stream holds no pointers into itself, so a bitwise copy restores the state.
+        unsafe {
+            std::ptr::copy_nonoverlapping(state as *const LZ4Stream, self.get_ptr(), 1);
+        }
+    }
+
+    fn as_ptr(&self) -> *const LZ4Stream {
+        match &self.stream {
+            Stream::Stack(stream) => stream as *const LZ4Stream,
+            Stream::Heap(ptr) => ptr.as_ptr(),
+        }
+    }
+
     pub fn load_dict(&mut self, dict: &[u8]) {
         unsafe {
             binding::LZ4_loadDict(
@@ -108,6 +156,25 @@ impl CompressionContext {
             binding::LZ4_attach_dictionary(self.get_ptr(), dict_ptr);
         }
     }
+
+    pub fn attach_shared_dict(&mut self, dict: &SharedCompressionContext) {
+        unsafe {
+            binding::LZ4_resetStream_fast(self.get_ptr());
+            binding::LZ4_attach_dictionary(self.get_ptr(), dict.0.as_ptr());
+        }
+    }
+}
+
+/// A compression context which holds a loaded dictionary and is never modified again.
+pub struct SharedCompressionContext(CompressionContext);
+
+// LZ4_attach_dictionary and the compression functions only read the dictionary stream.
+unsafe impl Sync for SharedCompressionContext {}
+
+impl SharedCompressionContext {
+    pub fn new(ctx: CompressionContext) -> Self {
+        Self(ctx)
+    }
 }
 
 impl Drop for CompressionContext {
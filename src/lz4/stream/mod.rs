@@ -2,10 +2,84 @@ mod api;
 
 use crate::{
     common::{DEFAULT_BUF_SIZE, DICTIONARY_SIZE},
-    lz4, Error, ErrorKind, Result,
+    lz4, Error, ErrorKind, Result, Stats, StatsCallback,
 };
-use api::{CompressionContext, DecompressionContext};
-use std::{borrow::Cow, cmp, collections::LinkedList, pin::Pin};
+use api::{CompressionContext, DecompressionContext, SharedCompressionContext};
+use std::{borrow::Cow, cmp, collections::LinkedList, pin::Pin, sync::Arc};
+
+/// A dictionary prepared once and shared between compressors.
+///
+/// Compressors reference the loaded dictionary in place instead of copying
+/// or reloading it. `PreparedDict` is cheap to clone and can be shared between threads.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4;
+/// use std::thread;
+///
+/// let dict = lz4::PreparedDict::new(b"The quick brown fox jumps over the lazy dog.")?;
+///
+/// let workers = (0..4)
+///     .map(|_| {
+///         let dict = dict.clone();
+///         thread::spawn(move || -> lzzzz::Result<Vec<u8>> {
+///             let mut comp = lz4::Compressor::with_prepared_dict(&dict)?;
+///             let mut buf = Vec::new();
+///             comp.next_to_vec(b"The quick brown fox", &mut buf, lz4::ACC_LEVEL_DEFAULT)?;
+///             Ok(buf)
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for worker in workers {
+///     let comp = worker.join().unwrap()?;
+///     let mut buf = [0u8; 19];
+///     lz4::decompress_with_dict(&comp, &mut buf, dict.as_bytes())?;
+///     assert_eq!(&buf, b"The quick brown fox");
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct PreparedDict(Arc<PreparedDictInner>);
+
+struct PreparedDictInner {
+    ctx: SharedCompressionContext,
+    data: Box<[u8]>,
+}
+
+impl PreparedDict {
+    /// Loads a dictionary.
+    ///
+    /// Only the last 64 KiB of `data` are used.
+    pub fn new(data: &[u8]) -> Result<Self> {
+        Self::load(data, CompressionContext::load_dict)
+    }
+
+    /// Loads a dictionary, spending more time to reference it better.
+    ///
+    /// See [`Compressor::with_dict_slow`] for details.
+    ///
+    /// [`Compressor::with_dict_slow`]: struct.Compressor.html#method.with_dict_slow
+    pub fn new_slow(data: &[u8]) -> Result<Self> {
+        Self::load(data, CompressionContext::load_dict_slow)
+    }
+
+    fn load(data: &[u8], load: fn(&mut CompressionContext, &[u8])) -> Result<Self> {
+        let data: Box<[u8]> = data[data.len().saturating_sub(DICTIONARY_SIZE)..].into();
+        let mut ctx = CompressionContext::new()?;
+        load(&mut ctx, &data);
+        Ok(Self(Arc::new(PreparedDictInner {
+            ctx: SharedCompressionContext::new(ctx),
+            data,
+        })))
+    }
+
+    /// Returns the dictionary data for the decompressors.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0.data
+    }
+}
 
 /// Streaming LZ4 compressor.
 ///
@@ -33,6 +107,10 @@ pub struct Compressor<'a> {
     ctx: CompressionContext,
     dict: Pin<Cow<'a, [u8]>>,
     safe_buf: Vec<u8>,
+    total_in: u64,
+    total_out: u64,
+    block_callback: Option<StatsCallback>,
+    prepared_dict: Option<PreparedDict>,
 }
 
 impl<'a> Compressor<'a> {
@@ -42,9 +120,20 @@ impl<'a> Compressor<'a> {
             ctx: CompressionContext::new()?,
             dict: Pin::new(Cow::Borrowed(&[])),
             safe_buf: Vec::new(),
+            total_in: 0,
+            total_out: 0,
+            block_callback: None,
+            prepared_dict: None,
         })
     }
 
+    /// Creates a new `Compressor` with a prepared dictionary.
+    pub fn with_prepared_dict(dict: &PreparedDict) -> Result<Self> {
+        let mut comp = Self::new()?;
+        comp.attach_prepared_dict(dict);
+        Ok(comp)
+    }
+
     /// Creates a new `Compressor` with a dictionary.
     pub fn with_dict<D>(dict: D) -> Result<Self>
     where
@@ -86,15 +175,72 @@ impl<'a> Compressor<'a> {
 
         self.save_dict();
 
-        if dst_len > 0 {
+        if dst_len > 0 || is_empty {
+            self.record(src.len(), dst_len);
             Ok(dst_len)
-        } else if is_empty {
-            Ok(0)
         } else {
             Err(Error::new(ErrorKind::CompressionFailed))
         }
     }
 
+    /// Performs LZ4 streaming compression to fill `dst`.
+    ///
+    /// This function either compresses the entire `src` buffer into `dst` if it's
+    /// large enough, or compresses the longest prefix of `src` which would fit in
+    /// `dst` without the history. The history usually makes the output shorter,
+    /// so `dst` may not be filled completely.
+    /// Unlike [`lz4::compress_fill`], the previous data is kept as the history.
+    ///
+    /// Returns a pair `(read, wrote)` giving the number of bytes read from `src`
+    /// and the number of bytes written to `dst`.
+    ///
+    /// [`lz4::compress_fill`]: fn.compress_fill.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4;
+    ///
+    /// let data = b"The quick brown fox jumps over the lazy dog.";
+    ///
+    /// let mut smallbuf = [0u8; 32];
+    /// assert!(smallbuf.len() < lz4::max_compressed_size(data.len()));
+    ///
+    /// let mut comp = lz4::Compressor::new()?;
+    /// let (read, wrote) = comp.next_fill(data, &mut smallbuf, lz4::ACC_LEVEL_DEFAULT)?;
+    /// let remaining_data = &data[read..];
+    ///
+    /// # let mut buf = [0u8; 256];
+    /// # let len = lz4::decompress(&smallbuf[..wrote], &mut buf)?;
+    /// # assert_eq!(&buf[..len], &data[..read]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn next_fill(&mut self, src: &[u8], dst: &mut [u8], acc: i32) -> Result<(usize, usize)> {
+        if src.is_empty() || dst.len() >= lz4::max_compressed_size(src.len()) {
+            return self.next(src, dst, acc).map(|len| (src.len(), len));
+        }
+
+        // liblz4 has no streaming variant of LZ4_compress_destSize.
+        // The length of the prefix is taken from a compression without the history,
+        // then the prefix is compressed once more from the saved state.
+        let state = self.ctx.state();
+        let (mut src_len, _) = self.ctx.next_dest_size(src, dst, acc);
+        self.ctx.set_state(&state);
+        if src_len == 0 {
+            return Err(Error::new(ErrorKind::CompressionFailed));
+        }
+        let mut dst_len = self.ctx.next(&src[..src_len], dst.as_mut_ptr(), dst.len(), acc);
+        if dst_len == 0 {
+            // The history rarely makes the output longer. In that case,
+            // the block is compressed without it and the stream starts over.
+            (src_len, dst_len) = self.ctx.next_dest_size(src, dst, acc);
+        }
+
+        self.save_dict();
+        self.record(src_len, dst_len);
+        Ok((src_len, dst_len))
+    }
+
     /// Appends compressed data to `Vec<u8>`.
     ///
     /// Returns the number of bytes appended to the given `Vec<u8>`.
@@ -114,6 +260,44 @@ impl<'a> Compressor<'a> {
         }
     }
 
+    /// Returns the total number of uncompressed bytes consumed.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the total number of compressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Returns the compression ratio of the data compressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        Stats::new(self.total_in, self.total_out).ratio()
+    }
+
+    /// Sets a callback which receives the statistics of each compressed block.
+    ///
+    /// The callback is invoked for every successful compression call,
+    /// including calls with empty input.
+    pub fn set_block_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.block_callback = Some(Box::new(callback));
+    }
+
+    fn record(&mut self, src_len: usize, dst_len: usize) {
+        self.total_in += src_len as u64;
+        self.total_out += dst_len as u64;
+        if let Some(callback) = &mut self.block_callback {
+            callback(&Stats::new(src_len as u64, dst_len as u64));
+        }
+    }
+
     fn save_dict(&mut self) {
         self.safe_buf.resize(DICTIONARY_SIZE, 0);
         self.ctx.save_dict(&mut self.safe_buf);
@@ -161,6 +345,16 @@ impl<'a> Compressor<'a> {
             self.ctx.attach_dict(None);
         }
     }
+
+    /// Resets the stream and attaches a prepared dictionary.
+    ///
+    /// The following data is compressed with `dict` as the dictionary,
+    /// independently of the data compressed before.
+    /// Attaching is cheap, so a compressor can be reused for each message.
+    pub fn attach_prepared_dict(&mut self, dict: &PreparedDict) {
+        self.ctx.attach_shared_dict(&dict.0.ctx);
+        self.prepared_dict = Some(dict.clone());
+    }
 }
 
 /// Streaming LZ4 decompressor.
@@ -50,6 +50,30 @@ pub fn compress_ext_state_fast_reset(
     }
 }
 
+pub fn compress_ext_state_favor_dec_speed(
+    state: &mut [u8],
+    src: &[u8],
+    dst: *mut u8,
+    dst_len: usize,
+    compression_level: i32,
+) -> usize {
+    let stream = state.as_mut_ptr() as *mut binding::LZ4StreamHC;
+    unsafe {
+        binding::LZ4_resetStreamHC_fast(stream, compression_level as c_int);
+        binding::LZ4_favorDecompressionSpeed(stream, 1);
+        let len = binding::LZ4_compress_HC_continue(
+            stream,
+            src.as_ptr() as *const c_char,
+            dst as *mut c_char,
+            src.len() as c_int,
+            dst_len as c_int,
+        );
+        // The flag survives the fast reset, so clear it for the other functions sharing the state.
+        binding::LZ4_favorDecompressionSpeed(stream, 0);
+        len as usize
+    }
+}
+
 pub fn compress_dest_size(
     state: &mut [u8],
     src: &[u8],
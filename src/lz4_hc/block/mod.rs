@@ -56,6 +56,27 @@ fn compress_to_ptr(src: &[u8], dst: *mut u8, dst_len: usize, level: i32) -> Resu
     }
 }
 
+/// Performs LZ4_HC block compression favoring the decompression speed.
+pub(crate) fn compress_favor_dec_speed(src: &[u8], dst: &mut [u8], level: i32) -> Result<usize> {
+    if src.is_empty() {
+        return Ok(0);
+    }
+    let len = ExtState::with(|state, _reset| {
+        api::compress_ext_state_favor_dec_speed(
+            &mut state.borrow_mut(),
+            src,
+            dst.as_mut_ptr(),
+            dst.len(),
+            level,
+        )
+    });
+    if len > 0 {
+        Ok(len)
+    } else {
+        Err(Error::new(ErrorKind::CompressionFailed))
+    }
+}
+
 /// Compress data to fill `dst`.
 ///
 /// This function either compresses the entire `src` buffer into `dst` if it's
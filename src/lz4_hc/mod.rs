@@ -28,14 +28,9 @@ pub const CLEVEL_OPT_MIN: i32 = 10;
 pub const CLEVEL_MAX: i32 = 12;
 
 /// Decompression speed mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum FavorDecSpeed {
+    #[default]
     Disabled,
     Enabled,
 }
-
-impl Default for FavorDecSpeed {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
@@ -106,6 +106,25 @@ impl CompressionContext {
             binding::LZ4_attach_HC_dictionary(self.stream.as_ptr(), dict_ptr);
         }
     }
+
+    pub fn attach_shared_dict(&mut self, dict: &SharedCompressionContext, compression_level: i32) {
+        unsafe {
+            binding::LZ4_resetStreamHC_fast(self.stream.as_ptr(), compression_level as c_int);
+            binding::LZ4_attach_HC_dictionary(self.stream.as_ptr(), dict.0.stream.as_ptr());
+        }
+    }
+}
+
+/// A compression context which holds a loaded dictionary and is never modified again.
+pub struct SharedCompressionContext(CompressionContext);
+
+// LZ4_attach_HC_dictionary and the compression functions only read the dictionary stream.
+unsafe impl Sync for SharedCompressionContext {}
+
+impl SharedCompressionContext {
+    pub fn new(ctx: CompressionContext) -> Self {
+        Self(ctx)
+    }
 }
 
 impl Drop for CompressionContext {
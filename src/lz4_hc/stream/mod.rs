@@ -1,8 +1,80 @@
 mod api;
 
-use crate::{common::DICTIONARY_SIZE, lz4, lz4_hc::FavorDecSpeed, Result};
-use api::CompressionContext;
-use std::{borrow::Cow, cmp, io::Cursor, pin::Pin};
+use crate::{common::DICTIONARY_SIZE, lz4, lz4_hc::FavorDecSpeed, Result, Stats, StatsCallback};
+use api::{CompressionContext, SharedCompressionContext};
+use std::{borrow::Cow, cmp, io::Cursor, pin::Pin, sync::Arc};
+
+/// A dictionary prepared once and shared between compressors.
+///
+/// Compressors reference the loaded dictionary in place instead of copying
+/// or reloading it. `PreparedDict` is cheap to clone and can be shared between threads.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4, lz4_hc};
+/// use std::thread;
+///
+/// let dict = lz4_hc::PreparedDict::new(
+///     b"The quick brown fox jumps over the lazy dog.",
+///     lz4_hc::CLEVEL_DEFAULT,
+/// )?;
+///
+/// let workers = (0..4)
+///     .map(|_| {
+///         let dict = dict.clone();
+///         thread::spawn(move || -> lzzzz::Result<Vec<u8>> {
+///             let mut comp = lz4_hc::Compressor::with_prepared_dict(&dict)?;
+///             let mut buf = Vec::new();
+///             comp.next_to_vec(b"The quick brown fox", &mut buf)?;
+///             Ok(buf)
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for worker in workers {
+///     let comp = worker.join().unwrap()?;
+///     let mut buf = [0u8; 19];
+///     lz4::decompress_with_dict(&comp, &mut buf, dict.as_bytes())?;
+///     assert_eq!(&buf, b"The quick brown fox");
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct PreparedDict(Arc<PreparedDictInner>);
+
+struct PreparedDictInner {
+    ctx: SharedCompressionContext,
+    data: Box<[u8]>,
+    compression_level: i32,
+}
+
+impl PreparedDict {
+    /// Loads a dictionary for `compression_level`.
+    ///
+    /// Only the last 64 KiB of `data` are used.
+    pub fn new(data: &[u8], compression_level: i32) -> Result<Self> {
+        let data: Box<[u8]> = data[data.len().saturating_sub(DICTIONARY_SIZE)..].into();
+        let mut ctx = CompressionContext::new()?;
+        ctx.set_compression_level(compression_level);
+        ctx.load_dict(&data);
+        Ok(Self(Arc::new(PreparedDictInner {
+            ctx: SharedCompressionContext::new(ctx),
+            data,
+            compression_level,
+        })))
+    }
+
+    /// Returns the compression level the dictionary was loaded for.
+    pub fn compression_level(&self) -> i32 {
+        self.0.compression_level
+    }
+
+    /// Returns the dictionary data for the decompressors.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0.data
+    }
+}
 
 /// Streaming LZ4_HC compressor.
 ///
@@ -30,6 +102,10 @@ pub struct Compressor<'a> {
     ctx: CompressionContext,
     dict: Pin<Cow<'a, [u8]>>,
     safe_buf: Vec<u8>,
+    total_in: u64,
+    total_out: u64,
+    block_callback: Option<StatsCallback>,
+    prepared_dict: Option<PreparedDict>,
 }
 
 impl<'a> Compressor<'a> {
@@ -39,9 +115,22 @@ impl<'a> Compressor<'a> {
             ctx: CompressionContext::new()?,
             dict: Pin::new(Cow::Borrowed(&[])),
             safe_buf: Vec::new(),
+            total_in: 0,
+            total_out: 0,
+            block_callback: None,
+            prepared_dict: None,
         })
     }
 
+    /// Creates a new `Compressor` with a prepared dictionary.
+    ///
+    /// The compression level is taken from the dictionary.
+    pub fn with_prepared_dict(dict: &PreparedDict) -> Result<Self> {
+        let mut comp = Self::new()?;
+        comp.attach_prepared_dict(dict);
+        Ok(comp)
+    }
+
     /// Creates a new `Compressor` with a dictionary.
     pub fn with_dict<D>(dict: D, compression_level: i32) -> Result<Self>
     where
@@ -84,6 +173,7 @@ impl<'a> Compressor<'a> {
     fn next_to_ptr(&mut self, src: &[u8], dst: *mut u8, dst_len: usize) -> Result<usize> {
         let result = self.ctx.next(src, dst, dst_len)?;
         self.save_dict();
+        self.record(src.len(), result);
         Ok(result)
     }
 
@@ -117,6 +207,7 @@ impl<'a> Compressor<'a> {
     pub fn next_fill(&mut self, src: &[u8], dst: &mut [u8]) -> Result<(usize, usize)> {
         let (src_len, dst_len) = self.ctx.next_partial(src, dst)?;
         self.save_dict();
+        self.record(src_len, dst_len);
         Ok((src_len, dst_len))
     }
 
@@ -134,6 +225,7 @@ impl<'a> Compressor<'a> {
         let (src_len, dst_len) = self.ctx.next_partial(src_ref, dst)?;
         src.set_position(src.position() + src_len as u64);
         self.save_dict();
+        self.record(src_len, dst_len);
         Ok(dst_len)
     }
 
@@ -155,6 +247,44 @@ impl<'a> Compressor<'a> {
         }
     }
 
+    /// Returns the total number of uncompressed bytes consumed.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Returns the total number of compressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Returns the compression ratio of the data compressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        Stats::new(self.total_in, self.total_out).ratio()
+    }
+
+    /// Sets a callback which receives the statistics of each compressed block.
+    ///
+    /// The callback is invoked for every successful compression call,
+    /// including calls with empty input.
+    pub fn set_block_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.block_callback = Some(Box::new(callback));
+    }
+
+    fn record(&mut self, src_len: usize, dst_len: usize) {
+        self.total_in += src_len as u64;
+        self.total_out += dst_len as u64;
+        if let Some(callback) = &mut self.block_callback {
+            callback(&Stats::new(src_len as u64, dst_len as u64));
+        }
+    }
+
     fn save_dict(&mut self) {
         self.safe_buf.resize(DICTIONARY_SIZE, 0);
         self.ctx.save_dict(&mut self.safe_buf);
@@ -203,4 +333,16 @@ impl<'a> Compressor<'a> {
             self.ctx.attach_dict(None, compression_level);
         }
     }
+
+    /// Resets the stream and attaches a prepared dictionary.
+    ///
+    /// The following data is compressed with `dict` as the dictionary,
+    /// independently of the data compressed before.
+    /// The compression level is reset to the level of the dictionary.
+    /// Attaching is cheap, so a compressor can be reused for each message.
+    pub fn attach_prepared_dict(&mut self, dict: &PreparedDict) {
+        self.ctx
+            .attach_shared_dict(&dict.0.ctx, dict.compression_level());
+        self.prepared_dict = Some(dict.clone());
+    }
 }
@@ -10,7 +10,11 @@ use super::{
 };
 use crate::lz4f::{Error, ErrorKind, FrameInfo, Preferences, Result};
 
-use std::{mem::MaybeUninit, os::raw::c_void, ptr::NonNull};
+use std::{
+    mem::MaybeUninit,
+    os::raw::{c_int, c_uint, c_void},
+    ptr::NonNull,
+};
 
 pub const LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH: usize = 5;
 pub const LZ4F_HEADER_SIZE_MAX: usize = 19;
@@ -40,6 +44,10 @@ impl CompressionContext {
         }
     }
 
+    pub fn set_dict(&mut self, dict: Option<Dictionary>) {
+        self.dict = dict;
+    }
+
     pub fn begin(&mut self, dst: *mut u8, dst_len: usize, prefs: &Preferences) -> Result<usize> {
         let code = unsafe {
             if let Some(dict) = &self.dict {
@@ -78,6 +86,41 @@ impl CompressionContext {
         result_from_code(code).map(|_| code)
     }
 
+    pub fn uncompressed_update(
+        &mut self,
+        dst: *mut u8,
+        dst_len: usize,
+        src: &[u8],
+        stable_src: bool,
+    ) -> Result<usize> {
+        let opt = LZ4FCompressionOptions::stable(stable_src);
+        let code = unsafe {
+            binding::LZ4F_uncompressedUpdate(
+                self.ctx.as_ptr(),
+                dst as *mut c_void,
+                dst_len,
+                src.as_ptr() as *const c_void,
+                src.len(),
+                &opt as *const LZ4FCompressionOptions,
+            )
+        };
+        result_from_code(code).map(|_| code)
+    }
+
+    /// Changes the compression level of the current frame from the next block.
+    ///
+    /// The level must stay in the same family (fast or HC) as the level the frame started with.
+    pub fn set_compression_level(&mut self, prefs: &Preferences) -> Result<()> {
+        let code = unsafe {
+            binding::lzzzz_LZ4F_setCompressionLevel(
+                self.ctx.as_ptr(),
+                prefs.compression_level() as c_int,
+                prefs.favor_dec_speed() as c_uint,
+            )
+        };
+        result_from_code(code)
+    }
+
     pub fn flush(&mut self, dst: *mut u8, dst_len: usize, stable_src: bool) -> Result<usize> {
         let opt = LZ4FCompressionOptions::stable(stable_src);
         let code = unsafe {
@@ -104,6 +147,31 @@ impl CompressionContext {
         result_from_code(code).map(|_| code)
     }
 
+    pub fn compress_frame(
+        &mut self,
+        src: &[u8],
+        dst: *mut u8,
+        dst_len: usize,
+        prefs: &Preferences,
+    ) -> Result<usize> {
+        let dict = self
+            .dict
+            .as_ref()
+            .map_or(std::ptr::null(), |dict| dict.handle().0.as_ptr());
+        let code = unsafe {
+            binding::LZ4F_compressFrame_usingCDict(
+                self.ctx.as_ptr(),
+                dst as *mut c_void,
+                dst_len,
+                src.as_ptr() as *const c_void,
+                src.len(),
+                dict,
+                prefs as *const Preferences,
+            )
+        };
+        result_from_code(code).map(|_| code)
+    }
+
     pub fn compress_bound(src_size: usize, prefs: &Preferences) -> usize {
         unsafe { binding::LZ4F_compressBound(src_size, prefs as *const Preferences) }
     }
@@ -1,5 +1,5 @@
 use super::{FrameInfo, Preferences};
-use std::os::raw::{c_uint, c_void};
+use std::os::raw::{c_int, c_uint, c_void};
 
 #[allow(non_camel_case_types)]
 type size_t = usize;
@@ -96,6 +96,15 @@ extern "C" {
         dist: *const LZ4FCompressionDict,
         prefs: *const Preferences,
     ) -> size_t;
+    pub fn LZ4F_compressFrame_usingCDict(
+        ctx: *mut LZ4FCompressionCtx,
+        dst_buffer: *mut c_void,
+        dst_capacity: size_t,
+        src_buffer: *const c_void,
+        src_size: size_t,
+        dict: *const LZ4FCompressionDict,
+        prefs: *const Preferences,
+    ) -> size_t;
     pub fn LZ4F_compressUpdate(
         ctx: *mut LZ4FCompressionCtx,
         dst_buffer: *mut c_void,
@@ -104,6 +113,19 @@ extern "C" {
         src_size: size_t,
         opt: *const LZ4FCompressionOptions,
     ) -> size_t;
+    pub fn LZ4F_uncompressedUpdate(
+        ctx: *mut LZ4FCompressionCtx,
+        dst_buffer: *mut c_void,
+        dst_capacity: size_t,
+        src_buffer: *const c_void,
+        src_size: size_t,
+        opt: *const LZ4FCompressionOptions,
+    ) -> size_t;
+    pub fn lzzzz_LZ4F_setCompressionLevel(
+        ctx: *mut LZ4FCompressionCtx,
+        level: c_int,
+        favor_dec_speed: c_uint,
+    ) -> size_t;
     pub fn LZ4F_flush(
         ctx: *mut LZ4FCompressionCtx,
         dst_buffer: *mut c_void,
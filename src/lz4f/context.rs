@@ -0,0 +1,203 @@
+use super::{api, Result};
+use std::{
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// A reusable LZ4F compression context.
+///
+/// A context can be passed to the compressors with `with_context`.
+/// If it is taken from a [`ContextPool`], it goes back to the pool when dropped.
+///
+/// [`ContextPool`]: struct.ContextPool.html
+pub struct CompressionContext {
+    ctx: Option<api::CompressionContext>,
+    pool: Option<ContextPool>,
+}
+
+impl CompressionContext {
+    /// Creates a new `CompressionContext`.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            ctx: Some(api::CompressionContext::new(None)?),
+            pool: None,
+        })
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut api::CompressionContext {
+        self.ctx.as_mut().unwrap()
+    }
+}
+
+impl fmt::Debug for CompressionContext {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("CompressionContext").finish()
+    }
+}
+
+impl Drop for CompressionContext {
+    fn drop(&mut self) {
+        if let (Some(mut ctx), Some(pool)) = (self.ctx.take(), &self.pool) {
+            ctx.set_dict(None);
+            let mut idle = pool.lock_comp();
+            if idle.len() < pool.0.capacity {
+                idle.push(ctx);
+            }
+        }
+    }
+}
+
+/// A reusable LZ4F decompression context.
+///
+/// A context can be passed to the decompressors with `with_context`.
+/// If it is taken from a [`ContextPool`], it goes back to the pool when dropped.
+///
+/// [`ContextPool`]: struct.ContextPool.html
+pub struct DecompressionContext {
+    ctx: Option<api::DecompressionContext>,
+    pool: Option<ContextPool>,
+}
+
+impl DecompressionContext {
+    /// Creates a new `DecompressionContext`.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            ctx: Some(api::DecompressionContext::new()?),
+            pool: None,
+        })
+    }
+
+    pub(crate) fn get(&self) -> &api::DecompressionContext {
+        self.ctx.as_ref().unwrap()
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut api::DecompressionContext {
+        self.ctx.as_mut().unwrap()
+    }
+}
+
+impl fmt::Debug for DecompressionContext {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("DecompressionContext").finish()
+    }
+}
+
+impl Drop for DecompressionContext {
+    fn drop(&mut self) {
+        if let (Some(mut ctx), Some(pool)) = (self.ctx.take(), &self.pool) {
+            ctx.reset();
+            let mut idle = pool.lock_decomp();
+            if idle.len() < pool.0.capacity {
+                idle.push(ctx);
+            }
+        }
+    }
+}
+
+/// A thread-safe pool of reusable LZ4F contexts.
+///
+/// Creating a context allocates the internal state of liblz4.
+/// `ContextPool` keeps dropped contexts and hands them out again,
+/// which avoids the allocation for each stream.
+///
+/// `ContextPool` is a shared handle; clones refer to the same pool.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{ContextPool, WriteCompressor};
+/// use std::io::prelude::*;
+///
+/// let pool = ContextPool::new();
+///
+/// for _ in 0..3 {
+///     let ctx = pool.compression_context()?;
+///     let mut w = WriteCompressor::with_context(Vec::new(), Default::default(), ctx);
+///     w.write_all(b"Hello world!")?;
+///     w.finish()?;
+/// }
+///
+/// assert_eq!(pool.len(), 1);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct ContextPool(Arc<Pool>);
+
+struct Pool {
+    comp: Mutex<Vec<api::CompressionContext>>,
+    decomp: Mutex<Vec<api::DecompressionContext>>,
+    capacity: usize,
+}
+
+impl ContextPool {
+    /// Creates a new `ContextPool` without a limit on the number of idle contexts.
+    pub fn new() -> Self {
+        Self::with_capacity(usize::MAX)
+    }
+
+    /// Creates a new `ContextPool` which keeps at most `capacity` idle contexts of each kind.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Arc::new(Pool {
+            comp: Mutex::new(Vec::new()),
+            decomp: Mutex::new(Vec::new()),
+            capacity,
+        }))
+    }
+
+    /// Takes a compression context from the pool, or creates a new one if the pool is empty.
+    pub fn compression_context(&self) -> Result<CompressionContext> {
+        let ctx = self.lock_comp().pop();
+        Ok(CompressionContext {
+            ctx: Some(match ctx {
+                Some(ctx) => ctx,
+                None => api::CompressionContext::new(None)?,
+            }),
+            pool: Some(self.clone()),
+        })
+    }
+
+    /// Takes a decompression context from the pool, or creates a new one if the pool is empty.
+    pub fn decompression_context(&self) -> Result<DecompressionContext> {
+        let ctx = self.lock_decomp().pop();
+        Ok(DecompressionContext {
+            ctx: Some(match ctx {
+                Some(ctx) => ctx,
+                None => api::DecompressionContext::new()?,
+            }),
+            pool: Some(self.clone()),
+        })
+    }
+
+    /// Returns the number of idle contexts in the pool.
+    pub fn len(&self) -> usize {
+        self.lock_comp().len() + self.lock_decomp().len()
+    }
+
+    /// Returns `true` if the pool has no idle contexts.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock_comp(&self) -> MutexGuard<'_, Vec<api::CompressionContext>> {
+        self.0.comp.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn lock_decomp(&self) -> MutexGuard<'_, Vec<api::DecompressionContext>> {
+        self.0.decomp.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Default for ContextPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ContextPool {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ContextPool")
+            .field("capacity", &self.0.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,239 @@
+//! Low-level LZ4 Frame Decoder
+
+use super::{stream::decomp::DictPtr, DecompressionContext, DictionaryRegistry, FrameInfo, Result};
+use crate::{Error, ErrorKind};
+use std::{borrow::Cow, pin::Pin, sync::Arc};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Idle,
+    Buffered,
+    Stable { addr: usize, len: usize, pos: usize },
+}
+
+/// Low-level LZ4F decoder with caller-provided output.
+///
+/// Unlike the streaming decompressors, `FrameDecoder` does not own any output buffer.
+/// Each call decodes as much data as possible into the given slice and returns
+/// liblz4's hint for the next input size.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+///
+/// let mut decoder = lz4f::FrameDecoder::new()?;
+/// let mut buf = [0u8; 256];
+/// let (consumed, produced, hint) = decoder.decode(&comp, &mut buf)?;
+///
+/// assert_eq!(consumed, comp.len());
+/// assert_eq!(&buf[..produced], &data[..]);
+/// assert_eq!(hint, 0);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct FrameDecoder<'a> {
+    ctx: DecompressionContext,
+    dict: Pin<Cow<'a, [u8]>>,
+    registry: Option<DictionaryRegistry>,
+    frame_dict: Option<Arc<[u8]>>,
+    comp_dict: Option<DictPtr>,
+    mode: Mode,
+}
+
+impl<'a> FrameDecoder<'a> {
+    /// Creates a new `FrameDecoder`.
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_context(DecompressionContext::new()?))
+    }
+
+    /// Creates a new `FrameDecoder` with a reusable decompression context.
+    pub fn with_context(ctx: DecompressionContext) -> Self {
+        Self {
+            ctx,
+            dict: Pin::new(Cow::Borrowed(&[])),
+            registry: None,
+            frame_dict: None,
+            comp_dict: None,
+            mode: Mode::Idle,
+        }
+    }
+
+    /// Sets the dictionary.
+    ///
+    /// The dictionary must not be changed while a frame is being decoded.
+    pub fn set_dict<D>(&mut self, dict: D)
+    where
+        D: Into<Cow<'a, [u8]>>,
+    {
+        self.dict = Pin::new(dict.into());
+    }
+
+    /// Sets the dictionary registry.
+    ///
+    /// If a frame header has a non-zero `dict_id`, the dictionary is taken from the registry
+    /// instead of the one given by [`set_dict`], and an unknown ID is reported as
+    /// `DictionaryNotFound`. The frame header must be decoded in a single call,
+    /// so the first `src` of each frame must contain the whole header
+    /// unless [`read_frame_info`] has already decoded it.
+    ///
+    /// [`set_dict`]: #method.set_dict
+    /// [`read_frame_info`]: #method.read_frame_info
+    pub fn set_dict_registry(&mut self, registry: DictionaryRegistry) {
+        self.registry = Some(registry);
+    }
+
+    /// Decodes the frame header and returns `FrameInfo` with the number of bytes consumed.
+    ///
+    /// `src` must contain the whole frame header. If the header is already decoded,
+    /// this function returns the current `FrameInfo` and consumes nothing.
+    pub fn read_frame_info(&mut self, src: &[u8]) -> Result<(FrameInfo, usize)> {
+        self.ctx.get().get_frame_info(src)
+    }
+
+    /// Decodes `src` into `dst`.
+    ///
+    /// Returns a tuple `(consumed, produced, hint)` giving the number of bytes read from `src`,
+    /// the number of bytes written into `dst` and the expected size of the next `src`.
+    /// A `hint` of `0` means that the current frame is fully decoded.
+    pub fn decode(&mut self, src: &[u8], dst: &mut [u8]) -> Result<(usize, usize, usize)> {
+        if let Mode::Stable { .. } = self.mode {
+            return Err(Error::new(ErrorKind::DestinationChangedDuringDecompression).into());
+        }
+        let header_len = self.load_frame_dict(src)?;
+        self.check_dict()?;
+        let dict = match &self.frame_dict {
+            Some(dict) => &dict[..],
+            None => &self.dict[..],
+        };
+        let (src_len, dst_len, hint) =
+            self.ctx
+                .get_mut()
+                .decompress_dict(&src[header_len..], dst, dict, false)?;
+        self.mode = Mode::Buffered;
+        self.update_state(hint);
+        Ok((header_len + src_len, dst_len, hint))
+    }
+
+    /// Decodes `src` into `dst` in the stable-destination mode.
+    ///
+    /// `dst` must be the same slice for all calls within a frame,
+    /// typically pre-allocated for the whole decompressed content.
+    /// Decoded data is written right after the data produced by the previous calls,
+    /// so the decoder can reference the history in place
+    /// instead of copying it into the internal buffer.
+    ///
+    /// Returns a tuple `(consumed, produced, hint)` as [`decode`] does.
+    ///
+    /// [`decode`]: #method.decode
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4f;
+    ///
+    /// let data = b"The quick brown fox jumps over the lazy dog.";
+    /// let prefs = lz4f::PreferencesBuilder::new().content_size(1).build();
+    /// let mut comp = Vec::new();
+    /// lz4f::compress_to_vec(data, &mut comp, &prefs)?;
+    ///
+    /// let mut decoder = lz4f::FrameDecoder::new()?;
+    /// let (info, header_len) = decoder.read_frame_info(&comp)?;
+    /// let mut buf = vec![0; info.content_size()];
+    ///
+    /// let mut src = &comp[header_len..];
+    /// let mut len = 0;
+    /// loop {
+    ///     let (consumed, produced, hint) = decoder.decode_stable(src, &mut buf)?;
+    ///     src = &src[consumed..];
+    ///     len += produced;
+    ///     if hint == 0 {
+    ///         break;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(&buf[..len], &data[..]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn decode_stable(&mut self, src: &[u8], dst: &mut [u8]) -> Result<(usize, usize, usize)> {
+        let (addr, len) = (dst.as_ptr() as usize, dst.len());
+        let pos = match self.mode {
+            Mode::Idle => 0,
+            Mode::Stable {
+                addr: prev_addr,
+                len: prev_len,
+                pos,
+            } if prev_addr == addr && prev_len == len => pos,
+            _ => return Err(Error::new(ErrorKind::DestinationChangedDuringDecompression).into()),
+        };
+        let header_len = self.load_frame_dict(src)?;
+        self.check_dict()?;
+        let dict = match &self.frame_dict {
+            Some(dict) => &dict[..],
+            None => &self.dict[..],
+        };
+        let (src_len, dst_len, hint) =
+            self.ctx
+                .get_mut()
+                .decompress_dict(&src[header_len..], &mut dst[pos..], dict, true)?;
+        self.mode = Mode::Stable {
+            addr,
+            len,
+            pos: pos + dst_len,
+        };
+        self.update_state(hint);
+        Ok((header_len + src_len, dst_len, hint))
+    }
+
+    /// Resets the decoder state.
+    ///
+    /// This should be called after an error to start decoding a new frame.
+    pub fn reset(&mut self) {
+        self.ctx.get_mut().reset();
+        self.frame_dict = None;
+        self.comp_dict = None;
+        self.mode = Mode::Idle;
+    }
+
+    /// Looks up the dictionary of a new frame in the registry.
+    ///
+    /// Returns the number of header bytes consumed.
+    fn load_frame_dict(&mut self, src: &[u8]) -> Result<usize> {
+        let registry = match &self.registry {
+            Some(registry) if self.comp_dict.is_none() => registry,
+            _ => return Ok(0),
+        };
+        let (frame, header_len) = self.ctx.get().get_frame_info(src)?;
+        self.frame_dict = match frame.dict_id() {
+            0 => None,
+            dict_id => Some(
+                registry
+                    .get_shared(dict_id)
+                    .ok_or_else(|| Error::new(ErrorKind::DictionaryNotFound))?,
+            ),
+        };
+        Ok(header_len)
+    }
+
+    fn check_dict(&mut self) -> Result<()> {
+        let dict_ptr = match &self.frame_dict {
+            Some(dict) => DictPtr::new(dict),
+            None => DictPtr::new(&self.dict),
+        };
+        if dict_ptr != *self.comp_dict.get_or_insert(dict_ptr) {
+            return Err(Error::new(ErrorKind::DictionaryChangedDuringDecompression).into());
+        }
+        Ok(())
+    }
+
+    fn update_state(&mut self, hint: usize) {
+        if hint == 0 {
+            self.frame_dict = None;
+            self.comp_dict = None;
+            self.mode = Mode::Idle;
+        }
+    }
+}
@@ -1,14 +1,28 @@
 use super::{api::DictionaryHandle, Result};
-use std::sync::Arc;
+use std::{collections::HashMap, fmt, sync::Arc};
 
 /// A pre-compiled dictionary for the efficient compression.
 #[derive(Clone)]
-pub struct Dictionary(Arc<DictionaryHandle>);
+pub struct Dictionary(Arc<DictionaryHandle>, u32);
 
 impl Dictionary {
     /// Builds a new `Dictionary`.
     pub fn new(data: &[u8]) -> Result<Self> {
-        Ok(Self(Arc::new(DictionaryHandle::new(data)?)))
+        Self::with_id(data, 0)
+    }
+
+    /// Builds a new `Dictionary` with a dictionary ID.
+    ///
+    /// Compressors using this dictionary write `dict_id` to the frame header
+    /// unless the preferences specify another one.
+    /// The ID `0` means that the dictionary has no ID.
+    pub fn with_id(data: &[u8], dict_id: u32) -> Result<Self> {
+        Ok(Self(Arc::new(DictionaryHandle::new(data)?), dict_id))
+    }
+
+    /// Returns the dictionary ID.
+    pub const fn dict_id(&self) -> u32 {
+        self.1
     }
 
     pub(crate) fn handle(&self) -> &DictionaryHandle {
@@ -16,6 +30,102 @@ impl Dictionary {
     }
 }
 
+/// A set of decompression dictionaries keyed by dictionary ID.
+///
+/// Streaming decompressors and [`FrameDecoder`] with a registry look up the dictionary
+/// from the `dict_id` field of each frame header, as do the one-shot
+/// [`decompress_with_registry`] and [`decompress_to_vec_with_registry`].
+/// Cloning a registry is cheap because the dictionary data is shared.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, Dictionary, DictionaryRegistry, ReadDecompressor, WriteCompressor};
+/// use std::io::prelude::*;
+///
+/// let dict = b"The quick brown fox jumps over the lazy dog.";
+///
+/// let mut w = WriteCompressor::with_dict(
+///     Vec::new(),
+///     Default::default(),
+///     Dictionary::with_id(dict, 42)?,
+/// )?;
+/// w.write_all(b"The quick brown fox")?;
+/// let comp = w.finish()?;
+///
+/// let mut registry = DictionaryRegistry::new();
+/// registry.insert(42, dict);
+///
+/// let mut r = ReadDecompressor::new(&comp[..])?;
+/// r.set_dict_registry(registry);
+/// let mut buf = Vec::new();
+/// r.read_to_end(&mut buf)?;
+///
+/// assert_eq!(&buf[..], b"The quick brown fox");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`FrameDecoder`]: struct.FrameDecoder.html
+/// [`decompress_with_registry`]: fn.decompress_with_registry.html
+/// [`decompress_to_vec_with_registry`]: fn.decompress_to_vec_with_registry.html
+#[derive(Default, Clone)]
+pub struct DictionaryRegistry {
+    dicts: HashMap<u32, Arc<[u8]>>,
+}
+
+impl DictionaryRegistry {
+    /// Creates an empty `DictionaryRegistry`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a dictionary, replacing the previous one with the same ID.
+    ///
+    /// Frames with the ID `0` have no dictionary, so the ID `0` is never looked up.
+    pub fn insert(&mut self, dict_id: u32, data: &[u8]) {
+        self.dicts.insert(dict_id, data.into());
+    }
+
+    /// Removes a dictionary. Returns `true` if it was registered.
+    pub fn remove(&mut self, dict_id: u32) -> bool {
+        self.dicts.remove(&dict_id).is_some()
+    }
+
+    /// Returns the dictionary data for `dict_id`.
+    pub fn get(&self, dict_id: u32) -> Option<&[u8]> {
+        self.dicts.get(&dict_id).map(|data| &data[..])
+    }
+
+    /// Returns `true` if a dictionary is registered for `dict_id`.
+    pub fn contains(&self, dict_id: u32) -> bool {
+        self.dicts.contains_key(&dict_id)
+    }
+
+    /// Returns the number of registered dictionaries.
+    pub fn len(&self) -> usize {
+        self.dicts.len()
+    }
+
+    /// Returns `true` if no dictionary is registered.
+    pub fn is_empty(&self) -> bool {
+        self.dicts.is_empty()
+    }
+
+    pub(crate) fn get_shared(&self, dict_id: u32) -> Option<Arc<[u8]>> {
+        self.dicts.get(&dict_id).cloned()
+    }
+}
+
+impl fmt::Debug for DictionaryRegistry {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids = self.dicts.keys().collect::<Vec<_>>();
+        ids.sort();
+        fmt.debug_struct("DictionaryRegistry")
+            .field("dict_ids", &ids)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Dictionary;
@@ -24,5 +134,6 @@ mod tests {
     fn create_dictionary() {
         assert!(Dictionary::new(&[]).is_ok());
         assert!(Dictionary::new(&b"quick brown fox jumps over the lazy dog"[..]).is_ok());
+        assert_eq!(Dictionary::with_id(&[], 7).unwrap().dict_id(), 7);
     }
 }
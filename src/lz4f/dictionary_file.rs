@@ -0,0 +1,159 @@
+use super::{Dictionary, FrameInfo, Result};
+use crate::{common::xxh32, Error, ErrorKind, TrainedDictionary};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+const DICTIONARY_MAGIC: [u8; 4] = *b"LZ4D";
+const DICTIONARY_HEADER_SIZE: usize = 16;
+
+/// Dictionary data with its dictionary ID, in a self-describing file format.
+///
+/// The format consists of a 16-byte header followed by the dictionary data.
+/// The fields of the header are little-endian.
+///
+/// | Offset | Size | Field                            |
+/// |--------|------|----------------------------------|
+/// | 0      | 4    | Magic number (`LZ4D`)            |
+/// | 4      | 4    | Dictionary ID                    |
+/// | 8      | 4    | Length of the data               |
+/// | 12     | 4    | XXH32 checksum of the data       |
+/// | 16     |      | Data                             |
+///
+/// # Example
+///
+/// ```
+/// # use std::env;
+/// # use assert_fs::prelude::*;
+/// # let tmp_dir = assert_fs::TempDir::new().unwrap().into_persistent();
+/// # env::set_current_dir(tmp_dir.path()).unwrap();
+/// use lzzzz::lz4f::{DictionaryFile, ReadDecompressor, WriteCompressor};
+/// use std::io::prelude::*;
+///
+/// DictionaryFile::new(42, &b"The quick brown fox jumps over the lazy dog."[..]).save("foo.dict")?;
+///
+/// let dict = DictionaryFile::load("foo.dict")?;
+/// let mut w = WriteCompressor::with_dict(Vec::new(), Default::default(), dict.dictionary()?)?;
+/// w.write_all(b"The quick brown fox")?;
+/// let comp = w.finish()?;
+///
+/// let mut r = ReadDecompressor::new(&comp[..])?;
+/// dict.check_frame(&r.read_frame_info()?)?;
+/// r.set_dict(dict.as_bytes());
+/// let mut buf = Vec::new();
+/// r.read_to_end(&mut buf)?;
+///
+/// assert_eq!(&buf[..], b"The quick brown fox");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DictionaryFile {
+    dict_id: u32,
+    data: Vec<u8>,
+}
+
+impl DictionaryFile {
+    /// Creates a new `DictionaryFile`.
+    pub fn new<D: Into<Vec<u8>>>(dict_id: u32, data: D) -> Self {
+        Self {
+            dict_id,
+            data: data.into(),
+        }
+    }
+
+    /// Parses a dictionary file.
+    ///
+    /// Returns `DictionaryInvalid` if the magic number, the length or the checksum is wrong.
+    pub fn from_bytes(src: &[u8]) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::DictionaryInvalid);
+        if src.len() < DICTIONARY_HEADER_SIZE || src[..4] != DICTIONARY_MAGIC {
+            return Err(invalid().into());
+        }
+        let field = |offset: usize| {
+            u32::from_le_bytes([
+                src[offset],
+                src[offset + 1],
+                src[offset + 2],
+                src[offset + 3],
+            ])
+        };
+        let data = &src[DICTIONARY_HEADER_SIZE..];
+        if field(8) as usize != data.len() || field(12) != xxh32(data) {
+            return Err(invalid().into());
+        }
+        Ok(Self::new(field(4), data))
+    }
+
+    /// Serializes the dictionary into the file format.
+    ///
+    /// Returns `DictionaryInvalid` if the data is too large for the 32-bit length field.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let len = u32::try_from(self.data.len())
+            .map_err(|_| Error::new(ErrorKind::DictionaryInvalid))?;
+        let mut buf = Vec::with_capacity(DICTIONARY_HEADER_SIZE + self.data.len());
+        buf.extend_from_slice(&DICTIONARY_MAGIC);
+        buf.extend_from_slice(&self.dict_id.to_le_bytes());
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(&xxh32(&self.data).to_le_bytes());
+        buf.extend_from_slice(&self.data);
+        Ok(buf)
+    }
+
+    /// Reads a dictionary file from `reader`.
+    pub fn read_from<R: Read>(mut reader: R) -> std::io::Result<Self> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(Self::from_bytes(&buf)?)
+    }
+
+    /// Writes the dictionary file to `writer`.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes()?)
+    }
+
+    /// Loads a dictionary file from `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::from_bytes(&fs::read(path)?)?)
+    }
+
+    /// Saves the dictionary file to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        fs::write(path, self.to_bytes()?)
+    }
+
+    /// Returns the dictionary ID.
+    pub const fn dict_id(&self) -> u32 {
+        self.dict_id
+    }
+
+    /// Returns the dictionary data for the decompressors.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Builds a [`Dictionary`] for the compressors, which writes the dictionary ID to the frame header.
+    ///
+    /// [`Dictionary`]: struct.Dictionary.html
+    pub fn dictionary(&self) -> Result<Dictionary> {
+        Dictionary::with_id(&self.data, self.dict_id)
+    }
+
+    /// Checks that a frame was compressed with this dictionary.
+    ///
+    /// Returns `DictionaryMismatch` if the `dict_id` of the frame differs from the dictionary ID.
+    pub fn check_frame(&self, frame: &FrameInfo) -> Result<()> {
+        if frame.dict_id() == self.dict_id {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::DictionaryMismatch).into())
+        }
+    }
+}
+
+impl From<TrainedDictionary> for DictionaryFile {
+    fn from(dict: TrainedDictionary) -> Self {
+        Self::new(dict.dict_id(), dict.into_bytes())
+    }
+}
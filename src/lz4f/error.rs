@@ -24,6 +24,9 @@ pub enum ErrorKind {
     HeaderChecksumInvalid,
     ContentChecksumInvalid,
     FrameDecodingAlreadyStarted,
+    ContentChecksumMissing,
+    BlockChecksumMissing,
+    TrailingData,
 }
 
 impl fmt::Display for ErrorKind {
@@ -43,11 +46,23 @@ impl Error {
     pub(super) const fn new(kind: ErrorKind) -> Self {
         Self::Lz4f(kind)
     }
+
+    /// Takes back the error wrapped in an `io::Error` by the readers working on slices.
+    pub(super) fn from_io(err: io::Error) -> Self {
+        let inner = err.get_ref();
+        if let Some(err) = inner.and_then(|err| err.downcast_ref::<Self>()) {
+            *err
+        } else if let Some(err) = inner.and_then(|err| err.downcast_ref::<crate::Error>()) {
+            (*err).into()
+        } else {
+            Self::new(ErrorKind::Generic)
+        }
+    }
 }
 
 impl convert::From<Error> for io::Error {
     fn from(err: Error) -> Self {
-        Self::new(io::ErrorKind::Other, err)
+        Self::other(err)
     }
 }
 
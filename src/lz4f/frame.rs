@@ -1,8 +1,14 @@
 //! LZ4 Frame Compressor/Decompressor
 
-use super::{api, Result};
-use crate::{common::DEFAULT_BUF_SIZE, lz4f::Preferences, Error, ErrorKind};
-use std::{cell::RefCell, ops::Deref};
+use super::{
+    api, DecompressionLimits, Dictionary, DictionaryRegistry, FrameType, Result, ValidationPolicy,
+};
+use crate::{
+    common::DEFAULT_BUF_SIZE,
+    lz4f::{self, Preferences},
+    Error, ErrorKind,
+};
+use std::{cell::RefCell, cmp, ops::Deref};
 
 /// Calculates the maximum size of the compressed output.
 ///
@@ -95,10 +101,145 @@ pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, prefs: &Preferences) -> Re
     }
 }
 
+/// Appends a frame compressed with a dictionary to `Vec<u8>`.
+///
+/// The dictionary ID of `dict` is written to the frame header
+/// unless `prefs` specifies another one.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let dict = b"The quick brown fox jumps over the lazy dog.";
+/// let data = b"The quick brown fox";
+/// let mut buf = Vec::new();
+///
+/// lz4f::compress_to_vec_with_dict(
+///     data,
+///     &mut buf,
+///     &Default::default(),
+///     &lz4f::Dictionary::new(dict)?,
+/// )?;
+/// # let comp = buf;
+/// # let mut buf = Vec::new();
+/// # lz4f::decompress_to_vec_with_dict(&comp, &mut buf, dict)?;
+/// # assert_eq!(buf.as_slice(), &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn compress_to_vec_with_dict(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    prefs: &Preferences,
+    dict: &Dictionary,
+) -> Result<usize> {
+    let mut prefs = *prefs;
+    if prefs.frame_info().content_size() > 0 {
+        prefs.set_content_size(src.len());
+    }
+    if prefs.frame_info().dict_id() == 0 {
+        prefs.set_dict_id(dict.dict_id());
+    }
+
+    let orig_len = dst.len();
+    dst.reserve(max_compressed_size(src.len(), &prefs));
+    CompressionCtx::with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        ctx.set_dict(Some(dict.clone()));
+        #[allow(unsafe_code)]
+        let result = unsafe {
+            let result = ctx.compress_frame(
+                src,
+                dst.as_mut_ptr().add(orig_len),
+                dst.capacity() - orig_len,
+                &prefs,
+            );
+            dst.set_len(orig_len + result.as_ref().unwrap_or(&0));
+            result
+        };
+        ctx.set_dict(None);
+        result
+    })
+}
+
+/// Decompresses an LZ4 frame into a slice.
+///
+/// This is useful when the original size is already known.
+/// The frame is decoded in a single pass without internal buffering,
+/// and the length of `dst` bounds the decompressed size.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// const ORIGINAL_SIZE: usize = 44;
+/// const COMPRESSED_DATA: &str =
+///     "BCJNGGBAgiwAAIBUaGUgcXVpY2sgYnJvd24gZm94IGp1bXBzIG92ZXIgdGhlIGxhenkgZG9nLgAAAAA=";
+///
+/// let data = base64::decode(COMPRESSED_DATA).unwrap();
+/// let mut buf = [0u8; ORIGINAL_SIZE];
+///
+/// let len = lz4f::decompress(&data[..], &mut buf[..])?;
+///
+/// assert_eq!(
+///     &buf[..len],
+///     &b"The quick brown fox jumps over the lazy dog."[..]
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress(src: &[u8], dst: &mut [u8]) -> Result<usize> {
+    decompress_with_consumed(src, dst).map(|(_, dst_len)| dst_len)
+}
+
+/// Decompresses an LZ4 frame, returning a tuple `(consumed, produced)`.
+pub(super) fn decompress_with_consumed(src: &[u8], dst: &mut [u8]) -> Result<(usize, usize)> {
+    DecompressionCtx::with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        ctx.reset();
+        decompress_stable(&mut ctx, src, dst, &[])
+    })
+}
+
+/// Decompresses an LZ4 frame into a slice, looking up the dictionary in `registry`.
+///
+/// This works like [`decompress`], but if the frame header has a non-zero `dict_id`,
+/// the dictionary is taken from `registry`. An unknown ID is reported as
+/// `DictionaryNotFound`.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// [`decompress`]: fn.decompress.html
+pub fn decompress_with_registry(
+    src: &[u8],
+    dst: &mut [u8],
+    registry: &DictionaryRegistry,
+) -> Result<usize> {
+    let dict = registry_dict(src, registry)?;
+    DecompressionCtx::with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        ctx.reset();
+        decompress_stable(&mut ctx, src, dst, dict).map(|(_, dst_len)| dst_len)
+    })
+}
+
 /// Decompresses an LZ4 frame.
 ///
+/// If the frame header has the content size field,
+/// the exact amount of memory is allocated in advance, up to 255 times the size of `src`.
+/// Use [`decompress_to_vec_with_limits`] with a `max_preallocation` limit to lower the cap.
+/// Data after the frame is ignored.
+/// Use [`decompress_to_vec_with_consumed`] to find where the frame ended.
+///
 /// Returns the number of bytes appended to the given `Vec<u8>`.
 ///
+/// [`decompress_to_vec_with_limits`]: fn.decompress_to_vec_with_limits.html
+/// [`decompress_to_vec_with_consumed`]: fn.decompress_to_vec_with_consumed.html
+///
 /// # Example
 ///
 /// ```
@@ -119,35 +260,347 @@ pub fn compress_to_vec(src: &[u8], dst: &mut Vec<u8>, prefs: &Preferences) -> Re
 /// # Ok::<(), std::io::Error>(())
 /// ```
 pub fn decompress_to_vec(src: &[u8], dst: &mut Vec<u8>) -> Result<usize> {
+    decompress_to_vec_with_limits(src, dst, &Default::default())
+}
+
+/// Decompresses an LZ4 frame compressed with a dictionary.
+///
+/// This works like [`decompress_to_vec`], but uses `dict` as the dictionary.
+/// `dict` must be the data the frame was compressed with.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, Dictionary, WriteCompressor};
+/// use std::io::prelude::*;
+///
+/// let dict = b"The quick brown fox jumps over the lazy dog.";
+/// let mut w = WriteCompressor::with_dict(Vec::new(), Default::default(), Dictionary::new(dict)?)?;
+/// w.write_all(b"The quick brown fox")?;
+/// let comp = w.finish()?;
+///
+/// let mut buf = Vec::new();
+/// lz4f::decompress_to_vec_with_dict(&comp, &mut buf, dict)?;
+/// assert_eq!(&buf[..], b"The quick brown fox");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_dict(src: &[u8], dst: &mut Vec<u8>, dict: &[u8]) -> Result<usize> {
+    decompress_to_vec_checked(src, dst, dict, &Default::default(), &Default::default())
+        .map(|(_, len)| len)
+}
+
+/// Decompresses an LZ4 frame, looking up the dictionary in `registry`.
+///
+/// This works like [`decompress_to_vec`], but if the frame header has a non-zero `dict_id`,
+/// the dictionary is taken from `registry`. An unknown ID is reported as
+/// `DictionaryNotFound`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, Dictionary, DictionaryRegistry, WriteCompressor};
+/// use std::io::prelude::*;
+///
+/// let dict = b"The quick brown fox jumps over the lazy dog.";
+/// let mut w = WriteCompressor::with_dict(
+///     Vec::new(),
+///     Default::default(),
+///     Dictionary::with_id(dict, 42)?,
+/// )?;
+/// w.write_all(b"The quick brown fox")?;
+/// let comp = w.finish()?;
+///
+/// let mut registry = DictionaryRegistry::new();
+/// registry.insert(42, dict);
+///
+/// let mut buf = Vec::new();
+/// lz4f::decompress_to_vec_with_registry(&comp, &mut buf, &registry)?;
+/// assert_eq!(&buf[..], b"The quick brown fox");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_registry(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    registry: &DictionaryRegistry,
+) -> Result<usize> {
+    let dict = registry_dict(src, registry)?;
+    decompress_to_vec_checked(src, dst, dict, &Default::default(), &Default::default())
+        .map(|(_, len)| len)
+}
+
+/// Looks up the dictionary for the frame header at the beginning of `src`.
+///
+/// A malformed header yields an empty dictionary, so the error is reported
+/// by the decompression itself.
+fn registry_dict<'r>(src: &[u8], registry: &'r DictionaryRegistry) -> Result<&'r [u8]> {
+    let dict_id = DecompressionCtx::with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        ctx.reset();
+        let dict_id = ctx.get_frame_info(src).map(|(info, _)| info.dict_id());
+        ctx.reset();
+        dict_id.unwrap_or(0)
+    });
+    if dict_id == 0 {
+        return Ok(&[]);
+    }
+    registry
+        .get(dict_id)
+        .ok_or_else(|| Error::new(ErrorKind::DictionaryNotFound).into())
+}
+
+/// Decompresses an LZ4 frame with limits.
+///
+/// This works like [`decompress_to_vec`], but fails with
+/// `DecompressionLimitExceeded` if the frame goes over `limits`.
+/// A frame header declaring a larger content size is rejected before any allocation,
+/// which protects against malicious headers.
+///
+/// Like [`decompress_to_vec`], only the first frame of `src` is decompressed,
+/// so `max_frames` only matters when it is set to 0, which rejects any input.
+/// Use the streaming decompressors to limit the number of concatenated frames.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+///
+/// let mut buf = Vec::new();
+/// let limits = lz4f::DecompressionLimitsBuilder::new()
+///     .max_output_size(16)
+///     .build();
+/// assert!(lz4f::decompress_to_vec_with_limits(&comp, &mut buf, &limits).is_err());
+///
+/// let limits = lz4f::DecompressionLimitsBuilder::new()
+///     .max_output_size(1024)
+///     .build();
+/// lz4f::decompress_to_vec_with_limits(&comp, &mut buf, &limits)?;
+/// assert_eq!(&buf[..], &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_limits(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    limits: &DecompressionLimits,
+) -> Result<usize> {
+    decompress_to_vec_checked(src, dst, &[], limits, &Default::default()).map(|(_, len)| len)
+}
+
+/// Decompresses an LZ4 frame with a validation policy.
+///
+/// This works like [`decompress_to_vec`], but fails if the frame violates `policy`.
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+/// comp.extend_from_slice(b"garbage");
+///
+/// let mut buf = Vec::new();
+/// let policy = lz4f::ValidationPolicyBuilder::new()
+///     .reject_trailing_data(true)
+///     .build();
+/// assert!(lz4f::decompress_to_vec_with_policy(&comp, &mut buf, &policy).is_err());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_policy(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    policy: &ValidationPolicy,
+) -> Result<usize> {
+    decompress_to_vec_checked(src, dst, &[], &Default::default(), policy).map(|(_, len)| len)
+}
+
+/// Decompresses an LZ4 frame and reports where it ended.
+///
+/// This works like [`decompress_to_vec`], but returns a tuple `(consumed, produced)`
+/// giving the number of bytes read from `src` and the number of bytes
+/// appended to the given `Vec<u8>`.
+/// Data after the frame is left untouched, so the next frame or any other data
+/// starts at `src[consumed..]`.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+/// let frame_len = comp.len();
+/// comp.extend_from_slice(b"trailer");
+///
+/// let mut buf = Vec::new();
+/// let (consumed, produced) = lz4f::decompress_to_vec_with_consumed(&comp, &mut buf)?;
+///
+/// assert_eq!(consumed, frame_len);
+/// assert_eq!(produced, data.len());
+/// assert_eq!(&comp[consumed..], b"trailer");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_consumed(src: &[u8], dst: &mut Vec<u8>) -> Result<(usize, usize)> {
+    decompress_to_vec_checked(src, dst, &[], &Default::default(), &Default::default())
+}
+
+fn decompress_to_vec_checked(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dict: &[u8],
+    limits: &DecompressionLimits,
+    policy: &ValidationPolicy,
+) -> Result<(usize, usize)> {
     let header_len = dst.len();
-    let mut src_offset = 0;
-    let mut dst_offset = header_len;
     DecompressionCtx::with(|ctx| {
         let mut ctx = ctx.borrow_mut();
-        ctx.reset();
-        loop {
-            dst.resize_with(dst.len() + DEFAULT_BUF_SIZE, Default::default);
-            match ctx.decompress_dict(&src[src_offset..], &mut dst[dst_offset..], &[], false) {
-                Ok((src_len, dst_len, expected)) => {
-                    src_offset += src_len;
-                    dst_offset += dst_len;
-                    if expected == 0 {
-                        dst.resize_with(dst_offset, Default::default);
-                        return Ok(dst_offset - header_len);
-                    } else if src_offset >= src.len() {
-                        dst.resize_with(header_len, Default::default);
-                        return Err(Error::new(ErrorKind::CompressedDataIncomplete).into());
-                    }
-                }
-                Err(err) => {
-                    dst.resize_with(header_len, Default::default);
-                    return Err(err);
-                }
+        match decompress_frame(&mut ctx, src, dst, dict, limits, policy) {
+            Ok((src_len, dst_len)) => {
+                dst.resize_with(header_len + dst_len, Default::default);
+                Ok((src_len, dst_len))
+            }
+            Err(err) => {
+                dst.resize_with(header_len, Default::default);
+                Err(err)
             }
         }
     })
 }
 
+fn decompress_frame(
+    ctx: &mut api::DecompressionContext,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dict: &[u8],
+    limits: &DecompressionLimits,
+    policy: &ValidationPolicy,
+) -> Result<(usize, usize)> {
+    let header_len = dst.len();
+    let max_len = limits.max_output_size();
+    policy.check_header(src)?;
+
+    ctx.reset();
+    let info = ctx.get_frame_info(src).map(|(info, _)| info);
+    ctx.reset();
+
+    let content_size = match info {
+        Ok(info) => {
+            policy.check_frame(&info)?;
+            if info.frame_type() == FrameType::Frame {
+                info.content_size()
+            } else {
+                0
+            }
+        }
+        Err(_) => 0,
+    };
+    limits.check_frame(1, content_size)?;
+    if content_size > max_len {
+        return Err(Error::new(ErrorKind::DecompressionLimitExceeded).into());
+    }
+
+    let max_prealloc = cmp::min(content_size_bound(src.len()), limits.max_preallocation());
+    let (src_len, dst_len) = if content_size > 0 && content_size <= max_prealloc {
+        dst.resize_with(header_len + content_size, Default::default);
+        decompress_stable(ctx, src, &mut dst[header_len..], dict).map_err(|err| {
+            if err == lz4f::Error::new(lz4f::ErrorKind::DstMaxSizeTooSmall) {
+                lz4f::Error::new(lz4f::ErrorKind::FrameSizeWrong)
+            } else {
+                err
+            }
+        })?
+    } else {
+        // The content size is unknown or cannot be trusted,
+        // so the buffer grows gradually.
+        decompress_buffered(ctx, src, dst, dict, max_len)?
+    };
+    policy.check_trailing_data(src.len() - src_len)?;
+    Ok((src_len, dst_len))
+}
+
+/// The decompressed size of a single frame cannot exceed this bound
+/// because an LZ4 sequence expands to 255 bytes per input byte at most.
+fn content_size_bound(src_len: usize) -> usize {
+    src_len.saturating_mul(255)
+}
+
+fn decompress_stable(
+    ctx: &mut api::DecompressionContext,
+    src: &[u8],
+    dst: &mut [u8],
+    dict: &[u8],
+) -> Result<(usize, usize)> {
+    let mut src_offset = 0;
+    let mut dst_offset = 0;
+    loop {
+        let (src_len, dst_len, expected) =
+            ctx.decompress_dict(&src[src_offset..], &mut dst[dst_offset..], dict, true)?;
+        src_offset += src_len;
+        dst_offset += dst_len;
+        if expected == 0 {
+            return Ok((src_offset, dst_offset));
+        } else if src_offset >= src.len() {
+            return Err(Error::new(ErrorKind::CompressedDataIncomplete).into());
+        } else if src_len == 0 && dst_len == 0 {
+            return Err(lz4f::Error::new(lz4f::ErrorKind::DstMaxSizeTooSmall));
+        }
+    }
+}
+
+fn decompress_buffered(
+    ctx: &mut api::DecompressionContext,
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    dict: &[u8],
+    max_len: usize,
+) -> Result<(usize, usize)> {
+    let header_len = dst.len();
+    let mut src_offset = 0;
+    let mut dst_offset = header_len;
+    loop {
+        let len = cmp::min(DEFAULT_BUF_SIZE, max_len - (dst_offset - header_len));
+        dst.resize_with(dst_offset + len, Default::default);
+        let (src_len, dst_len, expected) =
+            ctx.decompress_dict(&src[src_offset..], &mut dst[dst_offset..], dict, false)?;
+        src_offset += src_len;
+        dst_offset += dst_len;
+        if expected == 0 {
+            return Ok((src_offset, dst_offset - header_len));
+        } else if src_offset >= src.len() {
+            return Err(Error::new(ErrorKind::CompressedDataIncomplete).into());
+        } else if src_len == 0 && dst_len == 0 {
+            return Err(if dst_offset - header_len >= max_len {
+                Error::new(ErrorKind::DecompressionLimitExceeded).into()
+            } else {
+                lz4f::Error::new(lz4f::ErrorKind::DstMaxSizeTooSmall)
+            });
+        }
+    }
+}
+
 struct DecompressionCtx(RefCell<api::DecompressionContext>);
 
 impl DecompressionCtx {
@@ -171,4 +624,28 @@ impl Deref for DecompressionCtx {
     }
 }
 
+struct CompressionCtx(RefCell<api::CompressionContext>);
+
+impl CompressionCtx {
+    fn new() -> Self {
+        Self(RefCell::new(api::CompressionContext::new(None).unwrap()))
+    }
+
+    fn with<F, R>(f: F) -> R
+    where
+        F: FnOnce(&RefCell<api::CompressionContext>) -> R,
+    {
+        COMPRESSION_CTX.with(|state| (f)(state))
+    }
+}
+
+impl Deref for CompressionCtx {
+    type Target = RefCell<api::CompressionContext>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+thread_local!(static COMPRESSION_CTX: CompressionCtx = CompressionCtx::new());
 thread_local!(static DECOMPRESSION_CTX: DecompressionCtx = DecompressionCtx::new());
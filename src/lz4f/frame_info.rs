@@ -1,81 +1,162 @@
 use std::os::raw::{c_uint, c_ulonglong};
 
+/// Implements `FromStr` for a flag enum, matching the names case-insensitively,
+/// and the `TryFrom<String>` used for its deserialization.
+macro_rules! flag_from_str {
+    ($ty:ident { $($name:literal => $variant:ident),+ $(,)? }) => {
+        impl std::str::FromStr for $ty {
+            type Err = $crate::lz4f::Error;
+
+            fn from_str(s: &str) -> $crate::lz4f::Result<Self> {
+                [$(($name, $ty::$variant)),+]
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(s))
+                    .map(|(_, flag)| *flag)
+                    .ok_or_else(|| {
+                        $crate::Error::new($crate::ErrorKind::PresetInvalid).into()
+                    })
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl TryFrom<String> for $ty {
+            type Error = $crate::lz4f::Error;
+
+            fn try_from(s: String) -> $crate::lz4f::Result<Self> {
+                s.parse()
+            }
+        }
+    };
+}
+pub(super) use flag_from_str;
+
 /// Block size flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as
+/// `"default"`, `"64KB"`, `"256KB"`, `"1MB"` and `"4MB"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String")
+)]
 #[non_exhaustive]
 #[repr(C)]
 pub enum BlockSize {
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "default"))]
     Default = 0,
+    #[cfg_attr(feature = "serde", serde(rename = "64KB"))]
     Max64KB = 4,
+    #[cfg_attr(feature = "serde", serde(rename = "256KB"))]
     Max256KB = 5,
+    #[cfg_attr(feature = "serde", serde(rename = "1MB"))]
     Max1MB = 6,
+    #[cfg_attr(feature = "serde", serde(rename = "4MB"))]
     Max4MB = 7,
 }
 
-impl Default for BlockSize {
-    fn default() -> Self {
-        Self::Default
-    }
-}
+flag_from_str!(BlockSize {
+    "default" => Default,
+    "64KB" => Max64KB,
+    "256KB" => Max256KB,
+    "1MB" => Max1MB,
+    "4MB" => Max4MB,
+});
 
 /// Block mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as `"linked"` and `"independent"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase", try_from = "String")
+)]
 #[repr(C)]
 pub enum BlockMode {
+    #[default]
     Linked,
     Independent,
 }
 
-impl Default for BlockMode {
-    fn default() -> Self {
-        Self::Linked
-    }
-}
+flag_from_str!(BlockMode {
+    "linked" => Linked,
+    "independent" => Independent,
+});
 
 /// Content checksum flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as `"disabled"` and `"enabled"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase", try_from = "String")
+)]
 #[repr(C)]
 pub enum ContentChecksum {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for ContentChecksum {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
+flag_from_str!(ContentChecksum {
+    "disabled" => Disabled,
+    "enabled" => Enabled,
+});
 
 /// Frame type flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as `"frame"` and `"skippable"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String")
+)]
 #[repr(C)]
 pub enum FrameType {
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "frame"))]
     Frame,
+    #[cfg_attr(feature = "serde", serde(rename = "skippable"))]
     SkippableFrame,
 }
 
-impl Default for FrameType {
-    fn default() -> Self {
-        Self::Frame
-    }
-}
+flag_from_str!(FrameType {
+    "frame" => Frame,
+    "skippable" => SkippableFrame,
+});
 
 /// Block checksum flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as `"disabled"` and `"enabled"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase", try_from = "String")
+)]
 #[repr(C)]
 pub enum BlockChecksum {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for BlockChecksum {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
+flag_from_str!(BlockChecksum {
+    "disabled" => Disabled,
+    "enabled" => Enabled,
+});
 
 /// LZ4 Frame parameters.
+///
+/// With the `serde` feature, this is represented as a map of the fields.
+/// Missing fields take their default values.
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 #[repr(C)]
 pub struct FrameInfo {
     block_size: BlockSize,
@@ -135,6 +216,10 @@ impl FrameInfo {
         self.content_checksum = checksum;
     }
 
+    pub(super) fn set_frame_type(&mut self, frame_type: FrameType) {
+        self.frame_type = frame_type;
+    }
+
     pub(super) fn set_content_size(&mut self, size: usize) {
         self.content_size = size as c_ulonglong;
     }
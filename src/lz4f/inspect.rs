@@ -0,0 +1,430 @@
+//! Inspection of LZ4 frames.
+
+use super::{
+    api::{
+        header_size, DecompressionContext, LZ4F_HEADER_SIZE_MAX,
+        LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH,
+    },
+    recovery::{
+        incomplete, max_block_size, LZ4F_BLOCK_UNCOMPRESSED_FLAG, LZ4F_MAGIC_NUMBER,
+        LZ4F_MAGIC_SKIPPABLE_START,
+    },
+    BlockChecksum, BlockMode, ContentChecksum, Error, ErrorKind, FrameInfo, FrameType, Result,
+};
+use crate::{
+    common::{xxh32, Xxh32, DICTIONARY_SIZE},
+    lz4,
+};
+use std::io::{self, BufRead, Read};
+
+/// Result of a checksum verification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ChecksumStatus {
+    /// The frame has no checksum of this kind.
+    Disabled,
+    /// Every checksum matches the data.
+    Valid,
+    /// At least one checksum does not match the data.
+    Invalid,
+    /// The checksum could not be verified because the data could not be decoded.
+    Unverified,
+}
+
+/// A summary of a frame, returned by [`inspect`].
+///
+/// [`inspect`]: fn.inspect.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FrameSummary {
+    offset: usize,
+    len: usize,
+    magic: u32,
+    frame_info: FrameInfo,
+    block_count: usize,
+    uncompressed_size: Option<usize>,
+    block_checksum: ChecksumStatus,
+    content_checksum: ChecksumStatus,
+    error: Option<Error>,
+}
+
+impl FrameSummary {
+    /// Returns the offset of the frame in the inspected data.
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the length of the frame in bytes, including the header and the checksums.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the frame is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the magic number of the frame.
+    pub const fn magic(&self) -> u32 {
+        self.magic
+    }
+
+    /// Returns the frame type.
+    pub const fn frame_type(&self) -> FrameType {
+        self.frame_info.frame_type()
+    }
+
+    /// Returns the header fields of the frame.
+    ///
+    /// Skippable frames have no header fields, so the default values are returned.
+    pub const fn frame_info(&self) -> FrameInfo {
+        self.frame_info
+    }
+
+    /// Returns the number of data blocks, not counting the end mark.
+    pub const fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// Returns the decompressed size of the frame.
+    ///
+    /// Frames which need a dictionary are not decoded, so this is the content size
+    /// stored in the header, or `None` if the header has none.
+    /// Skippable frames have no decompressed data and return `Some(0)`.
+    pub const fn uncompressed_size(&self) -> Option<usize> {
+        self.uncompressed_size
+    }
+
+    /// Returns the verification result of the block checksums.
+    pub const fn block_checksum(&self) -> ChecksumStatus {
+        self.block_checksum
+    }
+
+    /// Returns the verification result of the content checksum.
+    pub const fn content_checksum(&self) -> ChecksumStatus {
+        self.content_checksum
+    }
+
+    /// Returns the error which stopped the decoding of the frame, if any.
+    ///
+    /// A damaged frame is inspected up to the error,
+    /// and the inspection stops after it.
+    pub const fn error(&self) -> Option<Error> {
+        self.error
+    }
+}
+
+/// Lists the frames in `src`.
+///
+/// Every frame is decoded to count its blocks and to verify its checksums,
+/// so the cost is comparable to [`decompress_to_vec`], but the decoded data is not kept.
+/// Linked blocks are decoded against the last 64 KB of the preceding data,
+/// so the memory usage is bounded by the block size of the frames.
+/// Use [`inspect_reader`] to inspect data which is not in memory.
+///
+/// Frames which need a dictionary are walked through without being decoded.
+/// Legacy frames, as written by `lz4 -l`, are not supported
+/// and are reported as data which is not a frame.
+///
+/// A damaged frame is reported with [`FrameSummary::error`] and ends the list.
+/// Data which is not a frame is reported as a damaged frame
+/// with the `FrameTypeUnknown` error.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+/// [`inspect_reader`]: fn.inspect_reader.html
+/// [`FrameSummary::error`]: struct.FrameSummary.html#method.error
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, BlockSize, ChecksumStatus, FrameType, PreferencesBuilder};
+///
+/// let data = [b'a'; 100_000];
+/// let prefs = PreferencesBuilder::new()
+///     .block_size(BlockSize::Max64KB)
+///     .build();
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(&data, &mut comp, &prefs)?;
+/// comp.extend_from_slice(&[0x50, 0x2a, 0x4d, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+///
+/// let frames = lz4f::inspect(&comp)?;
+/// assert_eq!(frames.len(), 2);
+/// assert_eq!(frames[0].frame_type(), FrameType::Frame);
+/// assert_eq!(frames[0].block_count(), 2);
+/// assert_eq!(frames[0].uncompressed_size(), Some(data.len()));
+/// assert_eq!(frames[0].content_checksum(), ChecksumStatus::Disabled);
+/// assert_eq!(frames[1].frame_type(), FrameType::SkippableFrame);
+/// assert_eq!(frames[1].len(), 11);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn inspect(src: &[u8]) -> Result<Vec<FrameSummary>> {
+    Inspector::new(src)?.frames().map_err(Error::from_io)
+}
+
+/// Lists the frames read from `reader`.
+///
+/// This works like [`inspect`], but the data is read incrementally
+/// instead of being loaded into memory.
+///
+/// [`inspect`]: fn.inspect.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+/// use std::io::BufReader;
+///
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(b"Hello world!", &mut comp, &Default::default())?;
+///
+/// let frames = lz4f::inspect_reader(BufReader::new(&comp[..]))?;
+/// assert_eq!(frames.len(), 1);
+/// assert_eq!(frames[0].len(), comp.len());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn inspect_reader<R: BufRead>(reader: R) -> io::Result<Vec<FrameSummary>> {
+    Inspector::new(reader)?.frames()
+}
+
+fn empty_summary(offset: usize, magic: u32) -> FrameSummary {
+    FrameSummary {
+        offset,
+        len: 0,
+        magic,
+        frame_info: FrameInfo::default(),
+        block_count: 0,
+        uncompressed_size: None,
+        block_checksum: ChecksumStatus::Disabled,
+        content_checksum: ChecksumStatus::Disabled,
+        error: None,
+    }
+}
+
+struct Inspector<R> {
+    reader: R,
+    ctx: DecompressionContext,
+    offset: usize,
+}
+
+impl<R: BufRead> Inspector<R> {
+    fn new(reader: R) -> Result<Self> {
+        Ok(Self {
+            reader,
+            ctx: DecompressionContext::new()?,
+            offset: 0,
+        })
+    }
+
+    fn frames(mut self) -> io::Result<Vec<FrameSummary>> {
+        let mut frames = Vec::new();
+        loop {
+            let offset = self.offset;
+            let mut magic = [0; 4];
+            let summary = match self.read(&mut magic)? {
+                0 => break,
+                4 => match u32::from_le_bytes(magic) {
+                    LZ4F_MAGIC_NUMBER => self.frame(offset)?,
+                    magic if magic & 0xFFFF_FFF0 == LZ4F_MAGIC_SKIPPABLE_START => {
+                        self.skippable_frame(offset, magic)?
+                    }
+                    magic => Self::unknown(offset, magic),
+                },
+                _ => Self::unknown(offset, 0),
+            };
+            let damaged = summary.error.is_some();
+            frames.push(self.finish(summary)?);
+            if damaged {
+                break;
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Sets the length of the frame. A damaged frame extends to the end of the input.
+    fn finish(&mut self, mut summary: FrameSummary) -> io::Result<FrameSummary> {
+        if summary.error.is_some() {
+            self.offset += io::copy(&mut self.reader, &mut io::sink())? as usize;
+        }
+        summary.len = self.offset - summary.offset;
+        Ok(summary)
+    }
+
+    /// Reads until `buf` is full or the reader reaches EOF.
+    ///
+    /// Returns the number of bytes read.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut len = 0;
+        while len < buf.len() {
+            match self.reader.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.offset += len;
+        Ok(len)
+    }
+
+    fn read_u32(&mut self) -> io::Result<Option<u32>> {
+        let mut buf = [0; 4];
+        Ok(if self.read(&mut buf)? == buf.len() {
+            Some(u32::from_le_bytes(buf))
+        } else {
+            None
+        })
+    }
+
+    fn unknown(offset: usize, magic: u32) -> FrameSummary {
+        let mut summary = empty_summary(offset, magic);
+        summary.error = Some(Error::new(ErrorKind::FrameTypeUnknown));
+        summary
+    }
+
+    fn skippable_frame(&mut self, offset: usize, magic: u32) -> io::Result<FrameSummary> {
+        let mut summary = empty_summary(offset, magic);
+        summary.frame_info.set_frame_type(FrameType::SkippableFrame);
+        let skipped = match self.read_u32()? {
+            Some(len) => {
+                let mut data = (&mut self.reader).take(u64::from(len));
+                let skipped = io::copy(&mut data, &mut io::sink())?;
+                self.offset += skipped as usize;
+                skipped == u64::from(len)
+            }
+            None => false,
+        };
+        if skipped {
+            summary.uncompressed_size = Some(0);
+        } else {
+            summary.error = Some(incomplete());
+        }
+        Ok(summary)
+    }
+
+    fn frame(&mut self, offset: usize) -> io::Result<FrameSummary> {
+        let mut summary = empty_summary(offset, LZ4F_MAGIC_NUMBER);
+        let mut header = [0; LZ4F_HEADER_SIZE_MAX];
+        header[..4].copy_from_slice(&LZ4F_MAGIC_NUMBER.to_le_bytes());
+        let mut header_len = 4 + self.read(&mut header[4..LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH])?;
+        if header_len == LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH {
+            let exact_header_len = header_size(&header[..header_len]);
+            if exact_header_len <= LZ4F_HEADER_SIZE_MAX {
+                header_len += self.read(&mut header[header_len..exact_header_len])?;
+            }
+        }
+        self.ctx.reset();
+        let header = self.ctx.get_frame_info(&header[..header_len]);
+        self.ctx.reset();
+        match header {
+            Ok((info, _)) => summary.frame_info = info,
+            Err(err) => {
+                summary.error = Some(err);
+                return Ok(summary);
+            }
+        }
+
+        let info = summary.frame_info;
+        if info.block_checksum() == BlockChecksum::Enabled {
+            summary.block_checksum = ChecksumStatus::Valid;
+        }
+        if let Err(err) = self.blocks(&mut summary)? {
+            summary.error = Some(err);
+            if info.content_checksum() == ContentChecksum::Enabled {
+                summary.content_checksum = ChecksumStatus::Unverified;
+            }
+        }
+        if summary.uncompressed_size.is_none() && info.content_size() > 0 {
+            summary.uncompressed_size = Some(info.content_size());
+        }
+        Ok(summary)
+    }
+
+    /// Walks through the blocks and the content checksum of a frame.
+    ///
+    /// Returns the error which stopped the decoding, if any.
+    fn blocks(&mut self, summary: &mut FrameSummary) -> io::Result<Result<()>> {
+        let info = summary.frame_info;
+        let decode = info.dict_id() == 0;
+        let max_block_size = max_block_size(info.block_size());
+        let checksum_len = if info.block_checksum() == BlockChecksum::Enabled {
+            4
+        } else {
+            0
+        };
+        let mut hasher = if decode && info.content_checksum() == ContentChecksum::Enabled {
+            Some(Xxh32::new()?)
+        } else {
+            None
+        };
+        if decode {
+            summary.uncompressed_size = Some(0);
+        }
+
+        let mut block_buf = Vec::new();
+        let mut dst = Vec::new();
+        let mut window = Vec::new();
+        loop {
+            let word = match self.read_u32()? {
+                Some(word) => word,
+                None => return Ok(Err(incomplete())),
+            };
+            if word == 0 {
+                break;
+            }
+
+            let len = (word & !LZ4F_BLOCK_UNCOMPRESSED_FLAG) as usize;
+            if len > max_block_size {
+                return Ok(Err(Error::new(ErrorKind::MaxBlockSizeInvalid)));
+            }
+            block_buf.resize_with(len + checksum_len, Default::default);
+            if self.read(&mut block_buf)? < block_buf.len() {
+                return Ok(Err(incomplete()));
+            }
+
+            let (block, checksum) = block_buf.split_at(len);
+            if checksum_len > 0 && checksum != xxh32(block).to_le_bytes() {
+                summary.block_checksum = ChecksumStatus::Invalid;
+            }
+            if decode {
+                let data = if word & LZ4F_BLOCK_UNCOMPRESSED_FLAG != 0 {
+                    block
+                } else {
+                    dst.resize_with(max_block_size, Default::default);
+                    match lz4::decompress_with_dict(block, &mut dst, &window) {
+                        Ok(len) => &dst[..len],
+                        Err(err) => return Ok(Err(err.into())),
+                    }
+                };
+                if let Some(hasher) = &mut hasher {
+                    hasher.update(data);
+                }
+                if let Some(size) = &mut summary.uncompressed_size {
+                    *size += data.len();
+                }
+                if info.block_mode() == BlockMode::Linked {
+                    window.extend_from_slice(data);
+                    window.drain(..window.len().saturating_sub(DICTIONARY_SIZE));
+                }
+            }
+            summary.block_count += 1;
+        }
+
+        if decode
+            && info.content_size() > 0
+            && summary.uncompressed_size != Some(info.content_size())
+        {
+            return Ok(Err(Error::new(ErrorKind::FrameSizeWrong)));
+        }
+        if info.content_checksum() == ContentChecksum::Disabled {
+            return Ok(Ok(()));
+        }
+        let checksum = match self.read_u32()? {
+            Some(checksum) => checksum,
+            None => return Ok(Err(incomplete())),
+        };
+        summary.content_checksum = match hasher {
+            None => ChecksumStatus::Unverified,
+            Some(hasher) if hasher.digest() == checksum => ChecksumStatus::Valid,
+            Some(_) => ChecksumStatus::Invalid,
+        };
+        Ok(Ok(()))
+    }
+}
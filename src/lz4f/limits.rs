@@ -0,0 +1,133 @@
+use crate::{Error, ErrorKind, Result};
+
+/// Decompression limits.
+///
+/// Limits protect decompressors from untrusted input which expands to
+/// an unexpectedly large output.
+/// Going over a limit raises `DecompressionLimitExceeded`.
+///
+/// By default, no limits are applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DecompressionLimits {
+    max_output_size: usize,
+    max_frames: usize,
+    max_content_size: usize,
+    max_preallocation: usize,
+}
+
+impl Default for DecompressionLimits {
+    fn default() -> Self {
+        Self {
+            max_output_size: usize::MAX,
+            max_frames: usize::MAX,
+            max_content_size: usize::MAX,
+            max_preallocation: usize::MAX,
+        }
+    }
+}
+
+impl DecompressionLimits {
+    /// Returns the maximum number of decompressed bytes in total.
+    pub const fn max_output_size(&self) -> usize {
+        self.max_output_size
+    }
+
+    /// Returns the maximum number of frames.
+    pub const fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+
+    /// Returns the maximum content size declared in a frame header.
+    pub const fn max_content_size(&self) -> usize {
+        self.max_content_size
+    }
+
+    /// Returns the maximum number of bytes allocated in advance from the content size.
+    pub const fn max_preallocation(&self) -> usize {
+        self.max_preallocation
+    }
+
+    pub(super) fn check_frame(&self, frames: usize, content_size: usize) -> Result<()> {
+        if frames > self.max_frames || content_size > self.max_content_size {
+            Err(Error::new(ErrorKind::DecompressionLimitExceeded))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the number of bytes which can still be output
+    /// after `total` bytes have been decompressed.
+    pub(super) fn remaining_output(&self, total: u64) -> usize {
+        usize::try_from(total)
+            .map(|total| self.max_output_size.saturating_sub(total))
+            .unwrap_or(0)
+    }
+}
+
+/// Builds a custom `DecompressionLimits`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::DecompressionLimitsBuilder;
+///
+/// let limits = DecompressionLimitsBuilder::new()
+///     .max_output_size(64 << 20)
+///     .max_frames(1)
+///     .build();
+/// ```
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DecompressionLimitsBuilder {
+    limits: DecompressionLimits,
+}
+
+impl DecompressionLimitsBuilder {
+    /// Creates a new `DecompressionLimitsBuilder`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the maximum number of decompressed bytes in total.
+    pub fn max_output_size(&mut self, size: usize) -> &mut Self {
+        self.limits.max_output_size = size;
+        self
+    }
+
+    /// Sets the maximum number of frames.
+    ///
+    /// Skippable frames are also counted.
+    pub fn max_frames(&mut self, frames: usize) -> &mut Self {
+        self.limits.max_frames = frames;
+        self
+    }
+
+    /// Sets the maximum content size declared in a frame header.
+    ///
+    /// Frames without the content size field are not affected.
+    pub fn max_content_size(&mut self, size: usize) -> &mut Self {
+        self.limits.max_content_size = size;
+        self
+    }
+
+    /// Sets the maximum number of bytes allocated in advance from the content size
+    /// declared in a frame header.
+    ///
+    /// Frames declaring a larger content size are decoded into a buffer
+    /// which grows gradually, so a forged header cannot force a large allocation
+    /// before any data is decoded. This does not limit the output size.
+    pub fn max_preallocation(&mut self, size: usize) -> &mut Self {
+        self.limits.max_preallocation = size;
+        self
+    }
+
+    /// Builds a `DecompressionLimits` with this configuration.
+    pub const fn build(&self) -> DecompressionLimits {
+        self.limits
+    }
+}
+
+impl From<DecompressionLimits> for DecompressionLimitsBuilder {
+    fn from(limits: DecompressionLimits) -> Self {
+        Self { limits }
+    }
+}
@@ -0,0 +1,24 @@
+/*
+ * Extensions to lz4frame which need access to the private compression context.
+ * lz4frame.c is included here so that it is compiled as a part of this unit.
+ */
+#include "lz4frame.c"
+
+/*
+ * Changes the compression level of a frame in progress.
+ * The new level takes effect from the next block. The level must stay
+ * in the same family (fast or HC), because the family selects the stream type.
+ */
+size_t lzzzz_LZ4F_setCompressionLevel(LZ4F_cctx* cctx, int level, unsigned favorDecSpeed)
+{
+    int const isHC = cctx->prefs.compressionLevel >= LZ4HC_CLEVEL_MIN;
+    RETURN_ERROR_IF(isHC != (level >= LZ4HC_CLEVEL_MIN), compressionLevel_invalid);
+    cctx->prefs.compressionLevel = level;
+    cctx->prefs.favorDecSpeed = favorDecSpeed;
+    if (isHC && cctx->cStage == 1) {
+        /* The linked HC stream keeps its own level. */
+        LZ4_setCompressionLevel((LZ4_streamHC_t*)cctx->lz4CtxPtr, level);
+        LZ4_favorDecompressionSpeed((LZ4_streamHC_t*)cctx->lz4CtxPtr, (int)favorDecSpeed);
+    }
+    return 0;
+}
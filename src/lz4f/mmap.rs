@@ -0,0 +1,94 @@
+//! Decompression of memory-mapped files.
+
+#![allow(unsafe_code)]
+
+use super::{frame, Result};
+use memmap2::Mmap;
+use std::{fs::File, io, path::Path};
+
+/// Decompresses an LZ4 file through a memory map.
+///
+/// The whole mapped file is passed to liblz4 at once, so the compressed data
+/// is read without an intermediate buffer.
+/// Concatenated frames are decompressed one after another
+/// and skippable frames are ignored.
+///
+/// The file must not be modified while it is decompressed.
+///
+/// Requires the `memmap2` feature.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+/// # let tmp_dir = assert_fs::TempDir::new().unwrap();
+/// # let path = tmp_dir.path().join("foo.lz4");
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+/// std::fs::write(&path, &comp)?;
+///
+/// assert_eq!(lz4f::decompress_file(&path)?, &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let file = File::open(path)?;
+    // The map is read-only and dropped before returning.
+    let mmap = unsafe { Mmap::map(&file)? };
+    #[cfg(unix)]
+    let _ = mmap.advise(memmap2::Advice::Sequential);
+
+    let mut buf = Vec::new();
+    let mut offset = 0;
+    while offset < mmap.len() {
+        let (src_len, _) = super::decompress_to_vec_with_consumed(&mmap[offset..], &mut buf)?;
+        offset += src_len;
+    }
+    Ok(buf)
+}
+
+/// Decompresses LZ4 frames in a memory map into `dst`.
+///
+/// Like [`decompress_file`], the whole map is passed to liblz4 at once
+/// and concatenated frames are decompressed one after another.
+/// `dst` must be large enough to hold the decompressed data.
+///
+/// Returns the number of bytes written into the destination buffer.
+///
+/// Requires the `memmap2` feature.
+///
+/// [`decompress_file`]: fn.decompress_file.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+/// use memmap2::Mmap;
+/// use std::fs::File;
+/// # let tmp_dir = assert_fs::TempDir::new().unwrap();
+/// # let path = tmp_dir.path().join("foo.lz4");
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+/// std::fs::write(&path, &comp)?;
+///
+/// let file = File::open(&path)?;
+/// let mmap = unsafe { Mmap::map(&file)? };
+/// let mut buf = [0u8; 44];
+/// let len = lz4f::decompress_mmap(&mmap, &mut buf)?;
+/// assert_eq!(&buf[..len], &data[..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_mmap(src: &Mmap, dst: &mut [u8]) -> Result<usize> {
+    let mut src_offset = 0;
+    let mut dst_offset = 0;
+    while src_offset < src.len() {
+        let (src_len, dst_len) =
+            frame::decompress_with_consumed(&src[src_offset..], &mut dst[dst_offset..])?;
+        src_offset += src_len;
+        dst_offset += dst_len;
+    }
+    Ok(dst_offset)
+}
@@ -1,18 +1,42 @@
 //! LZ4F compression and decompression.
 //!
 //! LZ4F: LZ4 Frame Format.
+//!
+//! The flag enums such as [`BlockSize`] implement `FromStr`, which accepts
+//! their names ignoring case. With the `serde` feature, they are serialized
+//! as these names and deserialized with the same rule as `FromStr`.
+//!
+//! [`BlockSize`]: enum.BlockSize.html
 mod api;
 mod binding;
+mod context;
+mod decoder;
 mod dictionary;
+mod dictionary_file;
 mod error;
 mod frame;
 mod frame_info;
+mod inspect;
+mod limits;
+#[cfg(feature = "memmap2")]
+mod mmap;
+mod policy;
 mod preferences;
+mod recovery;
 mod stream;
 
+pub use context::*;
+pub use decoder::*;
 pub use dictionary::*;
+pub use dictionary_file::*;
 pub use error::*;
 pub use frame::*;
 pub use frame_info::*;
+pub use inspect::*;
+pub use limits::*;
+#[cfg(feature = "memmap2")]
+pub use mmap::*;
+pub use policy::*;
 pub use preferences::*;
+pub use recovery::*;
 pub use stream::{comp::*, decomp::*};
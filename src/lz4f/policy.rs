@@ -0,0 +1,149 @@
+use super::{
+    recovery::LZ4F_MAGIC_NUMBER, BlockChecksum, ContentChecksum, Error, ErrorKind, FrameInfo,
+    FrameType, Result,
+};
+
+/// Frame validation policy.
+///
+/// liblz4 always verifies checksums which are present in a frame
+/// and fails with `FrameSizeWrong` if the decoded length does not match
+/// the declared content size.
+/// `ValidationPolicy` adds stricter requirements on top of them,
+/// and reports their violations as `lz4f::ErrorKind`s.
+///
+/// By default, no additional requirements are applied.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ValidationPolicy {
+    require_content_checksum: bool,
+    require_block_checksum: bool,
+    reject_trailing_data: bool,
+    reject_reserved_flags: bool,
+}
+
+impl ValidationPolicy {
+    /// Returns `true` if frames must have the content checksum.
+    pub const fn require_content_checksum(&self) -> bool {
+        self.require_content_checksum
+    }
+
+    /// Returns `true` if frames must have the block checksums.
+    pub const fn require_block_checksum(&self) -> bool {
+        self.require_block_checksum
+    }
+
+    /// Returns `true` if data after the last frame is rejected.
+    pub const fn reject_trailing_data(&self) -> bool {
+        self.reject_trailing_data
+    }
+
+    /// Returns `true` if frame headers with reserved bits set are rejected.
+    pub const fn reject_reserved_flags(&self) -> bool {
+        self.reject_reserved_flags
+    }
+
+    pub(super) fn check_header(&self, header: &[u8]) -> Result<()> {
+        if !self.reject_reserved_flags
+            || header.len() < 6
+            || header[..4] != LZ4F_MAGIC_NUMBER.to_le_bytes()
+        {
+            return Ok(());
+        }
+        let (flg, bd) = (header[4], header[5]);
+        if flg & 0b0000_0010 != 0 || bd & 0b1000_1111 != 0 {
+            Err(Error::new(ErrorKind::ReservedFlagSet))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(super) fn check_frame(&self, info: &FrameInfo) -> Result<()> {
+        if info.frame_type() == FrameType::SkippableFrame {
+            return Ok(());
+        }
+        if self.require_content_checksum && info.content_checksum() == ContentChecksum::Disabled {
+            return Err(Error::new(ErrorKind::ContentChecksumMissing));
+        }
+        if self.require_block_checksum && info.block_checksum() == BlockChecksum::Disabled {
+            return Err(Error::new(ErrorKind::BlockChecksumMissing));
+        }
+        Ok(())
+    }
+
+    pub(super) fn check_trailing_data(&self, len: usize) -> Result<()> {
+        if self.reject_trailing_data && len > 0 {
+            Err(Error::new(ErrorKind::TrailingData))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Builds a custom `ValidationPolicy`.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::ValidationPolicyBuilder;
+///
+/// let policy = ValidationPolicyBuilder::new()
+///     .require_content_checksum(true)
+///     .reject_trailing_data(true)
+///     .build();
+/// ```
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ValidationPolicyBuilder {
+    policy: ValidationPolicy,
+}
+
+impl ValidationPolicyBuilder {
+    /// Creates a new `ValidationPolicyBuilder`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Requires the content checksum.
+    ///
+    /// Frames without the content checksum are rejected with `ContentChecksumMissing`.
+    pub fn require_content_checksum(&mut self, flag: bool) -> &mut Self {
+        self.policy.require_content_checksum = flag;
+        self
+    }
+
+    /// Requires the block checksums.
+    ///
+    /// Frames without the block checksums are rejected with `BlockChecksumMissing`.
+    pub fn require_block_checksum(&mut self, flag: bool) -> &mut Self {
+        self.policy.require_block_checksum = flag;
+        self
+    }
+
+    /// Rejects data after the last frame with `TrailingData`.
+    ///
+    /// This applies to the one-shot functions, which decompress only the first frame of the input.
+    /// The streaming decompressors decode any following data as the next frame,
+    /// unless the single-frame mode leaves it unread.
+    pub fn reject_trailing_data(&mut self, flag: bool) -> &mut Self {
+        self.policy.reject_trailing_data = flag;
+        self
+    }
+
+    /// Rejects frame headers with reserved bits set with `ReservedFlagSet`.
+    ///
+    /// The reserved bits of the FLG and BD bytes are checked before the header
+    /// is passed to liblz4, which rejects them too when it parses the header.
+    pub fn reject_reserved_flags(&mut self, flag: bool) -> &mut Self {
+        self.policy.reject_reserved_flags = flag;
+        self
+    }
+
+    /// Builds a `ValidationPolicy` with this configuration.
+    pub const fn build(&self) -> ValidationPolicy {
+        self.policy
+    }
+}
+
+impl From<ValidationPolicy> for ValidationPolicyBuilder {
+    fn from(policy: ValidationPolicy) -> Self {
+        Self { policy }
+    }
+}
@@ -1,5 +1,14 @@
-use super::frame_info::{BlockChecksum, BlockMode, BlockSize, ContentChecksum, FrameInfo};
-use std::os::raw::{c_int, c_uint};
+use super::{
+    frame_info::{
+        flag_from_str, BlockChecksum, BlockMode, BlockSize, ContentChecksum, FrameInfo,
+    },
+    Error, Result,
+};
+use crate::Profile;
+use std::{
+    os::raw::{c_int, c_uint},
+    str::FromStr,
+};
 
 /// Predefined compression level (0).
 pub const CLEVEL_DEFAULT: i32 = 0;
@@ -11,35 +20,91 @@ pub const CLEVEL_HIGH: i32 = 10;
 pub const CLEVEL_MAX: i32 = 12;
 
 /// Auto flush mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as `"disabled"` and `"enabled"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase", try_from = "String")
+)]
 #[repr(C)]
 pub enum AutoFlush {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for AutoFlush {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
+flag_from_str!(AutoFlush {
+    "disabled" => Disabled,
+    "enabled" => Enabled,
+});
 
 /// Decompression speed mode flag.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+///
+/// With the `serde` feature, the values are represented as `"disabled"` and `"enabled"`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase", try_from = "String")
+)]
 #[repr(C)]
 pub enum FavorDecSpeed {
+    #[default]
     Disabled,
     Enabled,
 }
 
-impl Default for FavorDecSpeed {
-    fn default() -> Self {
-        Self::Disabled
-    }
-}
+flag_from_str!(FavorDecSpeed {
+    "disabled" => Disabled,
+    "enabled" => Enabled,
+});
 
 /// Compression preferences.
+///
+/// `Preferences` can be parsed from a compact preset string with [`str::parse`].
+/// The preset is a comma-separated list of these items:
+///
+/// | Item                 | Meaning                                    |
+/// |----------------------|--------------------------------------------|
+/// | `level=<n>`          | Compression level                          |
+/// | `block=<size>`       | Block size (`64KB`, `256KB`, `1MB`, `4MB`) |
+/// | `linked`             | Linked blocks                              |
+/// | `independent`        | Independent blocks                         |
+/// | `checksum`           | Content checksum                           |
+/// | `block-checksum`     | Block checksums                            |
+/// | `content-size=<n>`   | Content size                               |
+/// | `dict-id=<n>`        | Dictionary ID                              |
+/// | `auto-flush`         | Auto flush                                 |
+/// | `favor-dec-speed`    | Favor decompression speed                  |
+///
+/// Unknown items and invalid values are rejected with `PresetInvalid`.
+///
+/// With the `serde` feature, `Preferences` is represented as a flat map with the fields
+/// `compression_level`, `block_size`, `block_mode`, `block_checksum`, `content_checksum`,
+/// `content_size`, `dict_id`, `auto_flush` and `favor_dec_speed`.
+/// Missing fields take their default values.
+///
+/// [`str::parse`]: https://doc.rust-lang.org/std/primitive.str.html#method.parse
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{BlockSize, ContentChecksum, Preferences};
+///
+/// let prefs: Preferences = "level=9,block=1MB,checksum".parse()?;
+/// assert_eq!(prefs.compression_level(), 9);
+/// assert_eq!(prefs.frame_info().block_size(), BlockSize::Max1MB);
+/// assert_eq!(prefs.frame_info().content_checksum(), ContentChecksum::Enabled);
+/// # Ok::<(), std::io::Error>(())
+/// ```
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "PreferencesRepr", into = "PreferencesRepr")
+)]
 #[repr(C)]
 pub struct Preferences {
     frame_info: FrameInfo,
@@ -187,6 +252,18 @@ impl PreferencesBuilder {
         self
     }
 
+    /// Sets the compression level and the decompression speed mode flag from a [`Profile`].
+    ///
+    /// [`Profile`]: ../enum.Profile.html
+    pub fn profile(&mut self, profile: Profile) -> &mut Self {
+        self.compression_level(profile.lz4f_level())
+            .favor_dec_speed(if profile.favor_dec_speed() {
+                FavorDecSpeed::Enabled
+            } else {
+                FavorDecSpeed::Disabled
+            })
+    }
+
     /// Builds a `Preferences` with this configuration.
     pub const fn build(&self) -> Preferences {
         self.prefs
@@ -199,13 +276,102 @@ impl From<Preferences> for PreferencesBuilder {
     }
 }
 
+impl FromStr for Preferences {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::from(crate::Error::new(crate::ErrorKind::PresetInvalid));
+        let mut builder = PreferencesBuilder::new();
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (key, value) = match item.split_once('=') {
+                Some((key, value)) => (key.trim_end(), Some(value.trim_start())),
+                None => (item, None),
+            };
+            match (key, value) {
+                ("level", Some(level)) => {
+                    builder.compression_level(level.parse().map_err(|_| invalid())?)
+                }
+                ("block", Some(size)) => builder.block_size(size.parse()?),
+                ("linked", None) => builder.block_mode(BlockMode::Linked),
+                ("independent", None) => builder.block_mode(BlockMode::Independent),
+                ("checksum", None) => builder.content_checksum(ContentChecksum::Enabled),
+                ("block-checksum", None) => builder.block_checksum(BlockChecksum::Enabled),
+                ("content-size", Some(size)) => {
+                    builder.content_size(size.parse().map_err(|_| invalid())?)
+                }
+                ("dict-id", Some(id)) => builder.dict_id(id.parse().map_err(|_| invalid())?),
+                ("auto-flush", None) => builder.auto_flush(AutoFlush::Enabled),
+                ("favor-dec-speed", None) => builder.favor_dec_speed(FavorDecSpeed::Enabled),
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PreferencesRepr {
+    compression_level: i32,
+    block_size: BlockSize,
+    block_mode: BlockMode,
+    block_checksum: BlockChecksum,
+    content_checksum: ContentChecksum,
+    content_size: usize,
+    dict_id: u32,
+    auto_flush: AutoFlush,
+    favor_dec_speed: FavorDecSpeed,
+}
+
+#[cfg(feature = "serde")]
+impl Default for PreferencesRepr {
+    fn default() -> Self {
+        Preferences::default().into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Preferences> for PreferencesRepr {
+    fn from(prefs: Preferences) -> Self {
+        let info = prefs.frame_info;
+        Self {
+            compression_level: prefs.compression_level,
+            block_size: info.block_size(),
+            block_mode: info.block_mode(),
+            block_checksum: info.block_checksum(),
+            content_checksum: info.content_checksum(),
+            content_size: info.content_size(),
+            dict_id: info.dict_id(),
+            auto_flush: prefs.auto_flush,
+            favor_dec_speed: prefs.favor_dec_speed,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PreferencesRepr> for Preferences {
+    fn from(repr: PreferencesRepr) -> Self {
+        PreferencesBuilder::new()
+            .compression_level(repr.compression_level)
+            .block_size(repr.block_size)
+            .block_mode(repr.block_mode)
+            .block_checksum(repr.block_checksum)
+            .content_checksum(repr.content_checksum)
+            .content_size(repr.content_size)
+            .dict_id(repr.dict_id)
+            .auto_flush(repr.auto_flush)
+            .favor_dec_speed(repr.favor_dec_speed)
+            .build()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::lz4f::{
-        BlockChecksum, BlockMode, BlockSize, ContentChecksum, FavorDecSpeed, Preferences,
-        PreferencesBuilder, CLEVEL_DEFAULT, CLEVEL_HIGH, CLEVEL_MAX,
+        AutoFlush, BlockChecksum, BlockMode, BlockSize, ContentChecksum, Error, FavorDecSpeed,
+        Preferences, PreferencesBuilder, CLEVEL_DEFAULT, CLEVEL_HIGH, CLEVEL_MAX,
     };
-    use std::{i32, u32};
 
     #[test]
     fn preferences_builder() {
@@ -325,4 +491,50 @@ mod tests {
             u32::MIN
         );
     }
+
+    #[test]
+    fn preset() {
+        assert_eq!("".parse::<Preferences>(), Ok(Preferences::default()));
+        assert_eq!(
+            "level=9, block=1MB, checksum".parse::<Preferences>(),
+            Ok(PreferencesBuilder::new()
+                .compression_level(9)
+                .block_size(BlockSize::Max1MB)
+                .content_checksum(ContentChecksum::Enabled)
+                .build())
+        );
+        assert_eq!(
+            "level=-3,block=64kb,independent,block-checksum,content-size=100,dict-id=7,\
+             auto-flush,favor-dec-speed"
+                .parse::<Preferences>(),
+            Ok(PreferencesBuilder::new()
+                .compression_level(-3)
+                .block_size(BlockSize::Max64KB)
+                .block_mode(BlockMode::Independent)
+                .block_checksum(BlockChecksum::Enabled)
+                .content_size(100)
+                .dict_id(7)
+                .auto_flush(AutoFlush::Enabled)
+                .favor_dec_speed(FavorDecSpeed::Enabled)
+                .build())
+        );
+        assert_eq!(
+            "independent,linked".parse::<Preferences>(),
+            Ok(PreferencesBuilder::new()
+                .block_mode(BlockMode::Linked)
+                .build())
+        );
+
+        let invalid = Err(Error::Common(crate::ErrorKind::PresetInvalid));
+        for preset in &[
+            "level",
+            "level=high",
+            "block=2MB",
+            "checksum=yes",
+            "dict-id=-1",
+            "fast",
+        ] {
+            assert_eq!(preset.parse::<Preferences>(), invalid, "{}", preset);
+        }
+    }
 }
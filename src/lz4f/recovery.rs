@@ -0,0 +1,649 @@
+//! Recovery of damaged LZ4 frames.
+
+use super::{
+    api::{DecompressionContext, LZ4F_HEADER_SIZE_MAX},
+    BlockChecksum, BlockMode, BlockSize, ContentChecksum, DecompressionLimits, Error, ErrorKind,
+    FrameInfo, Result,
+};
+use crate::{
+    common::{xxh32, Xxh32, DEFAULT_BUF_SIZE},
+    lz4, Stats,
+};
+use std::{cmp, io, io::BufRead};
+
+pub(super) const LZ4F_MAGIC_NUMBER: u32 = 0x184D_2204;
+pub(super) const LZ4F_MAGIC_SKIPPABLE_START: u32 = 0x184D_2A50;
+pub(super) const LZ4F_BLOCK_UNCOMPRESSED_FLAG: u32 = 0x8000_0000;
+
+/// A damaged region of the compressed data.
+///
+/// Reported by [`decompress_to_vec_with_recovery`] and the recovery mode
+/// of the streaming decompressors.
+///
+/// [`decompress_to_vec_with_recovery`]: fn.decompress_to_vec_with_recovery.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DamagedRegion {
+    offset: usize,
+    len: usize,
+    output_offset: usize,
+    output_len: usize,
+    error: Error,
+}
+
+impl DamagedRegion {
+    /// Returns the offset of the region in the compressed data.
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the length of the region in bytes.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the region is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the offset in the decompressed data where the lost data would have been.
+    ///
+    /// The offset is relative to the beginning of the data appended by the call,
+    /// or to the beginning of the stream.
+    pub const fn output_offset(&self) -> usize {
+        self.output_offset
+    }
+
+    /// Returns the number of fill bytes written in place of the lost data.
+    ///
+    /// This is always 0 unless a gap fill byte is set.
+    pub const fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// Returns the error which made the region unreadable.
+    pub const fn error(&self) -> Error {
+        self.error
+    }
+}
+
+/// Decompresses damaged LZ4 frames, salvaging as much data as possible.
+///
+/// `src` may contain any number of frames. Unlike [`decompress_to_vec`],
+/// decoding errors do not abort the decompression:
+///
+/// - In frames with independent blocks, a corrupt block is skipped
+///   and decoding continues with the next block.
+/// - Otherwise, the decoder searches for the next frame magic number (`0x184D2204`)
+///   and resumes from there.
+///
+/// Each skipped region is passed to `report`.
+/// Dictionaries are not supported, so frames which need a dictionary are reported as damaged.
+///
+/// The same recovery is available for streams through
+/// [`BufReadDecompressor::set_recovery`] and [`ReadDecompressor::set_recovery`].
+///
+/// Returns the number of bytes appended to the given `Vec<u8>`.
+///
+/// [`decompress_to_vec`]: fn.decompress_to_vec.html
+/// [`BufReadDecompressor::set_recovery`]: struct.BufReadDecompressor.html#method.set_recovery
+/// [`ReadDecompressor::set_recovery`]: struct.ReadDecompressor.html#method.set_recovery
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.";
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+/// let frame_len = comp.len();
+/// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+///
+/// // Break the first frame.
+/// comp[10] ^= 0xff;
+///
+/// let mut buf = Vec::new();
+/// let mut damaged = Vec::new();
+/// lz4f::decompress_to_vec_with_recovery(&comp, &mut buf, |region| damaged.push(*region))?;
+///
+/// assert_eq!(&buf[..], &data[..]);
+/// assert_eq!(damaged.len(), 1);
+/// assert_eq!(damaged[0].offset() + damaged[0].len(), frame_len);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_recovery<F>(src: &[u8], dst: &mut Vec<u8>, report: F) -> Result<usize>
+where
+    F: FnMut(&DamagedRegion),
+{
+    recover_to_vec(src, dst, None, report)
+}
+
+/// Decompresses damaged LZ4 frames, filling the lost blocks with `fill`.
+///
+/// This works like [`decompress_to_vec_with_recovery`], but each corrupt block
+/// in a frame with independent blocks is replaced with `fill` bytes,
+/// so the following data stays at its original offset.
+///
+/// The length of a lost block is assumed to be the maximum block size of the frame,
+/// capped by the remaining content size if the frame header declares it.
+/// Regions skipped by searching for the next frame are not filled.
+///
+/// [`decompress_to_vec_with_recovery`]: fn.decompress_to_vec_with_recovery.html
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, BlockChecksum, BlockMode, BlockSize, PreferencesBuilder};
+///
+/// let data = (0..100_000u32).flat_map(u32::to_le_bytes).collect::<Vec<_>>();
+/// let prefs = PreferencesBuilder::new()
+///     .block_size(BlockSize::Max64KB)
+///     .block_mode(BlockMode::Independent)
+///     .block_checksum(BlockChecksum::Enabled)
+///     .content_size(data.len())
+///     .build();
+/// let mut comp = Vec::new();
+/// lz4f::compress_to_vec(&data, &mut comp, &prefs)?;
+///
+/// // Break the first block.
+/// comp[30] ^= 0xff;
+///
+/// let mut buf = Vec::new();
+/// let mut damaged = Vec::new();
+/// lz4f::decompress_to_vec_with_gap_fill(&comp, &mut buf, 0, |region| damaged.push(*region))?;
+///
+/// assert_eq!(buf.len(), data.len());
+/// assert_eq!(damaged[0].output_len(), 64 * 1024);
+/// assert_eq!(&buf[64 * 1024..], &data[64 * 1024..]);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn decompress_to_vec_with_gap_fill<F>(
+    src: &[u8],
+    dst: &mut Vec<u8>,
+    fill: u8,
+    report: F,
+) -> Result<usize>
+where
+    F: FnMut(&DamagedRegion),
+{
+    recover_to_vec(src, dst, Some(fill), report)
+}
+
+fn recover_to_vec<F>(
+    mut src: &[u8],
+    dst: &mut Vec<u8>,
+    gap_fill: Option<u8>,
+    report: F,
+) -> Result<usize>
+where
+    F: FnMut(&DamagedRegion),
+{
+    let header_len = dst.len();
+    let mut recovery = Recovery::new(gap_fill, Default::default(), report)?;
+    while recovery.step(&mut src, dst).map_err(Error::from_io)? {}
+    Ok(dst.len() - header_len)
+}
+
+pub(super) type RecoveryCallback<'a> = Box<dyn FnMut(&DamagedRegion) + Send + 'a>;
+
+enum Stage {
+    Frame,
+    Skippable {
+        offset: usize,
+        remaining: usize,
+    },
+    Independent {
+        info: FrameInfo,
+        hasher: Option<Xxh32>,
+        damaged: bool,
+        produced: usize,
+    },
+    Linked,
+    Resync {
+        offset: usize,
+        error: Error,
+    },
+}
+
+/// Incremental recovery decoder pulling the compressed data from a `BufRead`.
+///
+/// Each step decodes at most one block, so the memory usage is bounded
+/// by the maximum block size regardless of the frame size.
+pub(super) struct Recovery<F> {
+    ctx: DecompressionContext,
+    input: Vec<u8>,
+    input_pos: usize,
+    offset: usize,
+    eof: bool,
+    stage: Stage,
+    frame_info: Option<FrameInfo>,
+    frames: usize,
+    total_out: usize,
+    limits: DecompressionLimits,
+    gap_fill: Option<u8>,
+    report: F,
+    buf: Vec<u8>,
+    consumed: usize,
+}
+
+impl<F> Recovery<F>
+where
+    F: FnMut(&DamagedRegion),
+{
+    pub fn new(gap_fill: Option<u8>, limits: DecompressionLimits, report: F) -> Result<Self> {
+        Ok(Self {
+            ctx: DecompressionContext::new()?,
+            input: Vec::new(),
+            input_pos: 0,
+            offset: 0,
+            eof: false,
+            stage: Stage::Frame,
+            frame_info: None,
+            frames: 0,
+            total_out: 0,
+            limits,
+            gap_fill,
+            report,
+            buf: Vec::new(),
+            consumed: 0,
+        })
+    }
+
+    /// Returns the header of the last LZ4 frame reached.
+    pub fn frame_info(&self) -> Option<FrameInfo> {
+        self.frame_info
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats::new(self.total_out as u64, self.offset as u64)
+    }
+
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.limits = limits;
+    }
+
+    /// Makes at least `len` bytes available unless the reader reaches EOF,
+    /// and returns the number of available bytes.
+    fn fill<R: BufRead>(&mut self, reader: &mut R, len: usize) -> io::Result<usize> {
+        while self.input.len() - self.input_pos < len && !self.eof {
+            if self.input_pos > 0 {
+                self.input.drain(..self.input_pos);
+                self.input_pos = 0;
+            }
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                self.eof = true;
+            }
+            let needed = len - self.input.len();
+            let consumed = cmp::min(buf.len(), cmp::max(needed, DEFAULT_BUF_SIZE));
+            self.input.extend_from_slice(&buf[..consumed]);
+            reader.consume(consumed);
+        }
+        Ok(self.input.len() - self.input_pos)
+    }
+
+    fn available(&self) -> &[u8] {
+        &self.input[self.input_pos..]
+    }
+
+    fn advance(&mut self, len: usize) {
+        self.input_pos += len;
+        self.offset += len;
+    }
+
+    /// Decodes the next piece of the input into `dst`.
+    ///
+    /// Returns `false` if the input is exhausted.
+    /// Fails with `DecompressionLimitExceeded` if the output goes over the limits.
+    pub fn step<R: BufRead>(&mut self, reader: &mut R, dst: &mut Vec<u8>) -> io::Result<bool> {
+        let dst_offset = dst.len();
+        let remaining = self.limits.remaining_output(self.total_out as u64);
+        match std::mem::replace(&mut self.stage, Stage::Frame) {
+            Stage::Frame => return self.frame(reader),
+            Stage::Skippable { offset, remaining } => self.skippable(reader, offset, remaining)?,
+            Stage::Independent {
+                info,
+                hasher,
+                damaged,
+                produced,
+            } => self.independent_block(reader, dst, info, hasher, damaged, produced)?,
+            Stage::Linked => self.linked_blocks(reader, dst)?,
+            Stage::Resync { offset, error } => self.resync(reader, offset, error)?,
+        }
+        if dst.len() - dst_offset > remaining {
+            dst.truncate(dst_offset + remaining);
+            self.total_out = self.limits.max_output_size();
+            let err = crate::Error::new(crate::ErrorKind::DecompressionLimitExceeded);
+            return Err(Error::from(err).into());
+        }
+        Ok(true)
+    }
+
+    fn frame<R: BufRead>(&mut self, reader: &mut R) -> io::Result<bool> {
+        let offset = self.offset;
+        let len = self.fill(reader, LZ4F_HEADER_SIZE_MAX)?;
+        if len == 0 {
+            return Ok(false);
+        }
+        self.stage = match read_u32(self.available(), 0) {
+            Some(LZ4F_MAGIC_NUMBER) => {
+                self.ctx.reset();
+                let header = self.ctx.get_frame_info(self.available());
+                self.ctx.reset();
+                match header {
+                    Ok((info, header_len)) => {
+                        self.frames += 1;
+                        self.limits
+                            .check_frame(self.frames, info.content_size())
+                            .map_err(Error::from)?;
+                        self.frame_info = Some(info);
+                        match info.block_mode() {
+                            BlockMode::Independent => {
+                                self.advance(header_len);
+                                let hasher = if info.content_checksum() == ContentChecksum::Enabled
+                                {
+                                    Some(Xxh32::new()?)
+                                } else {
+                                    None
+                                };
+                                Stage::Independent {
+                                    info,
+                                    hasher,
+                                    damaged: false,
+                                    produced: 0,
+                                }
+                            }
+                            BlockMode::Linked => Stage::Linked,
+                        }
+                    }
+                    Err(error) => Stage::Resync { offset, error },
+                }
+            }
+            Some(magic) if magic & 0xFFFF_FFF0 == LZ4F_MAGIC_SKIPPABLE_START => {
+                match read_u32(self.available(), 4) {
+                    Some(len) => {
+                        self.frames += 1;
+                        self.limits
+                            .check_frame(self.frames, 0)
+                            .map_err(Error::from)?;
+                        self.advance(8);
+                        Stage::Skippable {
+                            offset,
+                            remaining: len as usize,
+                        }
+                    }
+                    None => Stage::Resync {
+                        offset,
+                        error: incomplete(),
+                    },
+                }
+            }
+            Some(_) => Stage::Resync {
+                offset,
+                error: Error::new(ErrorKind::FrameTypeUnknown),
+            },
+            None => Stage::Resync {
+                offset,
+                error: incomplete(),
+            },
+        };
+        Ok(true)
+    }
+
+    fn skippable<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        offset: usize,
+        remaining: usize,
+    ) -> io::Result<()> {
+        let len = cmp::min(self.fill(reader, 1)?, remaining);
+        self.advance(len);
+        self.stage = if len == remaining {
+            Stage::Frame
+        } else if len == 0 {
+            Stage::Resync {
+                offset,
+                error: incomplete(),
+            }
+        } else {
+            Stage::Skippable {
+                offset,
+                remaining: remaining - len,
+            }
+        };
+        Ok(())
+    }
+
+    fn independent_block<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        dst: &mut Vec<u8>,
+        info: FrameInfo,
+        mut hasher: Option<Xxh32>,
+        mut damaged: bool,
+        mut produced: usize,
+    ) -> io::Result<()> {
+        let max_block_size = max_block_size(info.block_size());
+        let checksum_len = if info.block_checksum() == BlockChecksum::Enabled {
+            4
+        } else {
+            0
+        };
+        let block_offset = self.offset;
+        if self.fill(reader, 4)? < 4 {
+            self.stage = Stage::Resync {
+                offset: block_offset,
+                error: incomplete(),
+            };
+            return Ok(());
+        }
+
+        let word = read_u32(self.available(), 0).unwrap_or_default();
+        if word == 0 {
+            self.advance(4);
+            if let Some(hasher) = hasher {
+                if self.fill(reader, 4)? < 4 {
+                    self.stage = Stage::Resync {
+                        offset: self.offset,
+                        error: incomplete(),
+                    };
+                    return Ok(());
+                }
+                let checksum = read_u32(self.available(), 0).unwrap_or_default();
+                if !damaged && checksum != hasher.digest() {
+                    self.report(
+                        self.offset,
+                        4,
+                        0,
+                        Error::new(ErrorKind::ContentChecksumInvalid),
+                    );
+                }
+                self.advance(4);
+            }
+            self.stage = Stage::Frame;
+            return Ok(());
+        }
+
+        let len = (word & !LZ4F_BLOCK_UNCOMPRESSED_FLAG) as usize;
+        if len > max_block_size {
+            self.stage = Stage::Resync {
+                offset: block_offset,
+                error: Error::new(ErrorKind::MaxBlockSizeInvalid),
+            };
+            return Ok(());
+        }
+        let block_len = 4 + len + checksum_len;
+        if self.fill(reader, block_len)? < block_len {
+            self.stage = Stage::Resync {
+                offset: block_offset,
+                error: incomplete(),
+            };
+            return Ok(());
+        }
+
+        let remaining = match info.content_size() {
+            0 => max_block_size,
+            size => cmp::min(max_block_size, size.saturating_sub(produced)),
+        };
+        let block = &self.input[self.input_pos + 4..][..len];
+        let dst_offset = dst.len();
+        let result =
+            if checksum_len > 0 && read_u32(self.available(), 4 + len) != Some(xxh32(block)) {
+                Err(Error::new(ErrorKind::BlockChecksumInvalid))
+            } else if word & LZ4F_BLOCK_UNCOMPRESSED_FLAG != 0 {
+                dst.extend_from_slice(block);
+                Ok(())
+            } else {
+                // An LZ4 sequence expands to 255 bytes per input byte at most.
+                let bound = cmp::min(remaining, len.saturating_mul(255));
+                dst.resize_with(dst_offset + bound, Default::default);
+                let result = lz4::decompress(block, &mut dst[dst_offset..]);
+                let len = result.as_ref().map_or(0, |len| *len);
+                dst.truncate(dst_offset + len);
+                result.map(|_| ()).map_err(Error::from)
+            };
+        match result {
+            Ok(()) => {
+                if let Some(hasher) = &mut hasher {
+                    hasher.update(&dst[dst_offset..]);
+                }
+            }
+            Err(error) => {
+                damaged = true;
+                let fill_len = self.gap_fill.map_or(0, |_| remaining);
+                self.report(block_offset, block_len, fill_len, error);
+                if let Some(fill) = self.gap_fill {
+                    dst.resize(dst_offset + fill_len, fill);
+                }
+            }
+        }
+        produced += dst.len() - dst_offset;
+        self.total_out += dst.len() - dst_offset;
+        self.advance(block_len);
+        self.stage = Stage::Independent {
+            info,
+            hasher,
+            damaged,
+            produced,
+        };
+        Ok(())
+    }
+
+    fn linked_blocks<R: BufRead>(&mut self, reader: &mut R, dst: &mut Vec<u8>) -> io::Result<()> {
+        let offset = self.offset;
+        if self.fill(reader, 1)? == 0 {
+            self.ctx.reset();
+            self.stage = Stage::Resync {
+                offset,
+                error: incomplete(),
+            };
+            return Ok(());
+        }
+        let dst_offset = dst.len();
+        dst.resize_with(dst_offset + DEFAULT_BUF_SIZE, Default::default);
+        let result = self.ctx.decompress_dict(
+            &self.input[self.input_pos..],
+            &mut dst[dst_offset..],
+            &[],
+            false,
+        );
+        self.stage = match result {
+            Ok((src_len, dst_len, expected)) => {
+                dst.truncate(dst_offset + dst_len);
+                self.total_out += dst_len;
+                self.advance(src_len);
+                if expected == 0 {
+                    Stage::Frame
+                } else {
+                    Stage::Linked
+                }
+            }
+            Err(error) => {
+                dst.truncate(dst_offset);
+                self.ctx.reset();
+                Stage::Resync { offset, error }
+            }
+        };
+        Ok(())
+    }
+
+    /// Searches for the next frame magic number and reports the skipped region.
+    fn resync<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        offset: usize,
+        error: Error,
+    ) -> io::Result<()> {
+        let magic = LZ4F_MAGIC_NUMBER.to_le_bytes();
+        if self.offset == offset && self.fill(reader, 1)? > 0 {
+            self.advance(1);
+        }
+        loop {
+            let len = self.fill(reader, magic.len())?;
+            if let Some(pos) = self
+                .available()
+                .windows(magic.len())
+                .position(|w| w == magic)
+            {
+                self.advance(pos);
+                break;
+            }
+            if len < magic.len() {
+                self.advance(len);
+                break;
+            }
+            self.advance(len - (magic.len() - 1));
+        }
+        self.report(offset, self.offset - offset, 0, error);
+        self.stage = Stage::Frame;
+        Ok(())
+    }
+
+    fn report(&mut self, offset: usize, len: usize, output_len: usize, error: Error) {
+        (self.report)(&DamagedRegion {
+            offset,
+            len,
+            output_offset: self.total_out,
+            output_len,
+            error,
+        });
+    }
+
+    /// Returns the decoded data, decoding more if the buffer is empty.
+    pub fn fill_buf<R: BufRead>(&mut self, reader: &mut R) -> io::Result<&[u8]> {
+        while self.consumed >= self.buf.len() {
+            self.buf.clear();
+            self.consumed = 0;
+            let mut buf = std::mem::take(&mut self.buf);
+            let result = self.step(reader, &mut buf);
+            self.buf = buf;
+            if !result? {
+                break;
+            }
+        }
+        Ok(&self.buf[self.consumed..])
+    }
+
+    pub fn consume(&mut self, amt: usize) {
+        self.consumed = cmp::min(self.consumed + amt, self.buf.len());
+    }
+}
+
+pub(super) fn incomplete() -> Error {
+    crate::Error::new(crate::ErrorKind::CompressedDataIncomplete).into()
+}
+
+pub(super) fn read_u32(src: &[u8], pos: usize) -> Option<u32> {
+    let bytes = src.get(pos..pos.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub(super) fn max_block_size(block_size: BlockSize) -> usize {
+    match block_size {
+        BlockSize::Max256KB => 256 * 1024,
+        BlockSize::Max1MB => 1024 * 1024,
+        BlockSize::Max4MB => 4 * 1024 * 1024,
+        _ => 64 * 1024,
+    }
+}
@@ -0,0 +1,273 @@
+use super::{Preferences, WriteCompressor};
+use crate::{
+    lz4_hc,
+    lz4f::{PreferencesBuilder, Result},
+    Profile,
+};
+use std::{
+    cmp, fmt,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_WINDOW_SIZE: u64 = 1024 * 1024;
+
+/// One side is the bottleneck if it takes this many times longer than the other.
+const BOTTLENECK_RATIO: u32 = 2;
+
+const DEFAULT_PROFILES: [Profile; 6] = [
+    Profile::Fast(16),
+    Profile::Fast(4),
+    Profile::Balanced,
+    Profile::High(lz4_hc::CLEVEL_MIN),
+    Profile::High(lz4_hc::CLEVEL_DEFAULT),
+    Profile::Max,
+];
+
+/// The [`Write`]-based streaming compressor which adapts the compression level
+/// to the speed of the writer.
+///
+/// The compressor measures the time spent in compression and the time spent
+/// in the writer for each window of uncompressed data.
+/// If the writer is the bottleneck (a slow link or backpressure), it moves to
+/// the next stronger profile. If the compression is the bottleneck, it moves to
+/// the next faster one.
+///
+/// A change within the same family (fast or HC) takes effect from the next block
+/// of the current frame. A change between fast and HC profiles ends the current frame,
+/// because the frame uses a different kind of stream for each family. The output is then
+/// a sequence of concatenated frames, which the streaming decompressors read as one stream.
+/// If the preferences declare the content size, the output must be a single frame,
+/// so the profile only moves within the family of the one in use when the data starts.
+///
+/// The compression level and the decompression speed flag of the preferences
+/// are replaced by the current profile.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::{lz4f::{AdaptiveWriteCompressor, ReadDecompressor}, Profile};
+/// use std::io::prelude::*;
+///
+/// let data = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+///
+/// let mut w = AdaptiveWriteCompressor::new(Vec::new(), Default::default())?;
+/// w.set_profiles(&[Profile::Fastest, Profile::Balanced, Profile::Max])?;
+/// w.set_window_size(4096);
+/// w.write_all(&data)?;
+/// let comp = w.finish()?;
+///
+/// let mut buf = Vec::new();
+/// ReadDecompressor::new(&comp[..])?.read_to_end(&mut buf)?;
+/// assert_eq!(buf, data);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+pub struct AdaptiveWriteCompressor<W: Write> {
+    inner: WriteCompressor<TimedWriter<W>>,
+    prefs: Preferences,
+    profiles: Vec<Profile>,
+    index: usize,
+    profile: Profile,
+    window_size: u64,
+    window_offset: u64,
+    window_write_time: Duration,
+    compress_time: Duration,
+}
+
+impl<W: Write> AdaptiveWriteCompressor<W> {
+    /// Creates a new `AdaptiveWriteCompressor<W>`.
+    ///
+    /// The compressor starts with `Profile::Balanced` and moves between the profiles
+    /// `Fast(16)`, `Fast(4)`, `Balanced`, `High(3)`, `High(9)` and `Max`.
+    pub fn new(writer: W, prefs: Preferences) -> Result<Self> {
+        let profiles = DEFAULT_PROFILES.to_vec();
+        let index = 2;
+        let profile = profiles[index];
+        let writer = TimedWriter {
+            writer,
+            elapsed: Duration::ZERO,
+        };
+        Ok(Self {
+            inner: WriteCompressor::new(writer, profile_prefs(prefs, profile))?,
+            prefs,
+            profiles,
+            index,
+            profile,
+            window_size: DEFAULT_WINDOW_SIZE,
+            window_offset: 0,
+            window_write_time: Duration::ZERO,
+            compress_time: Duration::ZERO,
+        })
+    }
+
+    /// Sets the profiles to move between, ordered from the fastest to the strongest.
+    ///
+    /// The current position in the list is kept if possible.
+    /// If `profiles` is empty, the default list is used.
+    pub fn set_profiles(&mut self, profiles: &[Profile]) -> std::io::Result<()> {
+        self.profiles = if profiles.is_empty() {
+            DEFAULT_PROFILES.to_vec()
+        } else {
+            profiles.to_vec()
+        };
+        self.select(cmp::min(self.index, self.profiles.len() - 1))
+    }
+
+    /// Sets the number of uncompressed bytes between two adjustments. The default is 1 MiB.
+    ///
+    /// The size must be greater than zero.
+    pub fn set_window_size(&mut self, size: u64) {
+        self.window_size = cmp::max(size, 1);
+    }
+
+    /// Returns the current profile.
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    /// Returns the total number of uncompressed bytes written.
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    /// Returns the total number of compressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    /// Finishes the stream and returns the ownership of the writer.
+    pub fn finish(self) -> std::io::Result<W> {
+        Ok(self.inner.finish()?.writer)
+    }
+
+    /// Finishes the stream without consuming the compressor.
+    ///
+    /// See [`WriteCompressor::try_finish`] for details.
+    ///
+    /// [`WriteCompressor::try_finish`]: struct.WriteCompressor.html#method.try_finish
+    pub fn try_finish(&mut self) -> std::io::Result<()> {
+        self.inner.try_finish()
+    }
+
+    /// Returns a mutable reference to the writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner.get_mut().writer
+    }
+
+    /// Returns a shared reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner.get_ref().writer
+    }
+
+    fn window_len(&self) -> u64 {
+        self.inner.total_in() - self.window_offset
+    }
+
+    fn timed<T>(
+        &mut self,
+        f: impl FnOnce(&mut WriteCompressor<TimedWriter<W>>) -> std::io::Result<T>,
+    ) -> std::io::Result<T> {
+        let write_time = self.inner.get_ref().elapsed;
+        let started = Instant::now();
+        let result = f(&mut self.inner);
+        let write_time = self.inner.get_ref().elapsed - write_time;
+        self.compress_time += started.elapsed().saturating_sub(write_time);
+        result
+    }
+
+    fn adapt(&mut self) -> std::io::Result<()> {
+        let write_time = self.inner.get_ref().elapsed - self.window_write_time;
+        let index = if write_time > self.compress_time * BOTTLENECK_RATIO {
+            cmp::min(self.index + 1, self.profiles.len() - 1)
+        } else if self.compress_time > write_time * BOTTLENECK_RATIO {
+            self.index.saturating_sub(1)
+        } else {
+            self.index
+        };
+        self.select(index)?;
+        self.window_offset = self.inner.total_in();
+        self.window_write_time = self.inner.get_ref().elapsed;
+        self.compress_time = Duration::ZERO;
+        Ok(())
+    }
+
+    fn select(&mut self, index: usize) -> std::io::Result<()> {
+        let profile = self.profiles[index];
+        self.index = index;
+        if profile == self.profile {
+            return Ok(());
+        }
+        let prefs = profile_prefs(self.prefs, profile);
+        if profile.is_high_compression() == self.profile.is_high_compression() {
+            self.inner.set_level(&prefs)?;
+        } else if self.prefs.frame_info().content_size() == 0 || self.inner.total_in() == 0 {
+            self.inner.start_frame(prefs)?;
+        } else {
+            // Another frame would break the declared content size.
+            return Ok(());
+        }
+        self.profile = profile;
+        Ok(())
+    }
+}
+
+fn profile_prefs(prefs: Preferences, profile: Profile) -> Preferences {
+    PreferencesBuilder::from(prefs).profile(profile).build()
+}
+
+impl<W> fmt::Debug for AdaptiveWriteCompressor<W>
+where
+    W: Write + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AdaptiveWriteCompressor")
+            .field("writer", self.get_ref())
+            .field("profile", &self.profile())
+            .field("window_size", &self.window_size)
+            .finish()
+    }
+}
+
+impl<W: Write> Write for AdaptiveWriteCompressor<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // The window may have been shrunk below the current length by `set_window_size`.
+        if self.window_len() >= self.window_size {
+            self.adapt()?;
+        }
+        let remaining = self.window_size - self.window_len();
+        let len = cmp::min(buf.len() as u64, remaining) as usize;
+        let len = self.timed(|inner| inner.write(&buf[..len]))?;
+        if self.window_len() >= self.window_size {
+            self.adapt()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.timed(|inner| inner.flush())
+    }
+}
+
+/// A writer which measures the time spent in the underlying writer.
+struct TimedWriter<W> {
+    writer: W,
+    elapsed: Duration,
+}
+
+impl<W: Write> Write for TimedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let started = Instant::now();
+        let result = self.writer.write(buf);
+        self.elapsed += started.elapsed();
+        result
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let started = Instant::now();
+        let result = self.writer.flush();
+        self.elapsed += started.elapsed();
+        result
+    }
+}
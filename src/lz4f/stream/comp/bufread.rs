@@ -1,5 +1,6 @@
 use super::{Compressor, Dictionary, Preferences};
-use crate::lz4f::Result;
+use crate::lz4f::CompressionContext;
+use crate::{lz4f::Result, Stats};
 use std::{
     fmt,
     io::{BufRead, Read},
@@ -60,6 +61,62 @@ impl<R: BufRead> BufReadCompressor<R> {
         })
     }
 
+    /// Creates a new `BufReadCompressor<R>` with a reusable compression context.
+    pub fn with_context(reader: R, prefs: Preferences, ctx: CompressionContext) -> Self {
+        Self {
+            inner: reader,
+            comp: Compressor::with_context(ctx, prefs, None),
+            consumed: 0,
+        }
+    }
+
+    /// Starts a new stream on `reader` with `prefs`.
+    ///
+    /// Compressed data which has not been read yet is discarded.
+    /// The compression context is reused, which avoids allocating a new one.
+    /// Returns the previous reader.
+    pub fn reset(&mut self, reader: R, prefs: Preferences) -> R {
+        self.comp.reset(prefs);
+        self.consumed = 0;
+        std::mem::replace(&mut self.inner, reader)
+    }
+
+    /// Returns the total number of uncompressed bytes consumed from the reader.
+    pub fn total_in(&self) -> u64 {
+        self.comp.stats().uncompressed_len()
+    }
+
+    /// Returns the total number of compressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.comp.stats().compressed_len()
+    }
+
+    /// Returns the compression ratio of the data compressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        self.comp.stats().ratio()
+    }
+
+    /// Enables the detection of incompressible data.
+    ///
+    /// See [`WriteCompressor::set_detect_incompressible`] for details.
+    ///
+    /// [`WriteCompressor::set_detect_incompressible`]: struct.WriteCompressor.html#method.set_detect_incompressible
+    pub fn set_detect_incompressible(&mut self, flag: bool) {
+        self.comp.set_detect_incompressible(flag);
+    }
+
+    /// Sets a callback which receives the statistics of each finished frame.
+    pub fn set_frame_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.comp.set_frame_callback(Box::new(callback));
+    }
+
     /// Returns ownership of the reader.
     pub fn into_inner(self) -> R {
         self.inner
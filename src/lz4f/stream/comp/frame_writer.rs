@@ -0,0 +1,163 @@
+use super::{Dictionary, Preferences, WriteCompressor};
+use crate::lz4f::Result;
+use std::{
+    cmp, fmt,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+/// The [`Write`]-based streaming compressor which splits the stream into frames.
+///
+/// `FrameWriter` ends the current frame once it reaches the maximum size
+/// or gets older than the maximum duration, and the following data goes to a new frame.
+/// Each finished frame is flushed and can be decoded on its own.
+///
+/// The duration is checked on `write` and `flush` calls;
+/// no frame is ended while the writer is idle.
+///
+/// # Example
+///
+/// ```
+/// use lzzzz::lz4f::{self, FrameWriter};
+/// use std::io::prelude::*;
+///
+/// let mut w = FrameWriter::new(Vec::new(), Default::default())?;
+/// w.set_max_frame_size(4);
+/// w.write_all(b"Hello world!")?;
+/// let comp = w.finish()?;
+///
+/// let mut buf = Vec::new();
+/// let mut src = &comp[..];
+/// let mut frames = 0;
+/// while !src.is_empty() {
+///     let (len, _) = lz4f::decompress_to_vec_with_consumed(src, &mut buf)?;
+///     src = &src[len..];
+///     frames += 1;
+/// }
+///
+/// assert_eq!(frames, 3);
+/// assert_eq!(&buf[..], b"Hello world!");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+pub struct FrameWriter<W: Write> {
+    inner: WriteCompressor<W>,
+    max_frame_size: u64,
+    max_frame_duration: Option<Duration>,
+    frame_offset: u64,
+    frame_started: Option<Instant>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Creates a new `FrameWriter<W>`.
+    pub fn new(writer: W, prefs: Preferences) -> Result<Self> {
+        Ok(Self::from_compressor(WriteCompressor::new(writer, prefs)?))
+    }
+
+    /// Creates a new `FrameWriter<W>` with a dictionary.
+    pub fn with_dict(writer: W, prefs: Preferences, dict: Dictionary) -> Result<Self> {
+        Ok(Self::from_compressor(WriteCompressor::with_dict(
+            writer, prefs, dict,
+        )?))
+    }
+
+    fn from_compressor(inner: WriteCompressor<W>) -> Self {
+        Self {
+            inner,
+            max_frame_size: u64::MAX,
+            max_frame_duration: None,
+            frame_offset: 0,
+            frame_started: None,
+        }
+    }
+
+    /// Sets the maximum number of uncompressed bytes in a frame.
+    ///
+    /// The size must be greater than zero.
+    pub fn set_max_frame_size(&mut self, size: u64) {
+        self.max_frame_size = cmp::max(size, 1);
+    }
+
+    /// Sets the maximum duration of a frame, counted from its first write.
+    pub fn set_max_frame_duration(&mut self, duration: Duration) {
+        self.max_frame_duration = Some(duration);
+    }
+
+    /// Ends the current frame.
+    ///
+    /// See [`WriteCompressor::finish_frame`] for details.
+    ///
+    /// [`WriteCompressor::finish_frame`]: struct.WriteCompressor.html#method.finish_frame
+    pub fn finish_frame(&mut self) -> std::io::Result<()> {
+        self.inner.finish_frame()?;
+        self.frame_offset = self.inner.total_in();
+        self.frame_started = None;
+        Ok(())
+    }
+
+    /// Finishes the stream and returns the ownership of the writer.
+    pub fn finish(self) -> std::io::Result<W> {
+        self.inner.finish()
+    }
+
+    /// Returns a mutable reference to the writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.get_mut()
+    }
+
+    /// Returns a shared reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.get_ref()
+    }
+
+    fn frame_len(&self) -> u64 {
+        self.inner.total_in() - self.frame_offset
+    }
+
+    fn is_expired(&self) -> bool {
+        match (self.frame_started, self.max_frame_duration) {
+            (Some(started), Some(duration)) => started.elapsed() >= duration,
+            _ => false,
+        }
+    }
+}
+
+impl<W> fmt::Debug for FrameWriter<W>
+where
+    W: Write + fmt::Debug,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("FrameWriter")
+            .field("inner", &self.inner)
+            .field("max_frame_size", &self.max_frame_size)
+            .field("max_frame_duration", &self.max_frame_duration)
+            .finish()
+    }
+}
+
+impl<W: Write> Write for FrameWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.is_expired() || self.frame_len() >= self.max_frame_size {
+            self.finish_frame()?;
+        }
+        let remaining = self.max_frame_size - self.frame_len();
+        let len = cmp::min(buf.len() as u64, remaining) as usize;
+        let len = self.inner.write(&buf[..len])?;
+        if len > 0 && self.frame_started.is_none() {
+            self.frame_started = Some(Instant::now());
+        }
+        if self.frame_len() >= self.max_frame_size {
+            self.finish_frame()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.is_expired() {
+            self.finish_frame()
+        } else {
+            self.inner.flush()
+        }
+    }
+}
@@ -1,71 +1,191 @@
 //! Streaming LZ4F compressors.
+mod adaptive;
 mod bufread;
+mod frame_writer;
 mod read;
 mod write;
 
 use crate::lz4f::Result;
 
+pub use adaptive::*;
 pub use bufread::*;
+pub use frame_writer::*;
 pub use read::*;
 pub use write::*;
 
-use crate::lz4f::{
-    api::{CompressionContext, LZ4F_HEADER_SIZE_MAX},
-    Dictionary, Preferences,
+use crate::{
+    lz4,
+    lz4f::{
+        api::{self, LZ4F_HEADER_SIZE_MAX},
+        recovery::max_block_size,
+        AutoFlush, BlockMode, CompressionContext, Dictionary, Preferences,
+    },
+    Stats, StatsCallback,
 };
+use std::cmp;
 
 pub(crate) struct Compressor {
     ctx: CompressionContext,
     prefs: Preferences,
     state: State,
     buffer: Vec<u8>,
+    total_in: u64,
+    total_out: u64,
+    frame_start: Stats,
+    frame_callback: Option<StatsCallback>,
+    dict_id: u32,
+    detect_incompressible: bool,
+    block_len: usize,
+    block_stored: bool,
 }
 
 impl Compressor {
     pub fn new(prefs: Preferences, dict: Option<Dictionary>) -> Result<Self> {
-        Ok(Self {
-            ctx: CompressionContext::new(dict)?,
+        Ok(Self::with_context(CompressionContext::new()?, prefs, dict))
+    }
+
+    pub fn with_context(
+        mut ctx: CompressionContext,
+        prefs: Preferences,
+        dict: Option<Dictionary>,
+    ) -> Self {
+        let dict_id = dict.as_ref().map_or(0, Dictionary::dict_id);
+        ctx.get_mut().set_dict(dict);
+        Self {
+            ctx,
             prefs,
             state: State::Created,
             buffer: Vec::with_capacity(LZ4F_HEADER_SIZE_MAX),
-        })
+            total_in: 0,
+            total_out: 0,
+            frame_start: Stats::default(),
+            frame_callback: None,
+            dict_id,
+            detect_incompressible: false,
+            block_len: 0,
+            block_stored: false,
+        }
+    }
+
+    pub fn reset(&mut self, prefs: Preferences) {
+        self.prefs = prefs;
+        self.state = State::Created;
+        self.buffer.clear();
+        self.total_in = 0;
+        self.total_out = 0;
+        self.frame_start = Stats::default();
+        self.block_len = 0;
     }
 
     pub fn prefs(&self) -> &Preferences {
         &self.prefs
     }
 
+    pub fn set_prefs(&mut self, prefs: Preferences) {
+        self.prefs = prefs;
+    }
+
+    /// Replaces the compression level and the decompression speed flag
+    /// without ending the current frame.
+    pub fn set_level(&mut self, prefs: &Preferences) -> Result<()> {
+        if self.is_active() {
+            self.ctx.get_mut().set_compression_level(prefs)?;
+        }
+        self.prefs.set_compression_level(prefs.compression_level());
+        self.prefs.set_favor_dec_speed(prefs.favor_dec_speed());
+        Ok(())
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, State::Active)
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats::new(self.total_in, self.total_out)
+    }
+
+    pub fn set_frame_callback(&mut self, callback: StatsCallback) {
+        self.frame_callback = Some(callback);
+    }
+
+    pub fn set_detect_incompressible(&mut self, flag: bool) {
+        self.detect_incompressible = flag;
+    }
+
     fn begin(&mut self) -> Result<()> {
         if let State::Created = self.state {
             assert!(self.buffer.is_empty());
             self.state = State::Active;
-            let len = self.ctx.begin(
+            let mut prefs = self.prefs;
+            if prefs.frame_info().dict_id() == 0 {
+                prefs.set_dict_id(self.dict_id);
+            }
+            let len = self.ctx.get_mut().begin(
                 self.buffer.as_mut_ptr(),
                 self.buffer.capacity(),
-                &self.prefs,
+                &prefs,
             )?;
             #[allow(unsafe_code)]
             unsafe {
                 self.buffer.set_len(len);
             }
+            self.total_out += len as u64;
         }
         Ok(())
     }
 
     pub fn update(&mut self, src: &[u8], stable_src: bool) -> Result<()> {
+        if let State::Finished = self.state {
+            self.state = State::Created;
+        }
         self.begin()?;
-        let ext_len = CompressionContext::compress_bound(src.len(), &self.prefs);
+        if !self.detects_incompressible() {
+            return self.update_block(src, stable_src, false);
+        }
+
+        // Decide at the beginning of each block whether to store it,
+        // so that no block is split by a change of the mode.
+        let block_size = max_block_size(self.prefs.frame_info().block_size());
+        let mut src = src;
+        while !src.is_empty() {
+            if self.block_len == 0 {
+                let block = &src[..cmp::min(src.len(), block_size)];
+                self.block_stored = lz4::is_incompressible(block);
+            }
+            let len = cmp::min(src.len(), block_size - self.block_len);
+            self.block_len = (self.block_len + len) % block_size;
+            if self.prefs.auto_flush() == AutoFlush::Enabled {
+                self.block_len = 0;
+            }
+            self.update_block(&src[..len], stable_src, self.block_stored)?;
+            src = &src[len..];
+        }
+        Ok(())
+    }
+
+    fn detects_incompressible(&self) -> bool {
+        // liblz4 supports uncompressed blocks only in the independent block mode.
+        self.detect_incompressible
+            && self.prefs.frame_info().block_mode() == BlockMode::Independent
+    }
+
+    fn update_block(&mut self, src: &[u8], stable_src: bool, stored: bool) -> Result<()> {
+        let ext_len = api::CompressionContext::compress_bound(src.len(), &self.prefs);
         self.buffer.reserve(ext_len);
         let offset = self.buffer.len();
         #[allow(unsafe_code)]
         unsafe {
-            let len = self.ctx.update(
-                self.buffer.as_mut_ptr().add(offset),
-                self.buffer.capacity() - offset,
-                src,
-                stable_src,
-            )?;
+            let ctx = self.ctx.get_mut();
+            let dst = self.buffer.as_mut_ptr().add(offset);
+            let dst_len = self.buffer.capacity() - offset;
+            let len = if stored {
+                ctx.uncompressed_update(dst, dst_len, src, stable_src)?
+            } else {
+                ctx.update(dst, dst_len, src, stable_src)?
+            };
             self.buffer.set_len(offset + len);
+            self.total_in += src.len() as u64;
+            self.total_out += len as u64;
             if len == 0 {
                 self.flush(stable_src)
             } else {
@@ -75,19 +195,24 @@ impl Compressor {
     }
 
     pub fn flush(&mut self, stable_src: bool) -> Result<()> {
+        if let State::Finished = self.state {
+            return Ok(());
+        }
         self.begin()?;
-        let ext_len = CompressionContext::compress_bound(0, &self.prefs);
+        let ext_len = api::CompressionContext::compress_bound(0, &self.prefs);
         self.buffer.reserve(ext_len);
         let offset = self.buffer.len();
         #[allow(unsafe_code)]
         unsafe {
-            let len = self.ctx.flush(
+            let len = self.ctx.get_mut().flush(
                 self.buffer.as_mut_ptr().add(offset),
                 self.buffer.capacity() - offset,
                 stable_src,
             )?;
             self.buffer.set_len(offset + len);
+            self.total_out += len as u64;
         }
+        self.block_len = 0;
         Ok(())
     }
 
@@ -95,18 +220,29 @@ impl Compressor {
         self.begin()?;
         if let State::Active = self.state {
             self.state = State::Finished;
-            let ext_len = CompressionContext::compress_bound(0, &self.prefs);
+            self.block_len = 0;
+            let ext_len = api::CompressionContext::compress_bound(0, &self.prefs);
             self.buffer.reserve(ext_len);
             let offset = self.buffer.len();
             #[allow(unsafe_code)]
             unsafe {
-                let len = self.ctx.end(
+                let len = self.ctx.get_mut().end(
                     self.buffer.as_mut_ptr().add(offset),
                     self.buffer.capacity() - offset,
                     stable_src,
                 )?;
                 self.buffer.set_len(offset + len);
+                self.total_out += len as u64;
+            }
+
+            let stats = self.stats();
+            if let Some(callback) = &mut self.frame_callback {
+                callback(&Stats::new(
+                    stats.uncompressed_len() - self.frame_start.uncompressed_len(),
+                    stats.compressed_len() - self.frame_start.compressed_len(),
+                ));
             }
+            self.frame_start = stats;
         }
         Ok(())
     }
@@ -1,5 +1,6 @@
 use super::{Compressor, Dictionary, Preferences};
-use crate::lz4f::Result;
+use crate::lz4f::CompressionContext;
+use crate::{lz4f::Result, Stats};
 use std::{fmt, io::Write};
 
 /// The [`Write`]-based streaming compressor.
@@ -20,6 +21,7 @@ use std::{fmt, io::Write};
 /// let mut w = WriteCompressor::new(&mut f, Default::default())?;
 ///
 /// w.write_all(b"Hello world!")?;
+/// w.finish()?;
 /// # Ok::<(), std::io::Error>(())
 /// ```
 ///
@@ -27,6 +29,8 @@ use std::{fmt, io::Write};
 pub struct WriteCompressor<W: Write> {
     inner: Option<W>,
     comp: Compressor,
+    written: usize,
+    unfinished: bool,
 }
 
 impl<W: Write> WriteCompressor<W> {
@@ -35,6 +39,8 @@ impl<W: Write> WriteCompressor<W> {
         Ok(Self {
             inner: Some(writer),
             comp: Compressor::new(prefs, None)?,
+            written: 0,
+            unfinished: false,
         })
     }
 
@@ -43,9 +49,93 @@ impl<W: Write> WriteCompressor<W> {
         Ok(Self {
             inner: Some(writer),
             comp: Compressor::new(prefs, Some(dict))?,
+            written: 0,
+            unfinished: false,
         })
     }
 
+    /// Returns the total number of uncompressed bytes written.
+    pub fn total_in(&self) -> u64 {
+        self.comp.stats().uncompressed_len()
+    }
+
+    /// Returns the total number of compressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.comp.stats().compressed_len()
+    }
+
+    /// Returns the compression ratio of the data compressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        self.comp.stats().ratio()
+    }
+
+    /// Creates a new `WriteCompressor<W>` with a reusable compression context.
+    pub fn with_context(writer: W, prefs: Preferences, ctx: CompressionContext) -> Self {
+        Self {
+            inner: Some(writer),
+            comp: Compressor::with_context(ctx, prefs, None),
+            written: 0,
+            unfinished: false,
+        }
+    }
+
+    /// Finishes the current stream and starts a new one on `writer` with `prefs`.
+    ///
+    /// The compression context is reused, which avoids allocating a new one.
+    /// Returns the previous writer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4f::WriteCompressor;
+    /// use std::io::prelude::*;
+    ///
+    /// let mut w = WriteCompressor::new(Vec::new(), Default::default())?;
+    /// w.write_all(b"Hello")?;
+    /// let first = w.reset(Vec::new(), Default::default())?;
+    /// w.write_all(b"world!")?;
+    /// let second = w.finish()?;
+    ///
+    /// # let mut buf = Vec::new();
+    /// # lzzzz::lz4f::decompress_to_vec(&first, &mut buf)?;
+    /// # lzzzz::lz4f::decompress_to_vec(&second, &mut buf)?;
+    /// # assert_eq!(&buf[..], b"Helloworld!");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reset(&mut self, writer: W, prefs: Preferences) -> std::io::Result<W> {
+        self.end()?;
+        let prev = self.inner.replace(writer).unwrap();
+        self.comp.reset(prefs);
+        Ok(prev)
+    }
+
+    /// Enables the detection of incompressible data.
+    ///
+    /// Blocks which look incompressible, such as random or encrypted data,
+    /// are written as uncompressed blocks without spending time on compression.
+    /// The decision is made with [`lz4::is_incompressible`] on the data available
+    /// when a block starts, so it works best when the data comes in whole blocks.
+    ///
+    /// liblz4 supports uncompressed blocks only in the independent block mode,
+    /// so the detection is ignored in the linked block mode. Disabled by default.
+    ///
+    /// [`lz4::is_incompressible`]: ../lz4/fn.is_incompressible.html
+    pub fn set_detect_incompressible(&mut self, flag: bool) {
+        self.comp.set_detect_incompressible(flag);
+    }
+
+    /// Sets a callback which receives the statistics of each finished frame.
+    pub fn set_frame_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.comp.set_frame_callback(Box::new(callback));
+    }
+
     /// Returns a mutable reference to the writer.
     pub fn get_mut(&mut self) -> &mut W {
         self.inner.as_mut().unwrap()
@@ -57,21 +147,119 @@ impl<W: Write> WriteCompressor<W> {
     }
 
     /// Returns the ownership of the writer, finishing the stream in the process.
+    ///
+    /// Errors while finishing the stream are ignored. Use [`finish`] to handle them.
+    ///
+    /// [`finish`]: #method.finish
     pub fn into_inner(mut self) -> W {
         let _ = self.end();
         self.inner.take().unwrap()
     }
 
+    /// Finishes the stream and returns the ownership of the writer.
+    ///
+    /// This writes the EndMark and the content checksum, then flushes the writer.
+    /// Unlike [`into_inner`] and dropping the compressor, this reports write errors.
+    ///
+    /// Dropping a compressor after writing data without calling `finish` or
+    /// [`try_finish`] fails a debug assertion. Earlier versions finished the stream
+    /// on drop silently, so code which relies on that has to call `finish` now.
+    ///
+    /// [`into_inner`]: #method.into_inner
+    /// [`try_finish`]: #method.try_finish
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let result = self.end();
+        let writer = self.inner.take().unwrap();
+        result.map(|_| writer)
+    }
+
+    /// Ends the current frame.
+    ///
+    /// This writes the EndMark and the content checksum, then flushes the writer,
+    /// so the data written so far can be decoded without the following data.
+    /// Subsequent writes start a new frame with the same preferences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4f::{self, WriteCompressor};
+    /// use std::io::prelude::*;
+    ///
+    /// let mut w = WriteCompressor::new(Vec::new(), Default::default())?;
+    /// w.write_all(b"Hello ")?;
+    /// w.finish_frame()?;
+    /// w.write_all(b"world!")?;
+    /// let comp = w.finish()?;
+    ///
+    /// let mut buf = Vec::new();
+    /// let (len, _) = lz4f::decompress_to_vec_with_consumed(&comp, &mut buf)?;
+    /// lz4f::decompress_to_vec(&comp[len..], &mut buf)?;
+    /// assert_eq!(&buf[..], b"Hello world!");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn finish_frame(&mut self) -> std::io::Result<()> {
+        self.end()
+    }
+
+    /// Ends the current frame and uses `prefs` for the following frames.
+    ///
+    /// If no data has been written since the last frame ended,
+    /// this only replaces the preferences.
+    pub fn start_frame(&mut self, prefs: Preferences) -> std::io::Result<()> {
+        if self.comp.is_active() {
+            self.end()?;
+        }
+        self.comp.set_prefs(prefs);
+        Ok(())
+    }
+
+    /// Uses the compression level of `prefs` from the next block without ending the frame.
+    ///
+    /// The level must stay in the same family (fast or HC) during a frame.
+    pub(super) fn set_level(&mut self, prefs: &Preferences) -> Result<()> {
+        self.comp.set_level(prefs)
+    }
+
+    /// Finishes the stream without consuming the compressor.
+    ///
+    /// If this fails, the data which has not reached the writer is kept
+    /// and the next call retries writing it without duplicating the rest.
+    pub fn try_finish(&mut self) -> std::io::Result<()> {
+        self.end()
+    }
+
     fn end(&mut self) -> std::io::Result<()> {
-        if let Some(device) = &mut self.inner {
+        // An explicit attempt reports its own errors, so dropping afterwards is fine.
+        self.unfinished = false;
+        if self.inner.is_some() {
             self.comp.end(false)?;
-            device.write_all(self.comp.buf())?;
-            self.comp.clear_buf();
-            device.flush()?;
+            self.write_buf()?;
+            self.get_mut().flush()?;
         }
 
         Ok(())
     }
+
+    /// Writes the pending compressed data.
+    ///
+    /// The number of bytes accepted by the writer is tracked,
+    /// so a partial write followed by an error can be resumed.
+    fn write_buf(&mut self) -> std::io::Result<()> {
+        if let Some(device) = &mut self.inner {
+            let buf = self.comp.buf();
+            while self.written < buf.len() {
+                match device.write(&buf[self.written..]) {
+                    Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                    Ok(len) => self.written += len,
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            self.comp.clear_buf();
+            self.written = 0;
+        }
+        Ok(())
+    }
 }
 
 impl<W> fmt::Debug for WriteCompressor<W>
@@ -88,22 +276,31 @@ where
 
 impl<W: Write> Write for WriteCompressor<W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_buf()?;
         self.comp.update(buf, false)?;
-        self.inner.as_mut().unwrap().write_all(self.comp.buf())?;
-        self.comp.clear_buf();
+        if !buf.is_empty() {
+            self.unfinished = true;
+        }
+        // `buf` is consumed at this point, so a write error is reported
+        // by the next call, which resumes writing the pending data.
+        let _ = self.write_buf();
         Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
+        self.write_buf()?;
         self.comp.flush(false)?;
-        self.inner.as_mut().unwrap().write_all(self.comp.buf())?;
-        self.comp.clear_buf();
-        self.inner.as_mut().unwrap().flush()
+        self.write_buf()?;
+        self.get_mut().flush()
     }
 }
 
 impl<W: Write> Drop for WriteCompressor<W> {
     fn drop(&mut self) {
+        debug_assert!(
+            !self.unfinished || std::thread::panicking(),
+            "WriteCompressor dropped without being finished; use finish() to handle write errors"
+        );
         let _ = self.end();
     }
 }
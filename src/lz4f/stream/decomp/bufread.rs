@@ -1,5 +1,12 @@
 use super::Decompressor;
-use crate::lz4f::{FrameInfo, Result};
+use crate::{
+    lz4f::{
+        recovery::{Recovery, RecoveryCallback},
+        DamagedRegion, DecompressionContext, DecompressionLimits, DictionaryRegistry, Error,
+        ErrorKind, FrameInfo, Result, ValidationPolicy,
+    },
+    Stats,
+};
 use std::{
     borrow::Cow,
     fmt,
@@ -42,6 +49,7 @@ pub struct BufReadDecompressor<'a, R: BufRead> {
     pub(super) inner: R,
     decomp: Decompressor<'a>,
     consumed: usize,
+    recovery: Option<Recovery<RecoveryCallback<'a>>>,
 }
 
 impl<'a, R: BufRead> BufReadDecompressor<'a, R> {
@@ -51,9 +59,20 @@ impl<'a, R: BufRead> BufReadDecompressor<'a, R> {
             inner: reader,
             decomp: Decompressor::new()?,
             consumed: 0,
+            recovery: None,
         })
     }
 
+    /// Creates a new `BufReadDecompressor<R>` with a reusable decompression context.
+    pub fn with_context(reader: R, ctx: DecompressionContext) -> Self {
+        Self {
+            inner: reader,
+            decomp: Decompressor::with_context(ctx),
+            consumed: 0,
+            recovery: None,
+        }
+    }
+
     /// Sets the dictionary.
     pub fn set_dict<D>(&mut self, dict: D)
     where
@@ -62,11 +81,170 @@ impl<'a, R: BufRead> BufReadDecompressor<'a, R> {
         self.decomp.set_dict(dict);
     }
 
+    /// Sets the dictionary registry.
+    ///
+    /// If a frame header has a non-zero `dict_id`, the dictionary is taken from the registry
+    /// instead of the one set by [`set_dict`]. An unregistered ID raises `DictionaryNotFound`.
+    ///
+    /// [`set_dict`]: #method.set_dict
+    pub fn set_dict_registry(&mut self, registry: DictionaryRegistry) {
+        self.decomp.set_dict_registry(registry);
+    }
+
+    /// Sets the decompression limits.
+    ///
+    /// Going over a limit raises `DecompressionLimitExceeded`.
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.decomp.set_limits(limits);
+        if let Some(recovery) = &mut self.recovery {
+            recovery.set_limits(limits);
+        }
+    }
+
+    /// Sets the frame validation policy.
+    ///
+    /// Data following a frame is decoded as the next frame, so trailing garbage
+    /// fails as an invalid frame header regardless of `reject_trailing_data`.
+    /// In the single-frame mode, the data after the frame is left unread and not validated.
+    pub fn set_validation_policy(&mut self, policy: ValidationPolicy) {
+        self.decomp.set_validation_policy(policy);
+    }
+
+    /// Stops decompression at the end of the current frame.
+    ///
+    /// By default, data following a frame is decoded as the next frame.
+    /// When this mode is enabled, the decompressor reaches EOF at the end of the frame
+    /// and leaves the reader positioned just after it,
+    /// so the rest of the data can be read from the reader.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4f::{self, BufReadDecompressor};
+    /// use std::io::prelude::*;
+    ///
+    /// let mut data = Vec::new();
+    /// lz4f::compress_to_vec(b"Hello world!", &mut data, &Default::default())?;
+    /// data.extend_from_slice(b"trailer");
+    ///
+    /// let mut src = &data[..];
+    /// let mut r = BufReadDecompressor::new(&mut src)?;
+    /// r.set_single_frame(true);
+    ///
+    /// let mut buf = Vec::new();
+    /// r.read_to_end(&mut buf)?;
+    /// assert_eq!(&buf[..], b"Hello world!");
+    /// assert_eq!(r.total_in() as usize, data.len() - 7);
+    ///
+    /// drop(r);
+    /// assert_eq!(src, b"trailer");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_single_frame(&mut self, flag: bool) {
+        self.decomp.set_single_frame(flag);
+    }
+
+    /// Enables the recovery mode.
+    ///
+    /// In the recovery mode, decoding errors do not abort the decompression.
+    /// Corrupt blocks in frames with independent blocks are skipped, and otherwise
+    /// the decompressor searches for the next frame magic number and resumes from there,
+    /// like [`decompress_to_vec_with_recovery`]. Each skipped region is passed to `report`,
+    /// with the offsets relative to the beginning of the stream.
+    ///
+    /// If `gap_fill` is set, each lost block is replaced with the fill byte
+    /// as [`decompress_to_vec_with_gap_fill`] does.
+    ///
+    /// The recovery mode must be enabled before reading any data.
+    /// The limits set by [`set_limits`] are also enforced in this mode.
+    /// Dictionaries, the validation policy, the single-frame mode and
+    /// the frame callback do not apply in this mode, and the reader may be read ahead.
+    ///
+    /// [`set_limits`]: #method.set_limits
+    /// [`decompress_to_vec_with_recovery`]: fn.decompress_to_vec_with_recovery.html
+    /// [`decompress_to_vec_with_gap_fill`]: fn.decompress_to_vec_with_gap_fill.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzzzz::lz4f::{self, BufReadDecompressor};
+    /// use std::io::prelude::*;
+    ///
+    /// let data = b"The quick brown fox jumps over the lazy dog.";
+    /// let mut comp = Vec::new();
+    /// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+    /// lz4f::compress_to_vec(data, &mut comp, &Default::default())?;
+    ///
+    /// // Break the first frame.
+    /// comp[10] ^= 0xff;
+    ///
+    /// let mut damaged = Vec::new();
+    /// let mut buf = Vec::new();
+    /// {
+    ///     let mut src = &comp[..];
+    ///     let mut r = BufReadDecompressor::new(&mut src)?;
+    ///     r.set_recovery(None, |region| damaged.push(*region))?;
+    ///     r.read_to_end(&mut buf)?;
+    /// }
+    ///
+    /// assert_eq!(&buf[..], &data[..]);
+    /// assert_eq!(damaged.len(), 1);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_recovery<F>(&mut self, gap_fill: Option<u8>, report: F) -> Result<()>
+    where
+        F: FnMut(&DamagedRegion) + Send + 'a,
+    {
+        let report = Box::new(report) as RecoveryCallback<'a>;
+        self.recovery = Some(Recovery::new(gap_fill, self.decomp.limits(), report)?);
+        Ok(())
+    }
+
+    fn stats(&self) -> Stats {
+        match &self.recovery {
+            Some(recovery) => recovery.stats(),
+            None => self.decomp.stats(),
+        }
+    }
+
+    /// Returns the total number of compressed bytes consumed from the reader.
+    pub fn total_in(&self) -> u64 {
+        self.stats().compressed_len()
+    }
+
+    /// Returns the total number of decompressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.stats().uncompressed_len()
+    }
+
+    /// Returns the compression ratio of the data decompressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        self.stats().ratio()
+    }
+
+    /// Sets a callback which receives the statistics of each decoded frame.
+    pub fn set_frame_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.decomp.set_frame_callback(Box::new(callback));
+    }
+
     /// Reads the frame header and returns `FrameInfo`.
     ///
     /// Calling this function before any `Read` or `BufRead` operations
     /// does not consume the frame body.
     pub fn read_frame_info(&mut self) -> std::io::Result<FrameInfo> {
+        if let Some(recovery) = &mut self.recovery {
+            recovery.fill_buf(&mut self.inner)?;
+            return recovery
+                .frame_info()
+                .ok_or_else(|| Error::new(ErrorKind::FrameHeaderIncomplete).into());
+        }
         loop {
             if let Some(frame) = self.decomp.frame_info() {
                 return Ok(frame);
@@ -106,11 +284,24 @@ where
 
 impl<R: BufRead> Read for BufReadDecompressor<'_, R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        loop {
+        if let Some(recovery) = &mut self.recovery {
+            let data = recovery.fill_buf(&mut self.inner)?;
+            let len = std::cmp::min(buf.len(), data.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            recovery.consume(len);
+            return Ok(len);
+        }
+
+        while !self.decomp.is_stopped() {
             let inner_buf = self.inner.fill_buf()?;
+            let eof = inner_buf.is_empty();
             let consumed = self.decomp.decompress(inner_buf)?;
             self.inner.consume(consumed);
             if consumed == 0 {
+                // The buffered output is returned before reporting a truncated frame.
+                if eof && self.decomp.buf().is_empty() {
+                    self.decomp.check_eof()?;
+                }
                 break;
             }
         }
@@ -128,11 +319,21 @@ impl<R: BufRead> Read for BufReadDecompressor<'_, R> {
 
 impl<R: BufRead> BufRead for BufReadDecompressor<'_, R> {
     fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
-        let _ = self.read(&mut [])?;
-        Ok(&self.decomp.buf()[self.consumed..])
+        if self.recovery.is_none() {
+            let _ = self.read(&mut [])?;
+            return Ok(&self.decomp.buf()[self.consumed..]);
+        }
+        match &mut self.recovery {
+            Some(recovery) => recovery.fill_buf(&mut self.inner),
+            None => Ok(&[]),
+        }
     }
 
     fn consume(&mut self, amt: usize) {
+        if let Some(recovery) = &mut self.recovery {
+            recovery.consume(amt);
+            return;
+        }
         self.consumed += amt;
         if self.consumed >= self.decomp.buf().len() {
             self.decomp.clear_buf();
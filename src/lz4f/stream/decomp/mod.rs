@@ -10,22 +10,30 @@ pub use write::*;
 use crate::{
     common::DEFAULT_BUF_SIZE,
     lz4f::{
-        api::{
-            header_size, DecompressionContext, LZ4F_HEADER_SIZE_MAX,
-            LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH,
-        },
-        FrameInfo, Result,
+        api::{header_size, LZ4F_HEADER_SIZE_MAX, LZ4F_MIN_SIZE_TO_KNOW_HEADER_LENGTH},
+        DecompressionContext, DecompressionLimits, DictionaryRegistry, FrameInfo, Result,
+        ValidationPolicy,
     },
-    Error, ErrorKind,
+    Error, ErrorKind, Stats, StatsCallback,
 };
-use std::{borrow::Cow, cmp, pin::Pin, ptr};
+use std::{borrow::Cow, cmp, pin::Pin, ptr, sync::Arc};
 
 #[derive(Clone, Copy, PartialEq)]
-struct DictPtr(*const u8, usize);
+pub(crate) struct DictPtr(*const u8, usize);
 
 #[allow(unsafe_code)]
 unsafe impl Send for DictPtr {}
 
+impl DictPtr {
+    pub fn new(dict: &[u8]) -> Self {
+        if dict.is_empty() {
+            Self(ptr::null(), 0)
+        } else {
+            Self(dict.as_ptr(), dict.len())
+        }
+    }
+}
+
 enum State {
     Header {
         header: [u8; LZ4F_HEADER_SIZE_MAX],
@@ -34,29 +42,62 @@ enum State {
     Body {
         frame_info: FrameInfo,
         comp_dict: Option<DictPtr>,
+        finished: bool,
     },
 }
 
+impl State {
+    fn new() -> Self {
+        Self::Header {
+            header: [0; LZ4F_HEADER_SIZE_MAX],
+            header_len: 0,
+        }
+    }
+}
+
 pub(crate) struct Decompressor<'a> {
     ctx: DecompressionContext,
     state: State,
     buffer: Vec<u8>,
     dict: Pin<Cow<'a, [u8]>>,
+    registry: Option<DictionaryRegistry>,
+    frame_dict: Option<Arc<[u8]>>,
     header_only: bool,
+    limits: DecompressionLimits,
+    policy: ValidationPolicy,
+    frames: usize,
+    total_in: u64,
+    total_out: u64,
+    single_frame: bool,
+    frame_start: Stats,
+    reported_frames: usize,
+    frame_callback: Option<StatsCallback>,
 }
 
 impl<'a> Decompressor<'a> {
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            ctx: DecompressionContext::new()?,
-            state: State::Header {
-                header: [0; LZ4F_HEADER_SIZE_MAX],
-                header_len: 0,
-            },
+        Ok(Self::with_context(DecompressionContext::new()?))
+    }
+
+    pub fn with_context(ctx: DecompressionContext) -> Self {
+        Self {
+            ctx,
+            state: State::new(),
             buffer: Vec::new(),
             dict: Pin::new(Cow::Borrowed(&[])),
+            registry: None,
+            frame_dict: None,
             header_only: false,
-        })
+            limits: Default::default(),
+            policy: Default::default(),
+            frames: 0,
+            total_in: 0,
+            total_out: 0,
+            single_frame: false,
+            frame_start: Stats::default(),
+            reported_frames: 0,
+            frame_callback: None,
+        }
     }
 
     pub fn set_dict<D>(&mut self, dict: D)
@@ -66,6 +107,10 @@ impl<'a> Decompressor<'a> {
         self.dict = Pin::new(dict.into());
     }
 
+    pub fn set_dict_registry(&mut self, registry: DictionaryRegistry) {
+        self.registry = Some(registry);
+    }
+
     pub fn frame_info(&self) -> Option<FrameInfo> {
         if let State::Body { frame_info, .. } = self.state {
             Some(frame_info)
@@ -78,7 +123,76 @@ impl<'a> Decompressor<'a> {
         self.header_only = flag;
     }
 
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> DecompressionLimits {
+        self.limits
+    }
+
+    pub fn set_validation_policy(&mut self, policy: ValidationPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn set_single_frame(&mut self, flag: bool) {
+        self.single_frame = flag;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.single_frame && self.is_finished()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats::new(self.total_out, self.total_in)
+    }
+
+    pub fn set_frame_callback(&mut self, callback: StatsCallback) {
+        self.frame_callback = Some(callback);
+    }
+
     pub fn decompress(&mut self, src: &[u8]) -> Result<usize> {
+        let len = self.decompress_frame(src)?;
+        self.total_in += len as u64;
+        if self.is_finished() && self.reported_frames < self.frames {
+            self.reported_frames = self.frames;
+            let stats = self.stats();
+            if let Some(callback) = &mut self.frame_callback {
+                callback(&Stats::new(
+                    stats.uncompressed_len() - self.frame_start.uncompressed_len(),
+                    stats.compressed_len() - self.frame_start.compressed_len(),
+                ));
+            }
+            self.frame_start = stats;
+        }
+        Ok(len)
+    }
+
+    /// Fails with `CompressedDataIncomplete` if the input ended in the middle of a frame.
+    pub fn check_eof(&self) -> Result<()> {
+        match self.state {
+            State::Header { header_len, .. } if header_len > 0 => {
+                Err(Error::new(ErrorKind::CompressedDataIncomplete).into())
+            }
+            State::Body {
+                finished: false, ..
+            } if !self.header_only => Err(Error::new(ErrorKind::CompressedDataIncomplete).into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.state, State::Body { finished: true, .. })
+    }
+
+    fn decompress_frame(&mut self, src: &[u8]) -> Result<usize> {
+        if let State::Body { finished: true, .. } = self.state {
+            if src.is_empty() || self.single_frame {
+                return Ok(0);
+            }
+            self.state = State::new();
+        }
+
         let mut header_consumed = 0;
         if let State::Header {
             ref mut header,
@@ -104,12 +218,25 @@ impl<'a> Decompressor<'a> {
                     header_consumed += len;
                 }
                 if *header_len >= exact_header_len {
-                    let (frame, rep) = self.ctx.get_frame_info(&header[..*header_len])?;
+                    self.policy.check_header(&header[..*header_len])?;
+                    let (frame, rep) = self.ctx.get().get_frame_info(&header[..*header_len])?;
                     header_consumed = cmp::min(header_consumed, rep);
 
+                    self.frames += 1;
+                    self.limits.check_frame(self.frames, frame.content_size())?;
+                    self.policy.check_frame(&frame)?;
+                    self.frame_dict = match (&self.registry, frame.dict_id()) {
+                        (Some(registry), dict_id) if dict_id != 0 => Some(
+                            registry
+                                .get_shared(dict_id)
+                                .ok_or_else(|| Error::new(ErrorKind::DictionaryNotFound))?,
+                        ),
+                        _ => None,
+                    };
                     self.state = State::Body {
                         frame_info: frame,
                         comp_dict: None,
+                        finished: false,
                     }
                 }
             }
@@ -117,7 +244,7 @@ impl<'a> Decompressor<'a> {
 
         if let State::Header { header, header_len } = self.state {
             if src.is_empty() {
-                self.ctx.get_frame_info(&header[..header_len])?;
+                self.ctx.get().get_frame_info(&header[..header_len])?;
             }
         }
 
@@ -128,21 +255,35 @@ impl<'a> Decompressor<'a> {
         let src = &src[header_consumed..];
         let dict_ptr = self.dict_ptr();
         if let State::Body {
-            ref mut comp_dict, ..
+            ref mut comp_dict,
+            ref mut finished,
+            ..
         } = &mut self.state
         {
             if dict_ptr != *comp_dict.get_or_insert(dict_ptr) {
                 return Err(Error::new(ErrorKind::DictionaryChangedDuringDecompression).into());
             }
 
+            // The output is clamped to the remaining budget so that
+            // the limit is never exceeded, even temporarily.
             let len = self.buffer.len();
-            if len < DEFAULT_BUF_SIZE {
-                self.buffer.resize_with(DEFAULT_BUF_SIZE, Default::default)
-            }
-            let (src_len, dst_len, _) =
+            let remaining = self.limits.remaining_output(self.total_out);
+            let capacity = cmp::min(DEFAULT_BUF_SIZE.saturating_sub(len), remaining);
+            self.buffer.resize_with(len + capacity, Default::default);
+            let dict = match &self.frame_dict {
+                Some(dict) => dict,
+                None => &self.dict[..],
+            };
+            let (src_len, dst_len, expected) =
                 self.ctx
-                    .decompress_dict(src, &mut self.buffer[len..], &self.dict, false)?;
+                    .get_mut()
+                    .decompress_dict(src, &mut self.buffer[len..], dict, false)?;
             self.buffer.resize_with(len + dst_len, Default::default);
+            *finished = expected == 0;
+            self.total_out += dst_len as u64;
+            if remaining == 0 && src_len == 0 && !src.is_empty() && !*finished {
+                return Err(Error::new(ErrorKind::DecompressionLimitExceeded).into());
+            }
             Ok(src_len + header_consumed)
         } else {
             Ok(header_consumed)
@@ -150,11 +291,9 @@ impl<'a> Decompressor<'a> {
     }
 
     fn dict_ptr(&self) -> DictPtr {
-        let dict = &self.dict;
-        if dict.is_empty() {
-            DictPtr(ptr::null(), 0)
-        } else {
-            DictPtr(dict.as_ptr(), dict.len())
+        match &self.frame_dict {
+            Some(dict) => DictPtr::new(dict),
+            None => DictPtr::new(&self.dict),
         }
     }
 
@@ -1,5 +1,11 @@
 use super::BufReadDecompressor;
-use crate::lz4f::{FrameInfo, Result};
+use crate::{
+    lz4f::{
+        DamagedRegion, DecompressionContext, DecompressionLimits, DictionaryRegistry, FrameInfo,
+        Result, ValidationPolicy,
+    },
+    Stats,
+};
 use std::{
     borrow::Cow,
     fmt,
@@ -57,6 +63,13 @@ impl<'a, R: Read> ReadDecompressor<'a, R> {
         })
     }
 
+    /// Creates a new `ReadDecompressor<R>` with a reusable decompression context.
+    pub fn with_context(reader: R, ctx: DecompressionContext) -> Self {
+        Self {
+            inner: BufReadDecompressor::with_context(BufReader::new(reader), ctx),
+        }
+    }
+
     /// Sets the dictionary.
     pub fn set_dict<D>(&mut self, dict: D)
     where
@@ -65,6 +78,83 @@ impl<'a, R: Read> ReadDecompressor<'a, R> {
         self.inner.set_dict(dict);
     }
 
+    /// Sets the dictionary registry.
+    ///
+    /// If a frame header has a non-zero `dict_id`, the dictionary is taken from the registry
+    /// instead of the one set by [`set_dict`]. An unregistered ID raises `DictionaryNotFound`.
+    ///
+    /// [`set_dict`]: #method.set_dict
+    pub fn set_dict_registry(&mut self, registry: DictionaryRegistry) {
+        self.inner.set_dict_registry(registry);
+    }
+
+    /// Sets the decompression limits.
+    ///
+    /// Going over a limit raises `DecompressionLimitExceeded`.
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.inner.set_limits(limits);
+    }
+
+    /// Sets the frame validation policy.
+    ///
+    /// Data following a frame is decoded as the next frame, so trailing garbage
+    /// fails as an invalid frame header regardless of `reject_trailing_data`.
+    /// In the single-frame mode, the data after the frame is not validated.
+    pub fn set_validation_policy(&mut self, policy: ValidationPolicy) {
+        self.inner.set_validation_policy(policy);
+    }
+
+    /// Stops decompression at the end of the current frame.
+    ///
+    /// This works like [`BufReadDecompressor::set_single_frame`],
+    /// but the reader itself may be read ahead because of internal buffering.
+    /// Use [`BufReadDecompressor`] to leave the reader positioned just after the frame.
+    ///
+    /// [`BufReadDecompressor`]: struct.BufReadDecompressor.html
+    /// [`BufReadDecompressor::set_single_frame`]: struct.BufReadDecompressor.html#method.set_single_frame
+    pub fn set_single_frame(&mut self, flag: bool) {
+        self.inner.set_single_frame(flag);
+    }
+
+    /// Enables the recovery mode.
+    ///
+    /// See [`BufReadDecompressor::set_recovery`] for details.
+    ///
+    /// [`BufReadDecompressor::set_recovery`]: struct.BufReadDecompressor.html#method.set_recovery
+    pub fn set_recovery<F>(&mut self, gap_fill: Option<u8>, report: F) -> Result<()>
+    where
+        F: FnMut(&DamagedRegion) + Send + 'a,
+    {
+        self.inner.set_recovery(gap_fill, report)
+    }
+
+    /// Returns the total number of compressed bytes consumed.
+    pub fn total_in(&self) -> u64 {
+        self.inner.total_in()
+    }
+
+    /// Returns the total number of decompressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.inner.total_out()
+    }
+
+    /// Returns the compression ratio of the data decompressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        self.inner.ratio()
+    }
+
+    /// Sets a callback which receives the statistics of each decoded frame.
+    pub fn set_frame_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.inner.set_frame_callback(callback);
+    }
+
     /// Reads the frame header and returns `FrameInfo`.
     ///
     /// Calling this function before any `Read` operations
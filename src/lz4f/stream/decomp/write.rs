@@ -1,4 +1,10 @@
-use crate::lz4f::{Decompressor, FrameInfo, Result};
+use crate::{
+    lz4f::{
+        DecompressionContext, DecompressionLimits, Decompressor, DictionaryRegistry, FrameInfo,
+        Result, ValidationPolicy,
+    },
+    Stats,
+};
 use std::{borrow::Cow, fmt, io::Write};
 
 /// The [`Write`]-based streaming decompressor.
@@ -40,6 +46,14 @@ impl<'a, W: Write> WriteDecompressor<'a, W> {
         })
     }
 
+    /// Creates a new `WriteDecompressor<W>` with a reusable decompression context.
+    pub fn with_context(writer: W, ctx: DecompressionContext) -> Self {
+        Self {
+            inner: writer,
+            decomp: Decompressor::with_context(ctx),
+        }
+    }
+
     /// Sets the dictionary.
     pub fn set_dict<D>(&mut self, dict: D)
     where
@@ -48,6 +62,31 @@ impl<'a, W: Write> WriteDecompressor<'a, W> {
         self.decomp.set_dict(dict);
     }
 
+    /// Sets the dictionary registry.
+    ///
+    /// If a frame header has a non-zero `dict_id`, the dictionary is taken from the registry
+    /// instead of the one set by [`set_dict`]. An unregistered ID raises `DictionaryNotFound`.
+    ///
+    /// [`set_dict`]: #method.set_dict
+    pub fn set_dict_registry(&mut self, registry: DictionaryRegistry) {
+        self.decomp.set_dict_registry(registry);
+    }
+
+    /// Sets the decompression limits.
+    ///
+    /// Going over a limit raises `DecompressionLimitExceeded`.
+    pub fn set_limits(&mut self, limits: DecompressionLimits) {
+        self.decomp.set_limits(limits);
+    }
+
+    /// Sets the frame validation policy.
+    ///
+    /// Data following a frame is decoded as the next frame, so trailing garbage
+    /// fails as an invalid frame header regardless of `reject_trailing_data`.
+    pub fn set_validation_policy(&mut self, policy: ValidationPolicy) {
+        self.decomp.set_validation_policy(policy);
+    }
+
     /// Returns `FrameInfo` if the frame header is already decoded.
     /// Otherwise, returns `None`.
     pub fn frame_info(&self) -> Option<FrameInfo> {
@@ -63,6 +102,33 @@ impl<'a, W: Write> WriteDecompressor<'a, W> {
         self.decomp.decode_header_only(flag);
     }
 
+    /// Returns the total number of compressed bytes consumed.
+    pub fn total_in(&self) -> u64 {
+        self.decomp.stats().compressed_len()
+    }
+
+    /// Returns the total number of decompressed bytes produced.
+    pub fn total_out(&self) -> u64 {
+        self.decomp.stats().uncompressed_len()
+    }
+
+    /// Returns the compression ratio of the data decompressed so far.
+    ///
+    /// See [`Stats::ratio`] for details.
+    ///
+    /// [`Stats::ratio`]: ../struct.Stats.html#method.ratio
+    pub fn ratio(&self) -> f64 {
+        self.decomp.stats().ratio()
+    }
+
+    /// Sets a callback which receives the statistics of each decoded frame.
+    pub fn set_frame_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Stats) + Send + 'static,
+    {
+        self.decomp.set_frame_callback(Box::new(callback));
+    }
+
     /// Returns a mutable reference to the writer.
     pub fn get_mut(&mut self) -> &mut W {
         &mut self.inner
@@ -0,0 +1,342 @@
+#![cfg(feature = "cli")]
+
+use assert_fs::TempDir;
+use lzzzz::{lz4, lz4f, lz4f::*};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+    process::{Command, Output, Stdio},
+    thread,
+};
+
+fn lzzzz(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lzzzz"))
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+fn lzzzz_pipe(dir: &Path, args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lzzzz"))
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap().unwrap();
+    output
+}
+
+fn sample_data() -> Vec<u8> {
+    (0..200_000)
+        .map(|i| format!("{} bottles of beer on the wall\n", i % 1000))
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn frame_info(comp: &[u8]) -> FrameInfo {
+    ReadDecompressor::new(comp)
+        .unwrap()
+        .read_frame_info()
+        .unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let tmp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+    for level in &["-1", "-3", "-9", "-12"] {
+        fs::write(tmp_dir.path().join("data"), &data).unwrap();
+        assert!(lzzzz(tmp_dir.path(), &[level, "-f", "data"])
+            .status
+            .success());
+
+        let comp = fs::read(tmp_dir.path().join("data.lz4")).unwrap();
+        let mut decomp = Vec::new();
+        lz4f::decompress_to_vec(&comp, &mut decomp).unwrap();
+        assert_eq!(decomp, data);
+
+        fs::remove_file(tmp_dir.path().join("data")).unwrap();
+        assert!(lzzzz(tmp_dir.path(), &["-d", "data.lz4"]).status.success());
+        assert_eq!(fs::read(tmp_dir.path().join("data")).unwrap(), data);
+        fs::remove_file(tmp_dir.path().join("data.lz4")).unwrap();
+    }
+}
+
+#[test]
+fn stdout() {
+    let tmp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+
+    let comp = lzzzz_pipe(tmp_dir.path(), &["-c"], &data);
+    assert!(comp.status.success());
+    let mut decomp = Vec::new();
+    lz4f::decompress_to_vec(&comp.stdout, &mut decomp).unwrap();
+    assert_eq!(decomp, data);
+
+    let mut comp = Vec::new();
+    lz4f::compress_to_vec(&data, &mut comp, &Default::default()).unwrap();
+    let decomp = lzzzz_pipe(tmp_dir.path(), &["-dc"], &comp);
+    assert!(decomp.status.success());
+    assert_eq!(decomp.stdout, data);
+}
+
+#[test]
+fn force() {
+    let tmp_dir = TempDir::new().unwrap();
+    fs::write(tmp_dir.path().join("data"), sample_data()).unwrap();
+    fs::write(tmp_dir.path().join("data.lz4"), b"existing").unwrap();
+
+    assert!(!lzzzz(tmp_dir.path(), &["data"]).status.success());
+    assert_eq!(
+        fs::read(tmp_dir.path().join("data.lz4")).unwrap(),
+        b"existing"
+    );
+    assert!(lzzzz(tmp_dir.path(), &["-f", "data"]).status.success());
+    assert_ne!(
+        fs::read(tmp_dir.path().join("data.lz4")).unwrap(),
+        b"existing"
+    );
+}
+
+#[test]
+fn remove() {
+    let tmp_dir = TempDir::new().unwrap();
+    fs::write(tmp_dir.path().join("data"), sample_data()).unwrap();
+
+    assert!(lzzzz(tmp_dir.path(), &["--rm", "data"]).status.success());
+    assert!(!tmp_dir.path().join("data").exists());
+    assert!(lzzzz(tmp_dir.path(), &["--rm", "data.lz4"])
+        .status
+        .success());
+    assert!(!tmp_dir.path().join("data.lz4").exists());
+    assert_eq!(
+        fs::read(tmp_dir.path().join("data")).unwrap(),
+        sample_data()
+    );
+
+    // The source is kept when the output goes to the standard output.
+    assert!(lzzzz(tmp_dir.path(), &["--rm", "-c", "data"])
+        .status
+        .success());
+    assert!(tmp_dir.path().join("data").exists());
+}
+
+#[test]
+fn test() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut comp = Vec::new();
+    lz4f::compress_to_vec(&sample_data(), &mut comp, &Default::default()).unwrap();
+    fs::write(tmp_dir.path().join("good.lz4"), &comp).unwrap();
+    let len = comp.len();
+    comp[len - 1] ^= 1;
+    fs::write(tmp_dir.path().join("bad.lz4"), &comp).unwrap();
+
+    assert!(lzzzz(tmp_dir.path(), &["-t", "good.lz4"]).status.success());
+    assert!(!lzzzz(tmp_dir.path(), &["-t", "bad.lz4"]).status.success());
+    assert!(!tmp_dir.path().join("good").exists());
+}
+
+#[test]
+fn truncated() {
+    let tmp_dir = TempDir::new().unwrap();
+    let mut comp = Vec::new();
+    lz4f::compress_to_vec(&sample_data(), &mut comp, &Default::default()).unwrap();
+    for len in [6, comp.len() / 2, comp.len() - 1] {
+        fs::write(tmp_dir.path().join("truncated.lz4"), &comp[..len]).unwrap();
+
+        let output = lzzzz(tmp_dir.path(), &["-t", "truncated.lz4"]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Incomplete"));
+
+        let output = lzzzz(tmp_dir.path(), &["-d", "-c", "truncated.lz4"]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Incomplete"));
+    }
+}
+
+#[test]
+fn legacy() {
+    let tmp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+
+    for level in &["-1", "-9"] {
+        let comp = lzzzz_pipe(tmp_dir.path(), &[level, "-l", "-c"], &data);
+        assert!(comp.status.success());
+        assert_eq!(&comp.stdout[..4], &0x184C_2102u32.to_le_bytes());
+
+        let len = u32::from_le_bytes([
+            comp.stdout[4],
+            comp.stdout[5],
+            comp.stdout[6],
+            comp.stdout[7],
+        ]) as usize;
+        assert_eq!(len + 8, comp.stdout.len());
+        let mut decomp = vec![0; data.len()];
+        assert_eq!(
+            lz4::decompress(&comp.stdout[8..], &mut decomp).unwrap(),
+            data.len()
+        );
+        assert_eq!(decomp, data);
+
+        let decomp = lzzzz_pipe(tmp_dir.path(), &["-dc"], &comp.stdout);
+        assert!(decomp.status.success());
+        assert_eq!(decomp.stdout, data);
+    }
+}
+
+#[test]
+fn concatenated() {
+    let tmp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+
+    let mut comp = Vec::new();
+    lz4f::compress_to_vec(&data, &mut comp, &Default::default()).unwrap();
+    comp.extend_from_slice(&lzzzz_pipe(tmp_dir.path(), &["-l", "-c"], &data).stdout);
+    let mut frame = Vec::new();
+    lz4f::compress_to_vec(&data, &mut frame, &Default::default()).unwrap();
+    comp.extend_from_slice(&frame);
+
+    let decomp = lzzzz_pipe(tmp_dir.path(), &["-dc"], &comp);
+    assert!(decomp.status.success());
+    assert_eq!(decomp.stdout, [&data[..], &data[..], &data[..]].concat());
+}
+
+#[test]
+fn multiple() {
+    let tmp_dir = TempDir::new().unwrap();
+    let files = ["a", "b", "c"];
+    for (i, file) in files.iter().enumerate() {
+        fs::write(tmp_dir.path().join(file), &sample_data()[i * 1000..]).unwrap();
+    }
+
+    assert!(lzzzz(tmp_dir.path(), &["-m", "a", "b", "c"])
+        .status
+        .success());
+    for file in &files {
+        fs::remove_file(tmp_dir.path().join(file)).unwrap();
+    }
+    assert!(
+        lzzzz(tmp_dir.path(), &["-d", "-m", "a.lz4", "b.lz4", "c.lz4"])
+            .status
+            .success()
+    );
+    for (i, file) in files.iter().enumerate() {
+        assert_eq!(
+            fs::read(tmp_dir.path().join(file)).unwrap(),
+            &sample_data()[i * 1000..]
+        );
+    }
+}
+
+#[test]
+fn frame_options() {
+    let tmp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+    fs::write(tmp_dir.path().join("data"), &data).unwrap();
+
+    let comp = lzzzz(tmp_dir.path(), &["-c", "data"]).stdout;
+    let info = frame_info(&comp);
+    assert_eq!(info.block_size(), BlockSize::Max4MB);
+    assert_eq!(info.block_mode(), BlockMode::Independent);
+    assert_eq!(info.block_checksum(), BlockChecksum::Disabled);
+    assert_eq!(info.content_checksum(), ContentChecksum::Enabled);
+    assert_eq!(info.content_size(), 0);
+
+    let comp = lzzzz(
+        tmp_dir.path(),
+        &[
+            "-B4",
+            "-BD",
+            "-BX",
+            "--content-size",
+            "--no-frame-crc",
+            "-c",
+            "data",
+        ],
+    )
+    .stdout;
+    let info = frame_info(&comp);
+    assert_eq!(info.block_size(), BlockSize::Max64KB);
+    assert_eq!(info.block_mode(), BlockMode::Linked);
+    assert_eq!(info.block_checksum(), BlockChecksum::Enabled);
+    assert_eq!(info.content_checksum(), ContentChecksum::Disabled);
+    assert_eq!(info.content_size(), data.len());
+
+    let mut decomp = Vec::new();
+    ReadDecompressor::new(&comp[..])
+        .unwrap()
+        .read_to_end(&mut decomp)
+        .unwrap();
+    assert_eq!(decomp, data);
+
+    let output = lzzzz_pipe(tmp_dir.path(), &["--content-size", "-c"], &[]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn invalid_option() {
+    let tmp_dir = TempDir::new().unwrap();
+    assert!(!lzzzz(tmp_dir.path(), &["-x"]).status.success());
+    assert!(!lzzzz(tmp_dir.path(), &["-B9"]).status.success());
+    assert!(lzzzz(tmp_dir.path(), &["-V"]).status.success());
+}
+
+#[test]
+fn list() {
+    let tmp_dir = TempDir::new().unwrap();
+    let data = sample_data();
+    let prefs = PreferencesBuilder::new()
+        .block_size(BlockSize::Max64KB)
+        .block_checksum(BlockChecksum::Enabled)
+        .content_checksum(ContentChecksum::Enabled)
+        .build();
+    let mut comp = Vec::new();
+    lz4f::compress_to_vec(&data, &mut comp, &prefs).unwrap();
+    comp.extend_from_slice(&[0x50, 0x2a, 0x4d, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+    fs::write(tmp_dir.path().join("data.lz4"), &comp).unwrap();
+
+    let output = lzzzz(tmp_dir.path(), &["--list", "data.lz4"]);
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("data.lz4: 2 frame(s)"));
+    assert!(text.contains("block size: 64KB"));
+    assert!(text.contains("block checksum: valid, content checksum: valid"));
+    assert!(text.contains("skippable frame (magic 0x184D2A50), 11 bytes"));
+
+    let output = lzzzz(tmp_dir.path(), &["inspect", "--json", "data.lz4"]);
+    assert!(output.status.success());
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert!(json.starts_with(r#"[{"file":"data.lz4","frames":[{"offset":0,"#));
+    assert!(json.contains(&format!(r#""uncompressed_size":{},"#, data.len())));
+    assert!(json.contains(r#""block_checksum":"valid","content_checksum":"valid","error":null}"#));
+    assert!(json.contains(&format!(
+        r#"{{"offset":{},"length":11,"magic":407710288,"type":"skippable","error":null}}]}}]"#,
+        comp.len() - 11
+    )));
+
+    let legacy = lzzzz_pipe(tmp_dir.path(), &["-l", "-c"], &data);
+    fs::write(tmp_dir.path().join("legacy.lz4"), &legacy.stdout).unwrap();
+    let output = lzzzz(tmp_dir.path(), &["--list", "legacy.lz4"]);
+    assert!(!output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("frame 1 at 0: legacy frame, not supported"));
+
+    let len = comp.len();
+    comp[len - 12] ^= 1;
+    fs::write(tmp_dir.path().join("broken.lz4"), &comp).unwrap();
+    let output = lzzzz(tmp_dir.path(), &["--list", "--json", "broken.lz4"]);
+    assert!(!output.status.success());
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert!(json.contains(r#""content_checksum":"invalid""#));
+}
@@ -53,6 +53,63 @@ mod compress_to_vec {
     }
 }
 
+mod compress_or_store {
+    use super::*;
+    use lzzzz::lz4::BlockEncoding;
+    use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
+
+    fn noise(len: usize) -> Vec<u8> {
+        SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(len)
+            .collect()
+    }
+
+    #[test]
+    fn default() {
+        lz4_test_set().par_bridge().for_each(|(src, mode)| {
+            let mut comp_buf = vec![0; lz4::max_compressed_size(src.len())];
+            let mut decomp_buf = vec![0; src.len()];
+            match lz4::compress_or_store(&src, &mut comp_buf, mode).unwrap() {
+                BlockEncoding::Compressed(len) => {
+                    assert!(len < src.len() || src.is_empty());
+                    lz4::decompress(&comp_buf[..len], &mut decomp_buf).unwrap();
+                }
+                BlockEncoding::Stored(len) => {
+                    assert_eq!(len, src.len());
+                    decomp_buf.copy_from_slice(&comp_buf[..len]);
+                }
+            }
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn incompressible() {
+        let text = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+        assert!(!lz4::is_incompressible(&text));
+        assert!(!lz4::is_incompressible(&noise(1000)));
+        assert!(lz4::is_incompressible(&noise(100_000)));
+
+        let mut buf = vec![0; 200_000];
+        let result = lz4::compress_or_store(&text, &mut buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert!(!result.is_stored());
+
+        for len in [10, 1000, 100_000] {
+            let src = noise(len);
+            let result = lz4::compress_or_store(&src, &mut buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+            assert_eq!(result, BlockEncoding::Stored(len));
+            assert_eq!(&buf[..len], &src[..]);
+            assert!(lz4::compress_or_store(&src, &mut buf[..len - 1], 1).is_err());
+        }
+
+        // A repeated random chunk compresses, but the samples do not.
+        let src = noise(4096).repeat(16);
+        assert!(lz4::is_incompressible(&src));
+        assert!(lz4::compress(&src, &mut buf, lz4::ACC_LEVEL_DEFAULT).unwrap() < src.len() / 4);
+    }
+}
+
 mod decompress {
     use super::*;
 
@@ -95,3 +152,89 @@ mod decompress {
         });
     }
 }
+
+mod compressed_vec {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set()
+            .flat_map(|(src, _)| vec![(src.clone(), 100), (src.clone(), 4096), (src, 65536)])
+            .par_bridge()
+            .for_each(|(src, block_size)| {
+                let mut vec = lz4::CompressedVec::with_block_size(block_size);
+                for chunk in src.chunks(3000) {
+                    vec.extend_from_slice(chunk).unwrap();
+                }
+                assert_eq!(vec.len(), src.len());
+                assert!(vec.compressed_len() <= src.len());
+                assert_eq!(vec.to_vec().unwrap(), src);
+            });
+    }
+
+    #[test]
+    fn random_access() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+        for &cache in &[0, 1, 4] {
+            let mut expected = src.clone();
+            let mut vec = lz4::CompressedVec::with_block_size(1000);
+            vec.set_cache_capacity(cache);
+            vec.extend_from_slice(&src).unwrap();
+            assert!(vec.compressed_len() < src.len() / 4);
+
+            let mut state = 1u64;
+            for i in 0..200 {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                let offset = (state >> 33) as usize % src.len();
+                let len = cmp::min((state >> 20) as usize % 3000, src.len() - offset);
+                let range = offset..offset + len;
+                if i % 2 == 0 {
+                    let data = vec![i as u8; len];
+                    expected[range].copy_from_slice(&data);
+                    vec.write_at(offset, &data).unwrap();
+                } else {
+                    assert_eq!(vec.get(range.clone()).unwrap(), &expected[range]);
+                }
+            }
+            assert_eq!(vec.len(), src.len());
+            assert_eq!(vec.to_vec().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn append() {
+        let mut vec = lz4::CompressedVec::with_block_size(1000);
+        vec.set_cache_capacity(1);
+        let mut expected = Vec::new();
+        for i in 0..1000 {
+            let data = format!("{} ", i);
+            vec.extend_from_slice(data.as_bytes()).unwrap();
+            expected.extend_from_slice(data.as_bytes());
+        }
+        assert_eq!(vec.len(), expected.len());
+        assert_eq!(vec.get(..10).unwrap(), &expected[..10]);
+        assert_eq!(vec.get(995..=1005).unwrap(), &expected[995..=1005]);
+        assert_eq!(vec.get(1500..).unwrap(), &expected[1500..]);
+        assert!(vec.get(100..100).unwrap().is_empty());
+
+        let mut buf = [0; 2100];
+        vec.read_at(3, &mut buf).unwrap();
+        assert_eq!(&buf[..], &expected[3..2103]);
+
+        vec.clear();
+        assert!(vec.is_empty());
+        assert_eq!(vec.compressed_len(), 0);
+        vec.extend_from_slice(b"data").unwrap();
+        assert_eq!(vec.to_vec().unwrap(), b"data");
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        let mut vec = lz4::CompressedVec::new();
+        vec.extend_from_slice(b"The quick brown fox").unwrap();
+        vec.write_at(10, b"jumps over").unwrap();
+    }
+}
@@ -7,6 +7,7 @@ mod common;
 use common::lz4_hc_stream_test_set;
 
 assert_impl_all!(lz4_hc::Compressor: Send);
+assert_impl_all!(lz4_hc::PreparedDict: Send, Sync);
 
 mod compressor {
     use super::*;
@@ -47,6 +48,48 @@ mod compressor {
             });
     }
 
+    #[test]
+    fn prepared_dictionary() {
+        lz4_hc_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, level)| {
+                let dict_data = SmallRng::seed_from_u64(0)
+                    .sample_iter(Standard)
+                    .take(64 * 1024)
+                    .collect::<Vec<_>>();
+                let dict = lz4_hc::PreparedDict::new(&dict_data, level).unwrap();
+                let mut comp = lz4_hc::Compressor::with_prepared_dict(&dict).unwrap();
+                let mut decomp = lz4::Decompressor::with_dict(&dict_data).unwrap();
+                for src in src_set {
+                    let mut comp_buf = vec![0; lz4::max_compressed_size(src.len())];
+                    let len = comp.next(&src, &mut comp_buf).unwrap();
+                    assert_eq!(decomp.next(&comp_buf[..len], src.len()).unwrap(), &src);
+                }
+            });
+    }
+
+    #[test]
+    fn shared_prepared_dictionary() {
+        let dict_data = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(32 * 1024)
+            .collect::<Vec<_>>();
+        let dict = lz4_hc::PreparedDict::new(&dict_data, lz4_hc::CLEVEL_DEFAULT).unwrap();
+        (0..64).into_par_iter().for_each(|i| {
+            let src = &dict_data[i * 256..i * 256 + 4096];
+            let mut comp = lz4_hc::Compressor::with_prepared_dict(&dict).unwrap();
+            for _ in 0..2 {
+                let mut comp_buf = Vec::new();
+                let len = comp.next_to_vec(src, &mut comp_buf).unwrap();
+                assert!(len < 64);
+                let mut decomp_buf = vec![0; src.len()];
+                lz4::decompress_with_dict(&comp_buf, &mut decomp_buf, dict.as_bytes()).unwrap();
+                assert_eq!(decomp_buf, src);
+                comp.attach_prepared_dict(&dict);
+            }
+        });
+    }
+
     #[test]
     fn dynamic_adaptation() {
         lz4_hc_stream_test_set()
@@ -106,4 +149,34 @@ mod compressor {
         // comp_regular_dict.next_to_vec(data, &mut output_regular_dict).unwrap();
         // assert_eq!(output_attached_dict, output_regular_dict, "Compressed data should match");
     }
+
+    #[test]
+    fn stats() {
+        lz4_hc_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, level)| {
+                let mut blocks = Vec::new();
+                let (tx, rx) = std::sync::mpsc::channel();
+                let mut comp = lz4_hc::Compressor::new().unwrap();
+                comp.set_compression_level(level);
+                comp.set_block_callback(move |stats| tx.send(*stats).unwrap());
+                let (mut total_in, mut total_out) = (0, 0);
+                for src in src_set {
+                    let mut comp_buf = vec![0; lz4::max_compressed_size(src.len())];
+                    let len = comp.next(&src, &mut comp_buf).unwrap();
+                    total_in += src.len() as u64;
+                    total_out += len as u64;
+                    blocks.push((src.len() as u64, len as u64));
+                }
+                assert_eq!(comp.total_in(), total_in);
+                assert_eq!(comp.total_out(), total_out);
+                assert_eq!(comp.ratio(), total_in as f64 / total_out as f64);
+                drop(comp);
+                let reported = rx
+                    .iter()
+                    .map(|stats| (stats.uncompressed_len(), stats.compressed_len()))
+                    .collect::<Vec<_>>();
+                assert_eq!(reported, blocks);
+            });
+    }
 }
@@ -0,0 +1,229 @@
+use lzzzz::lz4::{
+    PacketCompressor, PacketDecompressor, PacketEvent, PacketMode, PACKET_HEADER_SIZE,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rayon::{iter::ParallelBridge, prelude::*};
+
+mod common;
+use common::lz4_test_set;
+
+fn text() -> Vec<u8> {
+    let words = [
+        "quick ", "brown ", "fox ", "jumps ", "over ", "the ", "lazy ", "dog. ",
+    ];
+    let mut rng = SmallRng::seed_from_u64(0);
+    (0..5000)
+        .flat_map(|_| words[rng.gen_range(0..words.len())].bytes())
+        .collect()
+}
+
+fn decompress(packets: &[Vec<u8>]) -> (Vec<u8>, Vec<PacketEvent>) {
+    let mut decomp = PacketDecompressor::new();
+    let mut buf = Vec::new();
+    let mut events = Vec::new();
+    for packet in packets {
+        decomp
+            .push(packet, &mut buf, |event| events.push(*event))
+            .unwrap();
+    }
+    decomp
+        .skip_missing(&mut buf, |event| events.push(*event))
+        .unwrap();
+    (buf, events)
+}
+
+mod packet_compressor {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set()
+            .flat_map(|(src, _)| {
+                vec![
+                    (src.clone(), PacketMode::Independent, 576),
+                    (src.clone(), PacketMode::Independent, 1500),
+                    (src.clone(), PacketMode::Linked, 576),
+                    (src, PacketMode::Linked, 1500),
+                ]
+            })
+            .par_bridge()
+            .for_each(|(src, mode, mtu)| {
+                let mut comp = PacketCompressor::new(mtu, mode).unwrap();
+                let packets = comp.packets(&src).unwrap();
+                assert!(packets.iter().all(|packet| packet.len() <= mtu));
+                assert_eq!(comp.seq() as usize, packets.len());
+
+                let (buf, events) = decompress(&packets);
+                assert_eq!(buf, src);
+                assert_eq!(events.len(), packets.len());
+            });
+    }
+
+    #[test]
+    fn compressible() {
+        let src = text();
+        for &mode in &[PacketMode::Independent, PacketMode::Linked] {
+            for &mtu in &[0, 64, 1500, 100_000] {
+                let mut comp = PacketCompressor::new(mtu, mode).unwrap();
+                let packets = comp.packets(&src).unwrap();
+                assert!(packets.iter().all(|packet| packet.len() <= comp.mtu()));
+                assert_eq!(decompress(&packets).0, src);
+            }
+        }
+    }
+
+    #[test]
+    fn linked_ratio() {
+        let src = text();
+        let total = |mode| -> usize {
+            let mut comp = PacketCompressor::new(64, mode).unwrap();
+            comp.packets(&src).unwrap().iter().map(Vec::len).sum()
+        };
+        assert!(total(PacketMode::Linked) < total(PacketMode::Independent));
+    }
+
+    #[test]
+    fn empty() {
+        let mut comp = PacketCompressor::new(1500, PacketMode::Linked).unwrap();
+        assert_eq!(comp.next(&[], &mut [0; 1500]).unwrap(), (0, 0));
+        assert!(comp.packets(&[]).unwrap().is_empty());
+        assert!(comp.next(b"data", &mut [0; 16]).is_err());
+        assert_eq!(comp.seq(), 0);
+    }
+}
+
+mod packet_decompressor {
+    use super::*;
+
+    #[test]
+    fn reorder() {
+        let src = text();
+        for &mode in &[PacketMode::Independent, PacketMode::Linked] {
+            let mut comp = PacketCompressor::new(64, mode).unwrap();
+            let mut packets = comp.packets(&src).unwrap();
+            for chunk in packets.chunks_mut(5) {
+                chunk.reverse();
+            }
+            let (buf, events) = decompress(&packets);
+            assert_eq!(buf, src);
+            for (i, event) in events.iter().enumerate() {
+                assert!(matches!(event, PacketEvent::Received { seq, .. } if *seq as usize == i));
+            }
+        }
+    }
+
+    #[test]
+    fn duplicate() {
+        let src = text();
+        let mut comp = PacketCompressor::new(64, PacketMode::Linked).unwrap();
+        let packets = comp.packets(&src).unwrap();
+        let packets = packets
+            .iter()
+            .flat_map(|packet| vec![packet.clone(), packet.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(decompress(&packets).0, src);
+    }
+
+    #[test]
+    fn independent_loss() {
+        let src = text();
+        let mut comp = PacketCompressor::new(64, PacketMode::Independent).unwrap();
+        let mut packets = comp.packets(&src).unwrap();
+        let lost = packets.remove(10);
+        let lost_len = u16::from_le_bytes([lost[4], lost[5]]) as usize;
+
+        let (buf, events) = decompress(&packets);
+        assert_eq!(buf.len(), src.len() - lost_len);
+        assert_eq!(events[10], PacketEvent::Lost { seq: 10, count: 1 });
+        assert_eq!(events.len(), packets.len() + 1);
+    }
+
+    #[test]
+    fn linked_loss() {
+        let src = text();
+        let mut comp = PacketCompressor::new(64, PacketMode::Linked).unwrap();
+        let mut packets = Vec::new();
+        let mut buf = [0; 64];
+        let mut offset = 0;
+        let mut reset_offset = 0;
+        while offset < src.len() {
+            if packets.len() == 20 {
+                comp.reset().unwrap();
+                reset_offset = offset;
+            }
+            let (read, wrote) = comp.next(&src[offset..], &mut buf).unwrap();
+            packets.push(buf[..wrote].to_vec());
+            offset += read;
+        }
+        packets.remove(10);
+
+        let (buf, events) = decompress(&packets);
+        assert_eq!(events[10], PacketEvent::Lost { seq: 10, count: 10 });
+        assert_eq!(
+            buf[buf.len() - (src.len() - reset_offset)..],
+            src[reset_offset..]
+        );
+    }
+
+    #[test]
+    fn window() {
+        let src = text();
+        let mut comp = PacketCompressor::new(64, PacketMode::Independent).unwrap();
+        let packets = comp.packets(&src).unwrap();
+
+        let mut decomp = PacketDecompressor::new();
+        decomp.set_window(4);
+        let mut buf = Vec::new();
+        let mut events = Vec::new();
+        for packet in packets.iter().skip(1).take(3) {
+            decomp
+                .push(packet, &mut buf, |event| events.push(*event))
+                .unwrap();
+        }
+        assert!(events.is_empty());
+        assert_eq!(decomp.pending(), 3);
+
+        decomp
+            .push(&packets[4], &mut buf, |event| events.push(*event))
+            .unwrap();
+        assert_eq!(events[0], PacketEvent::Lost { seq: 0, count: 1 });
+        assert_eq!(events.len(), 5);
+        assert_eq!(decomp.next_seq(), 5);
+
+        // Late packets are ignored.
+        decomp
+            .push(&packets[0], &mut buf, |event| events.push(*event))
+            .unwrap();
+        assert_eq!(events.len(), 5);
+
+        // Packets far ahead make the packets before the window lost at once.
+        decomp
+            .push(&packets[100], &mut buf, |event| events.push(*event))
+            .unwrap();
+        assert_eq!(events[5], PacketEvent::Lost { seq: 5, count: 92 });
+        assert_eq!(decomp.pending(), 1);
+
+        decomp
+            .skip_missing(&mut buf, |event| events.push(*event))
+            .unwrap();
+        assert_eq!(events[6], PacketEvent::Lost { seq: 97, count: 3 });
+        assert!(matches!(events[7], PacketEvent::Received { seq: 100, .. }));
+        assert_eq!(events.len(), 8);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut decomp = PacketDecompressor::new();
+        let mut buf = Vec::new();
+        assert!(decomp
+            .push(&[0; PACKET_HEADER_SIZE - 1], &mut buf, |_| ())
+            .is_err());
+        assert!(decomp
+            .push(&[0, 0, 0, 0, 0, 0, 0x80], &mut buf, |_| ())
+            .is_err());
+        assert!(decomp
+            .push(&[0, 0, 0, 0, 10, 0, 0, 0xff], &mut buf, |_| ())
+            .is_err());
+        assert!(buf.is_empty());
+    }
+}
@@ -8,6 +8,7 @@ use common::lz4_stream_test_set;
 
 assert_impl_all!(lz4::Compressor: Send);
 assert_impl_all!(lz4::Decompressor: Send);
+assert_impl_all!(lz4::PreparedDict: Send, Sync);
 
 mod compressor {
     use super::*;
@@ -27,6 +28,76 @@ mod compressor {
             });
     }
 
+    #[test]
+    fn fill() {
+        lz4_stream_test_set()
+            .flat_map(|(src_set, mode)| [1500, 65536].map(move |len| (src_set.clone(), mode, len)))
+            .par_bridge()
+            .for_each(|(src_set, mode, len)| {
+                let mut comp = lz4::Compressor::new().unwrap();
+                let mut decomp = lz4::Decompressor::new().unwrap();
+                for src in src_set {
+                    let mut src = &src[..];
+                    while !src.is_empty() {
+                        let mut comp_buf = vec![0; len];
+                        let (read, wrote) = comp.next_fill(src, &mut comp_buf, mode).unwrap();
+                        assert!(read > 0 && wrote <= len);
+                        if read < src.len() {
+                            assert!(wrote >= len * 3 / 4);
+                        }
+                        assert_eq!(decomp.next(&comp_buf[..wrote], read).unwrap(), &src[..read]);
+                        src = &src[read..];
+                    }
+                }
+            });
+    }
+
+    #[test]
+    fn fill_history() {
+        let data = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(4096)
+            .collect::<Vec<u8>>();
+        let mut comp = lz4::Compressor::new().unwrap();
+        let mut decomp = lz4::Decompressor::new().unwrap();
+        let mut comp_buf = [0; 1500];
+
+        let (read, wrote) = comp.next_fill(&data, &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert!(read < data.len());
+        assert_eq!(decomp.next(&comp_buf[..wrote], read).unwrap(), &data[..read]);
+
+        // The data compressed before is referenced as the history.
+        let (read, wrote) = comp.next_fill(&data[..read], &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert!(wrote < 32);
+        assert_eq!(decomp.next(&comp_buf[..wrote], read).unwrap(), &data[..read]);
+        assert_eq!(comp.total_in(), read as u64 * 2);
+    }
+
+    #[test]
+    fn fill_linked() {
+        let data = b"The quick brown fox jumps over the lazy dog. "
+            .iter()
+            .copied()
+            .cycle()
+            .take(1 << 20)
+            .collect::<Vec<u8>>();
+        let mut comp = lz4::Compressor::new().unwrap();
+        let mut decomp = lz4::Decompressor::new().unwrap();
+
+        let mut comp_buf = vec![0; lz4::max_compressed_size(4096)];
+        let len = comp.next(&data[..4096], &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert_eq!(decomp.next(&comp_buf[..len], 4096).unwrap(), &data[..4096]);
+
+        let mut comp_buf = [0; 512];
+        let (read, wrote) = comp.next_fill(&data[4096..], &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert!(read < data.len() - 4096);
+        // The prefix refers to the history, so it is not longer than on its own.
+        let mut buf = vec![0; lz4::max_compressed_size(read)];
+        let len = lz4::compress(&data[4096..][..read], &mut buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+        assert!(wrote <= len);
+        assert_eq!(decomp.next(&comp_buf[..wrote], read).unwrap(), &data[4096..][..read]);
+    }
+
     #[test]
     fn dictionary() {
         run_dictionary_test(|dict| lz4::Compressor::with_dict(dict))
@@ -37,6 +108,33 @@ mod compressor {
         run_dictionary_test(|dict| lz4::Compressor::with_dict_slow(dict))
     }
 
+    #[test]
+    fn prepared_dictionary() {
+        run_dictionary_test(|dict| lz4::Compressor::with_prepared_dict(&lz4::PreparedDict::new(dict)?))
+    }
+
+    #[test]
+    fn shared_prepared_dictionary() {
+        let dict_data = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(32 * 1024)
+            .collect::<Vec<_>>();
+        let dict = lz4::PreparedDict::new(&dict_data).unwrap();
+        (0..64).into_par_iter().for_each(|i| {
+            let src = &dict_data[i * 256..i * 256 + 4096];
+            let mut comp = lz4::Compressor::with_prepared_dict(&dict).unwrap();
+            for _ in 0..2 {
+                let mut comp_buf = Vec::new();
+                let len = comp.next_to_vec(src, &mut comp_buf, lz4::ACC_LEVEL_DEFAULT).unwrap();
+                assert!(len < 64);
+                let mut decomp_buf = vec![0; src.len()];
+                lz4::decompress_with_dict(&comp_buf, &mut decomp_buf, dict.as_bytes()).unwrap();
+                assert_eq!(decomp_buf, src);
+                comp.attach_prepared_dict(&dict);
+            }
+        });
+    }
+
     /// Helper function to run dictionary compression tests with either normal or slow mode
     fn run_dictionary_test<F>(compressor_factory: F)
     where
@@ -91,4 +189,35 @@ mod compressor {
         assert_eq!(output_attached_dict, output_regular_dict, "Compressed data should match");
         assert_ne!(output_attached_dict, output_no_dict, "Data with no dict should be different");
     }
+
+    #[test]
+    fn stats() {
+        lz4_stream_test_set()
+            .par_bridge()
+            .for_each(|(src_set, mode)| {
+                let mut blocks = Vec::new();
+                let (tx, rx) = std::sync::mpsc::channel();
+                let mut comp = lz4::Compressor::new().unwrap();
+                comp.set_block_callback(move |stats| tx.send(*stats).unwrap());
+                let (mut total_in, mut total_out) = (0, 0);
+                for src in src_set {
+                    let mut comp_buf = vec![0; lz4::max_compressed_size(src.len())];
+                    let len = comp.next(&src, &mut comp_buf, mode).unwrap();
+                    total_in += src.len() as u64;
+                    total_out += len as u64;
+                    blocks.push((src.len() as u64, len as u64));
+                }
+                assert_eq!(comp.next(&[], &mut [], mode).unwrap(), 0);
+                blocks.push((0, 0));
+                assert_eq!(comp.total_in(), total_in);
+                assert_eq!(comp.total_out(), total_out);
+                assert_eq!(comp.ratio(), total_in as f64 / total_out as f64);
+                drop(comp);
+                let reported = rx
+                    .iter()
+                    .map(|stats| (stats.uncompressed_len(), stats.compressed_len()))
+                    .collect::<Vec<_>>();
+                assert_eq!(reported, blocks);
+            });
+    }
 }
@@ -27,6 +27,119 @@ mod compress_to_vec {
     }
 }
 
+mod compress_to_vec_with_dict {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let dict_data = &src[..src.len().min(32 * 1024)];
+            let dict = Dictionary::with_id(dict_data, 7).unwrap();
+            let header = &b"HEADER"[..];
+            let mut comp_buf = Vec::from(header);
+            let mut decomp_buf = Vec::from(header);
+
+            assert_eq!(
+                lz4f::compress_to_vec_with_dict(&src, &mut comp_buf, &prefs, &dict).unwrap(),
+                comp_buf.len() - header.len()
+            );
+            let comp_buf = &comp_buf[header.len()..];
+            assert_eq!(
+                lz4f::decompress_to_vec_with_dict(comp_buf, &mut decomp_buf, dict_data).unwrap(),
+                decomp_buf.len() - header.len()
+            );
+            assert_eq!(&decomp_buf[header.len()..], &src[..]);
+
+            let mut r = ReadDecompressor::new(comp_buf).unwrap();
+            let dict_id = prefs.frame_info().dict_id();
+            assert_eq!(
+                r.read_frame_info().unwrap().dict_id(),
+                if dict_id == 0 { 7 } else { dict_id }
+            );
+            r.set_dict(dict_data);
+            decomp_buf.clear();
+            r.read_to_end(&mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+
+            if src.len() >= 1024 {
+                assert!(lz4f::decompress_to_vec(comp_buf, &mut Vec::new()).is_err());
+            }
+        });
+    }
+
+    #[test]
+    fn streaming() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let dict_data = &src[..src.len().min(32 * 1024)];
+            let mut w =
+                WriteCompressor::with_dict(Vec::new(), prefs, Dictionary::new(dict_data).unwrap())
+                    .unwrap();
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec_with_dict(&comp_buf, &mut decomp_buf, dict_data).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+}
+
+mod decompress_to_vec_with_registry {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let dict_data = &src[..src.len().min(32 * 1024)];
+            let dict = Dictionary::with_id(dict_data, 7).unwrap();
+            let mut w = WriteCompressor::with_dict(Vec::new(), prefs, dict).unwrap();
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+            let dict_id = match prefs.frame_info().dict_id() {
+                0 => 7,
+                dict_id => dict_id,
+            };
+
+            let mut registry = DictionaryRegistry::new();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_registry(&comp_buf, &mut Vec::new(), &registry),
+                Err(Error::Common(lzzzz::ErrorKind::DictionaryNotFound))
+            );
+            registry.insert(dict_id, dict_data);
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec_with_registry(&comp_buf, &mut decomp_buf, &registry).unwrap();
+            assert_eq!(decomp_buf, src);
+
+            let mut decomp_buf = vec![0; src.len()];
+            assert_eq!(
+                lz4f::decompress_with_registry(&comp_buf, &mut decomp_buf, &registry).unwrap(),
+                src.len()
+            );
+            assert_eq!(decomp_buf, src);
+
+            let mut decoder = FrameDecoder::new().unwrap();
+            decoder.set_dict_registry(registry);
+            let mut decomp_buf = Vec::new();
+            let mut buf = [0u8; 1024];
+            let mut offset = 0;
+            loop {
+                let (consumed, produced, hint) =
+                    decoder.decode(&comp_buf[offset..], &mut buf).unwrap();
+                offset += consumed;
+                decomp_buf.extend_from_slice(&buf[..produced]);
+                if hint == 0 {
+                    break;
+                }
+            }
+            assert_eq!(offset, comp_buf.len());
+            assert_eq!(decomp_buf, src);
+        });
+    }
+}
+
 mod compress {
     use super::*;
 
@@ -79,9 +192,245 @@ mod compress {
     }
 }
 
+mod decompress {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = vec![0; src.len()];
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(
+                lz4f::decompress(&comp_buf, &mut decomp_buf).unwrap(),
+                src.len()
+            );
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn too_small_dst() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = vec![0; src.len() - 1];
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(
+                lz4f::decompress(&comp_buf, &mut decomp_buf),
+                Err(Error::Lz4f(ErrorKind::DstMaxSizeTooSmall))
+            );
+        });
+    }
+}
+
 mod decompress_to_vec {
     use super::*;
 
+    #[test]
+    fn content_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let header = &b"HEADER"[..];
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::from(header);
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap(),
+                src.len()
+            );
+            assert_eq!(&decomp_buf[header.len()..], &src[..]);
+        });
+    }
+
+    #[test]
+    fn consumed() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let frame_len = comp_buf.len();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_consumed(&comp_buf, &mut decomp_buf),
+                Ok((frame_len, src.len()))
+            );
+            assert_eq!(
+                lz4f::decompress_to_vec_with_consumed(&comp_buf[frame_len..], &mut decomp_buf),
+                Ok((comp_buf.len() - frame_len, src.len()))
+            );
+            assert_eq!(&decomp_buf[..src.len()], &src[..]);
+            assert_eq!(&decomp_buf[src.len()..], &src[..]);
+        });
+    }
+
+    #[test]
+    fn limits() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len() - 1)
+                .build();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits),
+                Err(Error::Common(lzzzz::ErrorKind::DecompressionLimitExceeded))
+            );
+            assert!(decomp_buf.is_empty());
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len())
+                .build();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits).unwrap(),
+                src.len()
+            );
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn content_size_limit() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let limits = DecompressionLimitsBuilder::new()
+                .max_content_size(src.len() - 1)
+                .build();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits),
+                Err(Error::Common(lzzzz::ErrorKind::DecompressionLimitExceeded))
+            );
+            assert!(decomp_buf.is_empty());
+        });
+    }
+
+    #[test]
+    fn preallocation_limit() {
+        let src = vec![0; 1 << 20];
+        let prefs = PreferencesBuilder::new()
+            .block_size(BlockSize::Max64KB)
+            .block_checksum(BlockChecksum::Enabled)
+            .content_size(src.len())
+            .build();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+        // The first block is broken, so the declared content size is never reached.
+        let mut broken = comp_buf.clone();
+        broken[30] ^= 0xff;
+        let mut decomp_buf = Vec::new();
+        assert!(lz4f::decompress_to_vec(&broken, &mut decomp_buf).is_err());
+        assert!(decomp_buf.capacity() >= src.len());
+
+        let limits = DecompressionLimitsBuilder::new()
+            .max_preallocation(64 * 1024)
+            .build();
+        let mut decomp_buf = Vec::new();
+        assert!(lz4f::decompress_to_vec_with_limits(&broken, &mut decomp_buf, &limits).is_err());
+        assert!(decomp_buf.capacity() < src.len());
+
+        lz4f::decompress_to_vec_with_limits(&comp_buf, &mut decomp_buf, &limits).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+
+    #[test]
+    fn validation_policy() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let policy = ValidationPolicyBuilder::new()
+                .require_content_checksum(true)
+                .build();
+            let result = lz4f::decompress_to_vec_with_policy(&comp_buf, &mut decomp_buf, &policy);
+            if prefs.frame_info().content_checksum() == ContentChecksum::Enabled {
+                assert_eq!(result, Ok(src.len()));
+            } else {
+                assert_eq!(
+                    result,
+                    Err(Error::Lz4f(ErrorKind::ContentChecksumMissing))
+                );
+            }
+
+            decomp_buf.clear();
+            let policy = ValidationPolicyBuilder::new()
+                .require_block_checksum(true)
+                .build();
+            let result = lz4f::decompress_to_vec_with_policy(&comp_buf, &mut decomp_buf, &policy);
+            if prefs.frame_info().block_checksum() == BlockChecksum::Enabled {
+                assert_eq!(result, Ok(src.len()));
+            } else {
+                assert_eq!(
+                    result,
+                    Err(Error::Lz4f(ErrorKind::BlockChecksumMissing))
+                );
+            }
+
+            decomp_buf.clear();
+            let policy = ValidationPolicyBuilder::new()
+                .reject_trailing_data(true)
+                .build();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_policy(&comp_buf, &mut decomp_buf, &policy),
+                Ok(src.len())
+            );
+            comp_buf.push(0);
+            decomp_buf.clear();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_policy(&comp_buf, &mut decomp_buf, &policy),
+                Err(Error::Lz4f(ErrorKind::TrailingData))
+            );
+            assert!(decomp_buf.is_empty());
+        });
+    }
+
+    #[test]
+    fn reject_reserved_flags() {
+        let mut comp_buf = Vec::new();
+        let mut decomp_buf = Vec::new();
+        lz4f::compress_to_vec(b"Hello world!", &mut comp_buf, &Default::default()).unwrap();
+
+        let policy = ValidationPolicyBuilder::new()
+            .reject_reserved_flags(true)
+            .build();
+        assert_eq!(
+            lz4f::decompress_to_vec_with_policy(&comp_buf, &mut decomp_buf, &policy),
+            Ok(12)
+        );
+        for (index, bit) in [(4, 0b0000_0010), (5, 0b1000_0000), (5, 0b0000_0001)] {
+            let mut comp_buf = comp_buf.clone();
+            comp_buf[index] |= bit;
+            decomp_buf.clear();
+            assert_eq!(
+                lz4f::decompress_to_vec_with_policy(&comp_buf, &mut decomp_buf, &policy),
+                Err(Error::Lz4f(ErrorKind::ReservedFlagSet))
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_content_size() {
+        let src = b"The quick brown fox jumps over the lazy dog.";
+        let prefs = PreferencesBuilder::new().content_size(1).build();
+        for content_size in [src.len() - 1, src.len() + 1] {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(src, &mut comp_buf, &prefs).unwrap();
+            comp_buf[6..14].copy_from_slice(&(content_size as u64).to_le_bytes());
+
+            // Find the header checksum for the modified header.
+            let result = (0..=u8::MAX)
+                .map(|checksum| {
+                    comp_buf[14] = checksum;
+                    lz4f::decompress_to_vec(&comp_buf, &mut Vec::new())
+                })
+                .find(|result| *result != Err(Error::Lz4f(ErrorKind::HeaderChecksumInvalid)));
+            assert_eq!(result, Some(Err(Error::Lz4f(ErrorKind::FrameSizeWrong))));
+        }
+    }
+
     #[test]
     fn invalid_header() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -118,3 +467,663 @@ mod decompress_to_vec {
         });
     }
 }
+
+mod frame_decoder {
+    use super::*;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut decoder = FrameDecoder::new().unwrap();
+            let mut buf = [0u8; 1024];
+            let mut offset = 0;
+            let mut rng = SmallRng::seed_from_u64(0);
+            loop {
+                let len = rng.gen_range(0..=comp_buf.len() - offset);
+                let (consumed, produced, hint) = decoder
+                    .decode(&comp_buf[offset..][..len], &mut buf)
+                    .unwrap();
+                offset += consumed;
+                decomp_buf.extend_from_slice(&buf[..produced]);
+                if hint == 0 {
+                    break;
+                }
+            }
+            assert_eq!(offset, comp_buf.len());
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn stable_dst() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut decoder = FrameDecoder::new().unwrap();
+            let (info, mut offset) = decoder.read_frame_info(&comp_buf).unwrap();
+            let mut decomp_buf = vec![0; info.content_size()];
+            let mut decomp_len = 0;
+            let mut rng = SmallRng::seed_from_u64(0);
+            loop {
+                let len = rng.gen_range(0..=comp_buf.len() - offset);
+                let (consumed, produced, hint) = decoder
+                    .decode_stable(&comp_buf[offset..][..len], &mut decomp_buf)
+                    .unwrap();
+                offset += consumed;
+                decomp_len += produced;
+                if hint == 0 {
+                    break;
+                }
+            }
+            assert_eq!(offset, comp_buf.len());
+            assert_eq!(decomp_len, src.len());
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn destination_changed() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(4096);
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+
+        let mut decoder = FrameDecoder::new().unwrap();
+        let mut buf = vec![0; src.len()];
+        let (consumed, _, _) = decoder
+            .decode_stable(&comp_buf[..comp_buf.len() / 2], &mut buf)
+            .unwrap();
+        let mut other = vec![0; src.len()];
+        assert_eq!(
+            decoder.decode_stable(&comp_buf[consumed..], &mut other),
+            Err(Error::Common(
+                lzzzz::ErrorKind::DestinationChangedDuringDecompression
+            ))
+        );
+        assert_eq!(
+            decoder.decode(&comp_buf[consumed..], &mut other),
+            Err(Error::Common(
+                lzzzz::ErrorKind::DestinationChangedDuringDecompression
+            ))
+        );
+    }
+}
+
+mod decompress_to_vec_with_recovery {
+    use super::*;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    fn text(len: usize) -> Vec<u8> {
+        let mut rng = SmallRng::seed_from_u64(0);
+        (0..len).map(|_| rng.gen_range(b'a'..=b'd')).collect()
+    }
+
+    #[test]
+    fn intact() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let result = lz4f::decompress_to_vec_with_recovery(&comp_buf, &mut decomp_buf, |r| {
+                panic!("unexpected damage: {:?}", r)
+            });
+            assert_eq!(result, Ok(src.len() * 2));
+            assert_eq!(&decomp_buf[..src.len()], &src[..]);
+            assert_eq!(&decomp_buf[src.len()..], &src[..]);
+        });
+    }
+
+    #[test]
+    fn corrupt_block() {
+        let src = text(4 * 64 * 1024);
+        let prefs = PreferencesBuilder::new()
+            .block_size(BlockSize::Max64KB)
+            .block_mode(BlockMode::Independent)
+            .block_checksum(BlockChecksum::Enabled)
+            .content_checksum(ContentChecksum::Enabled)
+            .build();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+        // Break the payload of the second block.
+        let header_len = 7;
+        let first_block_len = u32::from_le_bytes(comp_buf[7..11].try_into().unwrap()) as usize;
+        let second_block = header_len + 4 + first_block_len + 4;
+        comp_buf[second_block + 100] ^= 0xff;
+
+        let mut decomp_buf = Vec::new();
+        let mut damaged = Vec::new();
+        let len =
+            lz4f::decompress_to_vec_with_recovery(&comp_buf, &mut decomp_buf, |r| damaged.push(*r))
+                .unwrap();
+
+        assert_eq!(len, 3 * 64 * 1024);
+        assert_eq!(&decomp_buf[..64 * 1024], &src[..64 * 1024]);
+        assert_eq!(&decomp_buf[64 * 1024..], &src[2 * 64 * 1024..]);
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(damaged[0].offset(), second_block);
+        assert_eq!(damaged[0].output_offset(), 64 * 1024);
+        assert_eq!(
+            damaged[0].error(),
+            Error::Lz4f(ErrorKind::BlockChecksumInvalid)
+        );
+    }
+
+    #[test]
+    fn gap_fill() {
+        let src = text(4 * 64 * 1024 + 1000);
+        for content_size in [0, src.len()] {
+            let prefs = PreferencesBuilder::new()
+                .block_size(BlockSize::Max64KB)
+                .block_mode(BlockMode::Independent)
+                .block_checksum(BlockChecksum::Enabled)
+                .content_size(content_size)
+                .build();
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            // Break the payload of the second block and the last block.
+            let header_len = if content_size > 0 { 15 } else { 7 };
+            let mut blocks = Vec::new();
+            let mut pos = header_len;
+            loop {
+                let len = u32::from_le_bytes(comp_buf[pos..pos + 4].try_into().unwrap());
+                if len == 0 {
+                    break;
+                }
+                blocks.push(pos);
+                pos += 4 + (len & 0x7fff_ffff) as usize + 4;
+            }
+            comp_buf[blocks[1] + 10] ^= 0xff;
+            comp_buf[blocks[4] + 10] ^= 0xff;
+
+            let mut decomp_buf = Vec::new();
+            let mut damaged = Vec::new();
+            lz4f::decompress_to_vec_with_gap_fill(&comp_buf, &mut decomp_buf, b'?', |r| {
+                damaged.push(*r)
+            })
+            .unwrap();
+
+            assert_eq!(damaged.len(), 2);
+            assert_eq!(damaged[0].offset(), blocks[1]);
+            assert_eq!(damaged[0].output_offset(), 64 * 1024);
+            assert_eq!(damaged[0].output_len(), 64 * 1024);
+            assert_eq!(damaged[1].output_offset(), 4 * 64 * 1024);
+            assert!(decomp_buf[64 * 1024..][..64 * 1024]
+                .iter()
+                .all(|&b| b == b'?'));
+            assert_eq!(&decomp_buf[..64 * 1024], &src[..64 * 1024]);
+            assert_eq!(
+                &decomp_buf[2 * 64 * 1024..4 * 64 * 1024],
+                &src[2 * 64 * 1024..4 * 64 * 1024]
+            );
+            if content_size > 0 {
+                assert_eq!(damaged[1].output_len(), 1000);
+                assert_eq!(decomp_buf.len(), src.len());
+            } else {
+                assert_eq!(damaged[1].output_len(), 64 * 1024);
+            }
+        }
+    }
+
+    #[test]
+    fn resync() {
+        let src = text(1024);
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+        let frame_len = comp_buf.len();
+        comp_buf.extend_from_slice(b"garbage");
+        lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+
+        // Break the header of the first frame.
+        comp_buf[4] ^= 0xff;
+
+        let mut decomp_buf = Vec::new();
+        let mut damaged = Vec::new();
+        lz4f::decompress_to_vec_with_recovery(&comp_buf, &mut decomp_buf, |r| damaged.push(*r))
+            .unwrap();
+
+        assert_eq!(decomp_buf, src);
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(damaged[0].offset(), 0);
+        assert_eq!(damaged[0].len(), frame_len + b"garbage".len());
+    }
+}
+
+mod inspect {
+    use super::*;
+    use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    fn text(len: usize) -> Vec<u8> {
+        let mut rng = SmallRng::seed_from_u64(0);
+        (0..len).map(|_| rng.gen_range(b'a'..=b'd')).collect()
+    }
+
+    #[test]
+    fn default() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let frames = lz4f::inspect(&comp_buf).unwrap();
+            assert_eq!(frames.len(), 1);
+
+            let frame = &frames[0];
+            let info = frame.frame_info();
+            assert_eq!(frame.offset(), 0);
+            assert_eq!(frame.len(), comp_buf.len());
+            assert_eq!(frame.frame_type(), FrameType::Frame);
+            assert_eq!(frame.error(), None);
+            assert_eq!(info.dict_id(), prefs.frame_info().dict_id());
+            assert_eq!(frame.block_count() == 0, src.is_empty());
+            if info.dict_id() == 0 {
+                assert_eq!(frame.uncompressed_size(), Some(src.len()));
+            }
+            let expected = |enabled| match (enabled, info.dict_id()) {
+                (false, _) => ChecksumStatus::Disabled,
+                (true, 0) => ChecksumStatus::Valid,
+                (true, _) => ChecksumStatus::Unverified,
+            };
+            assert_eq!(
+                frame.content_checksum(),
+                expected(info.content_checksum() == ContentChecksum::Enabled)
+            );
+            if info.block_checksum() == BlockChecksum::Enabled {
+                assert_eq!(frame.block_checksum(), ChecksumStatus::Valid);
+            }
+        });
+    }
+
+    #[test]
+    fn reader() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            for len in [comp_buf.len(), comp_buf.len() / 2] {
+                let comp_buf = &comp_buf[..len];
+                let reader = std::io::BufReader::with_capacity(1000, comp_buf);
+                assert_eq!(
+                    lz4f::inspect_reader(reader).unwrap(),
+                    lz4f::inspect(comp_buf).unwrap()
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn multiple_frames() {
+        let src = text(300 * 1024);
+        let prefs = PreferencesBuilder::new()
+            .block_size(BlockSize::Max64KB)
+            .block_mode(BlockMode::Linked)
+            .content_size(src.len())
+            .build();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+        let first_len = comp_buf.len();
+        comp_buf.extend_from_slice(&0x184D_2A5Fu32.to_le_bytes());
+        comp_buf.extend_from_slice(&5u32.to_le_bytes());
+        comp_buf.extend_from_slice(b"hello");
+        lz4f::compress_to_vec(b"", &mut comp_buf, &Default::default()).unwrap();
+
+        let frames = lz4f::inspect(&comp_buf).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].len(), first_len);
+        assert_eq!(frames[0].block_count(), 5);
+        assert_eq!(frames[0].uncompressed_size(), Some(src.len()));
+        assert_eq!(frames[0].frame_info().content_size(), src.len());
+        assert_eq!(frames[1].offset(), first_len);
+        assert_eq!(frames[1].len(), 13);
+        assert_eq!(frames[1].magic(), 0x184D_2A5F);
+        assert_eq!(frames[1].frame_type(), FrameType::SkippableFrame);
+        assert_eq!(frames[2].offset(), first_len + 13);
+        assert_eq!(frames[2].block_count(), 0);
+        assert_eq!(frames[2].uncompressed_size(), Some(0));
+        assert_eq!(frames[2].offset() + frames[2].len(), comp_buf.len());
+    }
+
+    #[test]
+    fn checksums() {
+        let src = text(4 * 64 * 1024);
+        let prefs = PreferencesBuilder::new()
+            .block_size(BlockSize::Max64KB)
+            .block_mode(BlockMode::Independent)
+            .block_checksum(BlockChecksum::Enabled)
+            .content_checksum(ContentChecksum::Enabled)
+            .build();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+        // Break the checksum of the first block.
+        let header_len = 7;
+        let first_block_len = u32::from_le_bytes(comp_buf[7..11].try_into().unwrap()) as usize;
+        let mut broken = comp_buf.clone();
+        broken[header_len + 4 + first_block_len] ^= 0xff;
+        let frames = lz4f::inspect(&broken).unwrap();
+        assert_eq!(frames[0].block_checksum(), ChecksumStatus::Invalid);
+        assert_eq!(frames[0].content_checksum(), ChecksumStatus::Valid);
+        assert_eq!(frames[0].error(), None);
+
+        // Break the content checksum.
+        let mut broken = comp_buf.clone();
+        let len = broken.len();
+        broken[len - 1] ^= 0xff;
+        let frames = lz4f::inspect(&broken).unwrap();
+        assert_eq!(frames[0].block_checksum(), ChecksumStatus::Valid);
+        assert_eq!(frames[0].content_checksum(), ChecksumStatus::Invalid);
+        assert_eq!(frames[0].error(), None);
+    }
+
+    #[test]
+    fn damaged() {
+        let src = text(1024);
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &Default::default()).unwrap();
+        let frame_len = comp_buf.len();
+
+        let frames = lz4f::inspect(&comp_buf[..frame_len - 1]).unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), frame_len - 1);
+        assert_eq!(
+            frames[0].error(),
+            Some(Error::Common(lzzzz::ErrorKind::CompressedDataIncomplete))
+        );
+
+        comp_buf.extend_from_slice(b"garbage");
+        let frames = lz4f::inspect(&comp_buf).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].error(), None);
+        assert_eq!(frames[1].offset(), frame_len);
+        assert_eq!(frames[1].len(), b"garbage".len());
+        assert_eq!(
+            frames[1].error(),
+            Some(Error::Lz4f(ErrorKind::FrameTypeUnknown))
+        );
+    }
+}
+
+mod dictionary_file {
+    use super::*;
+    use lzzzz::DictionaryTrainer;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn round_trip() {
+        let samples = (0..500)
+            .map(|i| format!(r#"{{"id":{},"name":"user{}"}}"#, i, i * 7))
+            .collect::<Vec<_>>();
+        let dict = DictionaryFile::from(DictionaryTrainer::new().train(&samples));
+        let bytes = dict.to_bytes().unwrap();
+        assert_eq!(DictionaryFile::from_bytes(&bytes).unwrap(), dict);
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let path = tmp_dir.path().join("records.dict");
+        dict.save(&path).unwrap();
+        let dict = DictionaryFile::load(&path).unwrap();
+
+        let mut w =
+            WriteCompressor::with_dict(Vec::new(), Default::default(), dict.dictionary().unwrap())
+                .unwrap();
+        w.write_all(samples[0].as_bytes()).unwrap();
+        let comp_buf = w.finish().unwrap();
+
+        let mut r = ReadDecompressor::new(&comp_buf[..]).unwrap();
+        dict.check_frame(&r.read_frame_info().unwrap()).unwrap();
+        assert_eq!(
+            DictionaryFile::new(dict.dict_id() ^ 1, dict.as_bytes())
+                .check_frame(&r.read_frame_info().unwrap()),
+            Err(lz4f::Error::Common(lzzzz::ErrorKind::DictionaryMismatch))
+        );
+        r.set_dict(dict.as_bytes());
+        let mut decomp_buf = Vec::new();
+        r.read_to_end(&mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, samples[0].as_bytes());
+    }
+
+    #[test]
+    fn invalid() {
+        let bytes = DictionaryFile::new(1, &b"quick brown fox jumps over the lazy dog"[..])
+            .to_bytes()
+            .unwrap();
+        let invalid = Err(lz4f::Error::Common(lzzzz::ErrorKind::DictionaryInvalid));
+        assert_eq!(
+            DictionaryFile::from_bytes(&bytes[..bytes.len() - 1]),
+            invalid
+        );
+        assert_eq!(DictionaryFile::from_bytes(&bytes[..8]), invalid);
+        for i in [0, 8, 12, 20] {
+            let mut bytes = bytes.clone();
+            bytes[i] ^= 1;
+            assert_eq!(DictionaryFile::from_bytes(&bytes), invalid);
+        }
+    }
+}
+
+#[cfg(feature = "memmap2")]
+mod decompress_file {
+    use super::*;
+    use memmap2::Mmap;
+    use std::fs::{self, File};
+
+    #[test]
+    fn default() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        lz4f_test_set()
+            .enumerate()
+            .par_bridge()
+            .for_each(|(i, (src, prefs))| {
+                let mut comp_buf = Vec::new();
+                lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+                let path = tmp_dir.path().join(format!("{}.lz4", i));
+                fs::write(&path, &comp_buf).unwrap();
+
+                assert_eq!(lz4f::decompress_file(&path).unwrap(), src);
+
+                let mmap = unsafe { Mmap::map(&File::open(&path).unwrap()).unwrap() };
+                let mut decomp_buf = vec![0; src.len()];
+                assert_eq!(
+                    lz4f::decompress_mmap(&mmap, &mut decomp_buf).unwrap(),
+                    src.len()
+                );
+                assert_eq!(decomp_buf, src);
+            });
+    }
+
+    #[test]
+    fn concatenated() {
+        let data = b"The quick brown fox jumps over the lazy dog.";
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(data, &mut comp_buf, &Default::default()).unwrap();
+        comp_buf.extend_from_slice(&[0x50, 0x2a, 0x4d, 0x18, 4, 0, 0, 0, 1, 2, 3, 4]);
+        lz4f::compress_to_vec(data, &mut comp_buf, &Default::default()).unwrap();
+
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let path = tmp_dir.path().join("concat.lz4");
+        fs::write(&path, &comp_buf).unwrap();
+        assert_eq!(lz4f::decompress_file(&path).unwrap(), data.repeat(2));
+
+        let mmap = unsafe { Mmap::map(&File::open(&path).unwrap()).unwrap() };
+        let mut decomp_buf = vec![0; data.len() * 2];
+        assert_eq!(
+            lz4f::decompress_mmap(&mmap, &mut decomp_buf).unwrap(),
+            decomp_buf.len()
+        );
+        assert_eq!(decomp_buf, data.repeat(2));
+        assert!(lz4f::decompress_mmap(&mmap, &mut [0; 50]).is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        let tmp_dir = assert_fs::TempDir::new().unwrap();
+        let path = tmp_dir.path().join("empty.lz4");
+        fs::write(&path, b"").unwrap();
+        assert!(lz4f::decompress_file(&path).unwrap().is_empty());
+
+        let path = tmp_dir.path().join("garbage.lz4");
+        fs::write(&path, b"garbage").unwrap();
+        assert!(lz4f::decompress_file(&path).is_err());
+
+        assert!(lz4f::decompress_file(tmp_dir.path().join("missing.lz4")).is_err());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn preferences() {
+        let prefs = PreferencesBuilder::new()
+            .compression_level(9)
+            .block_size(BlockSize::Max64KB)
+            .block_mode(BlockMode::Independent)
+            .content_checksum(ContentChecksum::Enabled)
+            .dict_id(42)
+            .build();
+        let value = serde_json::to_value(prefs).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "compression_level": 9,
+                "block_size": "64KB",
+                "block_mode": "independent",
+                "block_checksum": "disabled",
+                "content_checksum": "enabled",
+                "content_size": 0,
+                "dict_id": 42,
+                "auto_flush": "disabled",
+                "favor_dec_speed": "disabled",
+            })
+        );
+        assert_eq!(serde_json::from_value::<Preferences>(value).unwrap(), prefs);
+
+        lz4f_test_set().for_each(|(_, prefs)| {
+            let json = serde_json::to_string(&prefs).unwrap();
+            assert_eq!(serde_json::from_str::<Preferences>(&json).unwrap(), prefs);
+        });
+    }
+
+    #[test]
+    fn partial() {
+        let prefs: Preferences =
+            serde_json::from_str(r#"{"block_size": "1MB", "block_checksum": "enabled"}"#).unwrap();
+        assert_eq!(
+            prefs,
+            PreferencesBuilder::new()
+                .block_size(BlockSize::Max1MB)
+                .block_checksum(BlockChecksum::Enabled)
+                .build()
+        );
+        assert!(serde_json::from_str::<Preferences>(r#"{"block_size": "2MB"}"#).is_err());
+        assert!(serde_json::from_str::<Preferences>(r#"{"blocksize": "1MB"}"#).is_err());
+    }
+
+    #[test]
+    fn from_str_names() {
+        for size in ["64KB", "64kb", "4mb", "Default"] {
+            assert_eq!(
+                serde_json::from_value::<BlockSize>(json!(size)).unwrap(),
+                size.parse().unwrap()
+            );
+        }
+        assert_eq!(
+            serde_json::from_value::<BlockMode>(json!("Independent")).unwrap(),
+            BlockMode::Independent
+        );
+        assert_eq!(
+            serde_json::from_value::<ContentChecksum>(json!("Enabled")).unwrap(),
+            ContentChecksum::Enabled
+        );
+        assert_eq!(
+            serde_json::from_value::<BlockChecksum>(json!("ENABLED")).unwrap(),
+            BlockChecksum::Enabled
+        );
+        assert_eq!(
+            serde_json::from_value::<FrameType>(json!("Skippable")).unwrap(),
+            FrameType::SkippableFrame
+        );
+        assert_eq!(
+            serde_json::from_value::<AutoFlush>(json!("Enabled")).unwrap(),
+            AutoFlush::Enabled
+        );
+        assert_eq!(
+            serde_json::from_value::<FavorDecSpeed>(json!("Enabled")).unwrap(),
+            FavorDecSpeed::Enabled
+        );
+        assert_eq!("skippable".parse(), Ok(FrameType::SkippableFrame));
+        assert!(serde_json::from_value::<FrameType>(json!("skippableframe")).is_err());
+        assert!(serde_json::from_value::<BlockSize>(json!("64")).is_err());
+        assert!(serde_json::from_value::<BlockSize>(json!(4)).is_err());
+        assert!(
+            serde_json::from_str::<Preferences>(r#"{"content_size": 18446744073709551616}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn frame_info() {
+        let mut comp_buf = Vec::new();
+        let prefs = PreferencesBuilder::new()
+            .block_size(BlockSize::Max64KB)
+            .content_size(5)
+            .build();
+        lz4f::compress_to_vec(b"hello", &mut comp_buf, &prefs).unwrap();
+        let info = ReadDecompressor::new(&comp_buf[..])
+            .unwrap()
+            .read_frame_info()
+            .unwrap();
+        let value = serde_json::to_value(info).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "block_size": "64KB",
+                "block_mode": "independent",
+                "content_checksum": "disabled",
+                "frame_type": "frame",
+                "content_size": 5,
+                "dict_id": 0,
+                "block_checksum": "disabled",
+            })
+        );
+        assert_eq!(serde_json::from_value::<FrameInfo>(value).unwrap(), info);
+        assert_eq!(
+            serde_json::from_str::<FrameInfo>("{}").unwrap(),
+            FrameInfo::default()
+        );
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(
+            serde_json::to_string(&BlockSize::Default).unwrap(),
+            r#""default""#
+        );
+        assert_eq!(
+            serde_json::to_string(&BlockSize::Max4MB).unwrap(),
+            r#""4MB""#
+        );
+        assert_eq!(
+            serde_json::to_string(&BlockMode::Linked).unwrap(),
+            r#""linked""#
+        );
+        assert_eq!(
+            serde_json::to_string(&FrameType::SkippableFrame).unwrap(),
+            r#""skippable""#
+        );
+        assert_eq!(
+            serde_json::from_str::<AutoFlush>(r#""enabled""#).unwrap(),
+            AutoFlush::Enabled
+        );
+        assert_eq!(
+            serde_json::from_str::<FavorDecSpeed>(r#""disabled""#).unwrap(),
+            FavorDecSpeed::Disabled
+        );
+    }
+}
@@ -13,13 +13,21 @@ use common::lz4f_test_set;
 assert_impl_all!(lz4f::BufReadCompressor<BufReader<File>>: Send);
 assert_impl_all!(lz4f::ReadCompressor<File>: Send);
 assert_impl_all!(lz4f::WriteCompressor<File>: Send);
+assert_impl_all!(lz4f::AdaptiveWriteCompressor<File>: Send);
 assert_impl_all!(lz4f::BufReadDecompressor<BufReader<File>>: Send);
 assert_impl_all!(lz4f::ReadDecompressor<File>: Send);
 assert_impl_all!(lz4f::WriteDecompressor<File>: Send);
+assert_impl_all!(lz4f::CompressionContext: Send);
+assert_impl_all!(lz4f::DecompressionContext: Send);
+assert_impl_all!(lz4f::ContextPool: Send, Sync);
 
 mod write_compressor {
     use super::*;
-    use lzzzz::lz4f::WriteCompressor;
+    use lzzzz::{
+        lz4f::{AdaptiveWriteCompressor, BufReadDecompressor, FrameWriter, WriteCompressor},
+        Profile,
+    };
+    use std::{cmp, thread, time::Duration};
 
     #[test]
     fn default() {
@@ -30,6 +38,7 @@ mod write_compressor {
                 let mut w = WriteCompressor::new(&mut comp_buf, prefs).unwrap();
                 w.write_all(&src).unwrap();
                 w.flush().unwrap();
+                w.try_finish().unwrap();
             }
             assert_eq!(
                 lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap(),
@@ -57,6 +66,7 @@ mod write_compressor {
                     w.flush().unwrap();
                     offset += len;
                 }
+                w.try_finish().unwrap();
             }
             assert_eq!(
                 lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap(),
@@ -65,6 +75,368 @@ mod write_compressor {
             assert_eq!(decomp_buf, src);
         });
     }
+
+    #[test]
+    fn finish() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut decomp_buf = Vec::new();
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+            assert_eq!(
+                lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap(),
+                src.len()
+            );
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn multi_frame() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let next_prefs = PreferencesBuilder::from(prefs)
+                .content_checksum(ContentChecksum::Enabled)
+                .build();
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            w.finish_frame().unwrap();
+            w.finish_frame().unwrap();
+            w.write_all(&src).unwrap();
+            w.start_frame(next_prefs).unwrap();
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let mut comp = comp_buf.as_slice();
+            let mut decomp_buf = Vec::new();
+            for prefs in [prefs, prefs, next_prefs] {
+                let mut r = BufReadDecompressor::new(&mut comp).unwrap();
+                r.set_single_frame(true);
+                assert_eq!(
+                    r.read_frame_info().unwrap().content_checksum(),
+                    prefs.frame_info().content_checksum()
+                );
+                decomp_buf.clear();
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+            }
+            assert!(comp.is_empty());
+        });
+    }
+
+    #[test]
+    fn frame_writer() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = FrameWriter::new(Vec::new(), prefs).unwrap();
+            w.set_max_frame_size(1000);
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let mut comp = comp_buf.as_slice();
+            let mut decomp_buf = Vec::new();
+            let mut frames = 0;
+            while !comp.is_empty() {
+                let (len, dst_len) =
+                    lz4f::decompress_to_vec_with_consumed(comp, &mut decomp_buf).unwrap();
+                assert!(dst_len <= 1000);
+                comp = &comp[len..];
+                frames += 1;
+            }
+            assert_eq!(frames, cmp::max(1, src.len().div_ceil(1000)));
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn frame_writer_duration() {
+        let mut w = FrameWriter::new(Vec::new(), Default::default()).unwrap();
+        w.set_max_frame_duration(Duration::ZERO);
+        w.write_all(b"Hello ").unwrap();
+        w.write_all(b"world!").unwrap();
+        w.flush().unwrap();
+        let comp_buf = w.finish().unwrap();
+
+        let mut decomp_buf = Vec::new();
+        let (len, _) = lz4f::decompress_to_vec_with_consumed(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, b"Hello ");
+        lz4f::decompress_to_vec(&comp_buf[len..], &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, b"Hello world!");
+    }
+
+    #[test]
+    fn adaptive() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = AdaptiveWriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.set_profiles(&[
+                Profile::Fastest,
+                Profile::High(4),
+                Profile::FavorDecompression,
+            ])
+            .unwrap();
+            w.set_window_size(1000);
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+            r.read_to_end(&mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn adaptive_slow_writer() {
+        struct SlowWriter(Vec<u8>);
+
+        impl Write for SlowWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                thread::sleep(Duration::from_millis(50));
+                self.0.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+        let mut w =
+            AdaptiveWriteCompressor::new(SlowWriter(Vec::new()), Default::default()).unwrap();
+        w.set_window_size(src.len() as u64);
+        assert_eq!(w.profile(), Profile::Balanced);
+        for _ in 0..2 {
+            w.write_all(&src).unwrap();
+            w.flush().unwrap();
+        }
+        assert!(w.profile().is_high_compression());
+
+        let comp_buf = w.finish().unwrap().0;
+        let mut decomp_buf = Vec::new();
+        let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+        r.read_to_end(&mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, src.repeat(2));
+    }
+
+    #[test]
+    fn adaptive_fast_writer() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+        let mut w = AdaptiveWriteCompressor::new(Vec::new(), Default::default()).unwrap();
+        w.set_profiles(&[Profile::Fastest, Profile::Max]).unwrap();
+        assert_eq!(w.profile(), Profile::Max);
+        w.set_window_size(src.len() as u64);
+        for _ in 0..4 {
+            w.write_all(&src).unwrap();
+            w.flush().unwrap();
+        }
+        assert_eq!(w.profile(), Profile::Fastest);
+        w.finish().unwrap();
+    }
+
+    #[test]
+    fn adaptive_same_family() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+        let profiles = [
+            &[Profile::High(3), Profile::High(9), Profile::Max][..],
+            &[Profile::Fastest, Profile::Fast(4), Profile::Balanced][..],
+            &[Profile::Fastest, Profile::Max][..],
+        ];
+        for (profiles, mode) in profiles
+            .iter()
+            .flat_map(|p| [(p, BlockMode::Linked), (p, BlockMode::Independent)])
+        {
+            let prefs = PreferencesBuilder::new()
+                .block_mode(mode)
+                .content_size(src.len() * 4)
+                .build();
+            let mut w = AdaptiveWriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.set_profiles(profiles).unwrap();
+            let last = *profiles.last().unwrap();
+            w.set_window_size(src.len() as u64);
+            for _ in 0..4 {
+                w.write_all(&src).unwrap();
+                w.flush().unwrap();
+            }
+            if profiles[0].is_high_compression() == last.is_high_compression() {
+                assert_eq!(w.profile(), profiles[0]);
+            } else {
+                assert_eq!(w.profile(), last);
+            }
+            let comp_buf = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            let len = lz4f::decompress_to_vec_with_consumed(&comp_buf, &mut decomp_buf)
+                .unwrap()
+                .0;
+            assert_eq!(len, comp_buf.len());
+            assert_eq!(decomp_buf, src.repeat(4));
+        }
+    }
+
+    #[test]
+    fn adaptive_shrink_window() {
+        let src = b"The quick brown fox jumps over the lazy dog.".repeat(1000);
+        let mut w = AdaptiveWriteCompressor::new(Vec::new(), Default::default()).unwrap();
+        w.write_all(&src[..10000]).unwrap();
+        w.set_window_size(4096);
+        w.write_all(&src[10000..]).unwrap();
+        let comp_buf = w.finish().unwrap();
+
+        let mut decomp_buf = Vec::new();
+        let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+        r.read_to_end(&mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, src);
+    }
+
+    #[test]
+    fn detect_incompressible() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.set_detect_incompressible(true);
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn detect_incompressible_stored() {
+        // The samples of a repeated random chunk do not compress, but the whole block does.
+        let noise = SmallRng::seed_from_u64(0)
+            .sample_iter(Standard)
+            .take(4096)
+            .collect::<Vec<u8>>()
+            .repeat(64);
+        let text = b"The quick brown fox jumps over the lazy dog.".repeat(6000);
+        let prefs = PreferencesBuilder::new()
+            .block_mode(BlockMode::Independent)
+            .block_size(BlockSize::Max64KB)
+            .content_checksum(ContentChecksum::Enabled)
+            .build();
+        let compress = |detect| {
+            let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+            w.set_detect_incompressible(detect);
+            for chunk in noise.chunks(65536).chain(text.chunks(65536)) {
+                w.write_all(chunk).unwrap();
+            }
+            w.finish().unwrap()
+        };
+
+        let comp_buf = compress(true);
+        assert!(comp_buf.len() > noise.len());
+        assert!(comp_buf.len() > compress(false).len() + noise.len() / 2);
+
+        let mut decomp_buf = Vec::new();
+        lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+        assert_eq!(decomp_buf, [noise, text].concat());
+
+        // Uncompressed blocks are not supported in the linked block mode.
+        let prefs = PreferencesBuilder::from(prefs)
+            .block_mode(BlockMode::Linked)
+            .build();
+        let mut w = WriteCompressor::new(Vec::new(), prefs).unwrap();
+        w.set_detect_incompressible(true);
+        w.write_all(&decomp_buf).unwrap();
+        assert!(w.finish().unwrap().len() < decomp_buf.len() / 4);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "WriteCompressor dropped without being finished")]
+    fn drop_unfinished() {
+        let mut w = WriteCompressor::new(Vec::new(), Default::default()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+    }
+
+    #[test]
+    fn finish_error() {
+        struct FailingWriter(bool);
+
+        impl Write for FailingWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                if self.0 {
+                    Err(std::io::ErrorKind::Other.into())
+                } else {
+                    Ok(buf.len())
+                }
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut w = WriteCompressor::new(FailingWriter(false), Default::default()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        w.get_mut().0 = true;
+        assert!(w.try_finish().is_err());
+        w.get_mut().0 = false;
+        w.try_finish().unwrap();
+
+        let mut w = WriteCompressor::new(FailingWriter(false), Default::default()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        w.get_mut().0 = true;
+        assert!(w.finish().is_err());
+
+        // The error has been reported, so dropping the compressor is fine.
+        let mut w = WriteCompressor::new(FailingWriter(false), Default::default()).unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        w.get_mut().0 = true;
+        assert!(w.try_finish().is_err());
+        drop(w);
+
+        // Nothing has been written.
+        drop(WriteCompressor::new(Vec::new(), Default::default()).unwrap());
+    }
+
+    #[test]
+    fn partial_write_error() {
+        struct PartialWriter {
+            data: Vec<u8>,
+            fail: bool,
+        }
+
+        impl Write for PartialWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                if self.fail {
+                    return Err(std::io::ErrorKind::Other.into());
+                }
+                // Accept a part of the data, then fail on the next call.
+                let len = buf.len().min(7);
+                self.data.extend_from_slice(&buf[..len]);
+                self.fail = true;
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let writer = PartialWriter {
+                data: Vec::new(),
+                fail: false,
+            };
+            let mut w = WriteCompressor::new(writer, prefs).unwrap();
+            for chunk in src.chunks(1024) {
+                while w.write(chunk).is_err() {
+                    w.get_mut().fail = false;
+                }
+            }
+            let mut errors = 0;
+            while w.try_finish().is_err() {
+                w.get_mut().fail = false;
+                errors += 1;
+            }
+            assert!(errors > 0);
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&w.get_ref().data, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
 }
 
 mod read_compressor {
@@ -89,6 +461,22 @@ mod read_compressor {
         });
     }
 
+    #[test]
+    fn detect_incompressible() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            {
+                let mut src = src.as_ref();
+                let mut r = ReadCompressor::new(&mut src, prefs).unwrap();
+                r.set_detect_incompressible(true);
+                r.read_to_end(&mut comp_buf).unwrap();
+            }
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
     #[test]
     fn random_chunk() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -244,6 +632,7 @@ mod write_decompressor {
                 )
                 .unwrap();
                 w.write_all(&src).unwrap();
+                w.finish().unwrap();
             }
             {
                 let mut w = WriteDecompressor::new(&mut decomp_buf).unwrap();
@@ -311,6 +700,59 @@ mod read_decompressor {
     use super::*;
     use lzzzz::lz4f::{ReadDecompressor, WriteCompressor};
 
+    #[test]
+    fn recovery() {
+        let src = (0..100_000u32)
+            .flat_map(u32::to_le_bytes)
+            .collect::<Vec<_>>();
+        let prefs = PreferencesBuilder::new()
+            .block_mode(BlockMode::Independent)
+            .block_checksum(BlockChecksum::Enabled)
+            .content_size(src.len())
+            .build();
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+        comp_buf[30] ^= 0xff;
+
+        let mut damaged = Vec::new();
+        let mut decomp_buf = Vec::new();
+        {
+            let mut comp = comp_buf.as_slice();
+            let mut r = ReadDecompressor::new(&mut comp).unwrap();
+            r.set_recovery(Some(0), |r| damaged.push(*r)).unwrap();
+            assert_eq!(r.read_frame_info().unwrap().content_size(), src.len());
+            r.read_to_end(&mut decomp_buf).unwrap();
+        }
+        assert_eq!(decomp_buf.len(), src.len());
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(damaged[0].output_len(), 64 * 1024);
+        assert_eq!(&decomp_buf[64 * 1024..], &src[64 * 1024..]);
+    }
+
+    #[test]
+    fn truncated() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let error = |len| {
+                let mut src = &comp_buf[..len];
+                let mut r = ReadDecompressor::new(&mut src).unwrap();
+                let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+                *err.into_inner().unwrap().downcast::<lz4f::Error>().unwrap()
+            };
+            assert_eq!(
+                error(1),
+                lz4f::Error::Lz4f(lz4f::ErrorKind::FrameHeaderIncomplete)
+            );
+            for len in [comp_buf.len() / 2, comp_buf.len() - 1] {
+                assert_eq!(
+                    error(len),
+                    lz4f::Error::Common(lzzzz::ErrorKind::CompressedDataIncomplete)
+                );
+            }
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -350,6 +792,7 @@ mod read_decompressor {
                 )
                 .unwrap();
                 w.write_all(&src).unwrap();
+                w.finish().unwrap();
             }
             {
                 let mut src = comp_buf.as_slice();
@@ -398,6 +841,84 @@ mod bufread_decompressor {
     use super::*;
     use lzzzz::lz4f::{BufReadDecompressor, WriteCompressor};
 
+    #[test]
+    fn recovery() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            comp_buf.extend_from_slice(b"garbage");
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let mut rng = SmallRng::seed_from_u64(comp_buf.len() as u64);
+            for _ in 0..3 {
+                let pos = rng.gen_range(0..comp_buf.len());
+                comp_buf[pos] ^= 0xff;
+            }
+
+            let mut expected = Vec::new();
+            let mut expected_damaged = Vec::new();
+            lz4f::decompress_to_vec_with_recovery(&comp_buf, &mut expected, |r| {
+                expected_damaged.push(*r)
+            })
+            .unwrap();
+
+            let mut damaged = Vec::new();
+            let mut decomp_buf = Vec::new();
+            {
+                let mut src = BufReader::with_capacity(rng.gen_range(1..1024), &comp_buf[..]);
+                let mut r = BufReadDecompressor::new(&mut src).unwrap();
+                r.set_recovery(None, |r| damaged.push(*r)).unwrap();
+                let mut offset = 0;
+                loop {
+                    let len = rng.gen_range(1..=1024);
+                    decomp_buf.resize(offset + len, 0);
+                    let len = r.read(&mut decomp_buf[offset..]).unwrap();
+                    decomp_buf.truncate(offset + len);
+                    offset += len;
+                    if len == 0 {
+                        break;
+                    }
+                }
+                assert_eq!(r.total_in(), comp_buf.len() as u64);
+                assert_eq!(r.total_out(), decomp_buf.len() as u64);
+            }
+
+            // Linked blocks are decoded by liblz4, which may output a part of
+            // a damaged block depending on how the input is split.
+            if prefs.frame_info().block_mode() == BlockMode::Independent {
+                assert_eq!(decomp_buf, expected);
+                assert_eq!(damaged, expected_damaged);
+            }
+        });
+    }
+
+    #[test]
+    fn truncated() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let mut src = &comp_buf[..comp_buf.len() - 1];
+            let mut r = BufReadDecompressor::new(&mut src).unwrap();
+            let mut decomp_buf = Vec::new();
+            loop {
+                match r.fill_buf() {
+                    Ok([]) => panic!("unexpected EOF"),
+                    Ok(buf) => {
+                        let len = buf.len();
+                        decomp_buf.extend_from_slice(buf);
+                        r.consume(len);
+                    }
+                    Err(err) => {
+                        assert_eq!(
+                            *err.into_inner().unwrap().downcast::<lz4f::Error>().unwrap(),
+                            lz4f::Error::Common(lzzzz::ErrorKind::CompressedDataIncomplete)
+                        );
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
     #[test]
     fn default() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -420,6 +941,28 @@ mod bufread_decompressor {
         });
     }
 
+    #[test]
+    fn single_frame() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let frame_len = comp_buf.len();
+            comp_buf.extend_from_slice(b"trailing data");
+
+            let mut comp = comp_buf.as_slice();
+            {
+                let mut r = BufReadDecompressor::new(&mut comp).unwrap();
+                r.set_single_frame(true);
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(r.total_in(), frame_len as u64);
+                assert_eq!(r.read(&mut [0; 16]).unwrap(), 0);
+            }
+            assert_eq!(decomp_buf, src);
+            assert_eq!(comp, b"trailing data");
+        });
+    }
+
     #[test]
     fn dictionary() {
         lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
@@ -437,6 +980,7 @@ mod bufread_decompressor {
                 )
                 .unwrap();
                 w.write_all(&src).unwrap();
+                w.finish().unwrap();
             }
             {
                 let mut src = comp_buf.as_slice();
@@ -480,3 +1024,394 @@ mod bufread_decompressor {
         });
     }
 }
+
+mod decompression_limits {
+    use super::*;
+    use lzzzz::lz4f::{
+        BufReadDecompressor, DecompressionLimitsBuilder, ReadDecompressor, WriteDecompressor,
+    };
+
+    fn limit_exceeded(err: std::io::Error) -> bool {
+        *err.into_inner().unwrap().downcast::<lz4f::Error>().unwrap()
+            == lz4f::Error::Common(lzzzz::ErrorKind::DecompressionLimitExceeded)
+    }
+
+    #[test]
+    fn max_output_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len() - 1)
+                .build();
+            {
+                let mut src = comp_buf.as_slice();
+                let mut r = ReadDecompressor::new(&mut src).unwrap();
+                r.set_limits(limits);
+                let mut decomp_buf = Vec::new();
+                let err = r.read_to_end(&mut decomp_buf).unwrap_err();
+                assert!(limit_exceeded(err));
+                assert!(decomp_buf.len() <= limits.max_output_size());
+                assert!(r.total_out() <= limits.max_output_size() as u64);
+            }
+            {
+                let mut src = comp_buf.as_slice();
+                let mut r = BufReadDecompressor::new(&mut src).unwrap();
+                r.set_limits(limits);
+                assert!(limit_exceeded(r.read_to_end(&mut Vec::new()).unwrap_err()));
+            }
+            {
+                let mut decomp_buf = Vec::new();
+                let mut w = WriteDecompressor::new(&mut decomp_buf).unwrap();
+                w.set_limits(limits);
+                assert!(limit_exceeded(w.write_all(&comp_buf).unwrap_err()));
+            }
+        });
+    }
+
+    #[test]
+    fn max_frames() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut decomp_buf = Vec::new();
+            {
+                let mut src = comp_buf.as_slice();
+                let mut r = ReadDecompressor::new(&mut src).unwrap();
+                r.set_limits(DecompressionLimitsBuilder::new().max_frames(2).build());
+                r.read_to_end(&mut decomp_buf).unwrap();
+            }
+            assert_eq!(decomp_buf, [&src[..], &src[..]].concat());
+
+            let mut src = comp_buf.as_slice();
+            let mut r = ReadDecompressor::new(&mut src).unwrap();
+            r.set_limits(DecompressionLimitsBuilder::new().max_frames(1).build());
+            assert!(limit_exceeded(r.read_to_end(&mut Vec::new()).unwrap_err()));
+        });
+    }
+
+    #[test]
+    fn max_content_size() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let prefs = PreferencesBuilder::from(prefs).content_size(1).build();
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut comp = comp_buf.as_slice();
+            let mut r = ReadDecompressor::new(&mut comp).unwrap();
+            r.set_limits(
+                DecompressionLimitsBuilder::new()
+                    .max_content_size(src.len() - 1)
+                    .build(),
+            );
+            assert!(limit_exceeded(r.read_frame_info().unwrap_err()));
+        });
+    }
+
+    #[test]
+    fn recovery() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let limits = DecompressionLimitsBuilder::new()
+                .max_output_size(src.len() - 1)
+                .build();
+            {
+                let mut comp = comp_buf.as_slice();
+                let mut r = BufReadDecompressor::new(&mut comp).unwrap();
+                r.set_limits(limits);
+                r.set_recovery(None, |_| ()).unwrap();
+                let mut decomp_buf = Vec::new();
+                assert!(limit_exceeded(r.read_to_end(&mut decomp_buf).unwrap_err()));
+                assert!(decomp_buf.len() <= limits.max_output_size());
+            }
+            {
+                let mut comp = comp_buf.as_slice();
+                let mut r = ReadDecompressor::new(&mut comp).unwrap();
+                r.set_recovery(None, |_| ()).unwrap();
+                r.set_limits(DecompressionLimitsBuilder::new().max_frames(1).build());
+                assert!(limit_exceeded(r.read_to_end(&mut Vec::new()).unwrap_err()));
+            }
+        });
+    }
+}
+
+mod validation_policy {
+    use super::*;
+    use lzzzz::lz4f::{ReadDecompressor, ValidationPolicyBuilder};
+
+    #[test]
+    fn require_checksum() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let mut comp = comp_buf.as_slice();
+            let mut r = ReadDecompressor::new(&mut comp).unwrap();
+            r.set_validation_policy(
+                ValidationPolicyBuilder::new()
+                    .require_content_checksum(true)
+                    .require_block_checksum(true)
+                    .build(),
+            );
+            let mut decomp_buf = Vec::new();
+            let result = r.read_to_end(&mut decomp_buf);
+            let info = prefs.frame_info();
+            if info.content_checksum() == ContentChecksum::Disabled
+                || info.block_checksum() == BlockChecksum::Disabled
+            {
+                assert!(result.is_err());
+            } else {
+                assert_eq!(decomp_buf, src);
+            }
+        });
+    }
+}
+
+mod stats {
+    use super::*;
+    use lzzzz::lz4f::{BufReadCompressor, ReadDecompressor, WriteCompressor, WriteDecompressor};
+    use std::sync::mpsc;
+
+    #[test]
+    fn compressor() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let (tx, rx) = mpsc::channel();
+            let mut comp_buf = Vec::new();
+            {
+                let mut w = WriteCompressor::new(&mut comp_buf, prefs).unwrap();
+                w.set_frame_callback(move |stats| tx.send(*stats).unwrap());
+                w.write_all(&src).unwrap();
+                assert_eq!(w.total_in(), src.len() as u64);
+                w.finish().unwrap();
+            }
+            let frames = rx.iter().collect::<Vec<_>>();
+            assert_eq!(frames.len(), 1);
+            assert_eq!(frames[0].uncompressed_len(), src.len() as u64);
+            assert_eq!(frames[0].compressed_len(), comp_buf.len() as u64);
+
+            let mut src_slice = &src[..];
+            let mut r = BufReadCompressor::new(&mut src_slice, prefs).unwrap();
+            let mut comp_buf = Vec::new();
+            r.read_to_end(&mut comp_buf).unwrap();
+            assert_eq!(r.total_in(), src.len() as u64);
+            assert_eq!(r.total_out(), comp_buf.len() as u64);
+            assert_eq!(r.ratio(), src.len() as f64 / comp_buf.len() as f64);
+        });
+    }
+
+    #[test]
+    fn decompressor() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut comp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            let frame_len = comp_buf.len() as u64;
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+
+            let (tx, rx) = mpsc::channel();
+            let mut comp = comp_buf.as_slice();
+            let mut r = ReadDecompressor::new(&mut comp).unwrap();
+            r.set_frame_callback(move |stats| tx.send(*stats).unwrap());
+            let mut decomp_buf = Vec::new();
+            r.read_to_end(&mut decomp_buf).unwrap();
+            assert_eq!(r.total_in(), comp_buf.len() as u64);
+            assert_eq!(r.total_out(), decomp_buf.len() as u64);
+            drop(r);
+            let frames = rx
+                .iter()
+                .map(|stats| (stats.uncompressed_len(), stats.compressed_len()))
+                .collect::<Vec<_>>();
+            assert_eq!(frames, [(src.len() as u64, frame_len); 2]);
+
+            let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+            w.write_all(&comp_buf).unwrap();
+            assert_eq!(w.total_in(), comp_buf.len() as u64);
+            assert_eq!(w.total_out(), src.len() as u64 * 2);
+        });
+    }
+}
+
+mod context_pool {
+    use super::*;
+    use lzzzz::lz4f::{
+        BufReadCompressor, ContextPool, ReadDecompressor, WriteCompressor, WriteDecompressor,
+    };
+
+    #[test]
+    fn default() {
+        let pool = ContextPool::new();
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let ctx = pool.compression_context().unwrap();
+            let mut w = WriteCompressor::with_context(Vec::new(), prefs, ctx);
+            w.write_all(&src).unwrap();
+            let comp_buf = w.finish().unwrap();
+
+            let ctx = pool.decompression_context().unwrap();
+            let mut r = ReadDecompressor::with_context(comp_buf.as_slice(), ctx);
+            let mut decomp_buf = Vec::new();
+            r.read_to_end(&mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+        assert!(!pool.is_empty());
+    }
+
+    #[test]
+    fn capacity() {
+        let pool = ContextPool::with_capacity(1);
+        let contexts = (0..3)
+            .map(|_| pool.decompression_context().unwrap())
+            .collect::<Vec<_>>();
+        assert!(pool.is_empty());
+        drop(contexts);
+        assert_eq!(pool.len(), 1);
+
+        // A context returned in the middle of a frame is reset.
+        let mut comp_buf = Vec::new();
+        lz4f::compress_to_vec(b"Hello world!", &mut comp_buf, &Default::default()).unwrap();
+        let mut w =
+            WriteDecompressor::with_context(Vec::new(), pool.decompression_context().unwrap());
+        w.write_all(&comp_buf[..comp_buf.len() / 2]).unwrap();
+        drop(w);
+
+        let mut w =
+            WriteDecompressor::with_context(Vec::new(), pool.decompression_context().unwrap());
+        w.write_all(&comp_buf).unwrap();
+        assert_eq!(w.get_ref(), b"Hello world!");
+    }
+
+    #[test]
+    fn reset() {
+        lz4f_test_set().par_bridge().for_each(|(src, prefs)| {
+            let mut w = WriteCompressor::new(Vec::new(), Default::default()).unwrap();
+            w.write_all(b"Hello world!").unwrap();
+            let first = w.reset(Vec::new(), prefs).unwrap();
+            w.write_all(&src).unwrap();
+            let second = w.finish().unwrap();
+
+            let mut decomp_buf = Vec::new();
+            lz4f::decompress_to_vec(&first, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, b"Hello world!");
+            decomp_buf.clear();
+            lz4f::decompress_to_vec(&second, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+
+            let mut r = BufReadCompressor::new(&b"Hello world!"[..], Default::default()).unwrap();
+            r.read_exact(&mut [0; 4]).unwrap();
+            r.reset(&src[..], prefs);
+            let mut comp_buf = Vec::new();
+            r.read_to_end(&mut comp_buf).unwrap();
+            decomp_buf.clear();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+}
+
+mod dictionary_registry {
+    use super::*;
+    use lzzzz::lz4f::{
+        BufReadDecompressor, Dictionary, DictionaryRegistry, ReadDecompressor, WriteCompressor,
+        WriteDecompressor,
+    };
+
+    fn dict(seed: u64) -> Vec<u8> {
+        SmallRng::seed_from_u64(seed)
+            .sample_iter(Standard)
+            .take(64_000)
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn lookup() {
+        let (dict_a, dict_b) = (dict(0), dict(1));
+        let mut registry = DictionaryRegistry::new();
+        registry.insert(1, &dict_a);
+        registry.insert(2, &dict_b);
+        assert_eq!(registry.len(), 2);
+
+        lz4f_test_set()
+            .filter(|(_, prefs)| prefs.frame_info().dict_id() == 0)
+            .par_bridge()
+            .for_each(|(src, prefs)| {
+                let mut w = WriteCompressor::with_dict(
+                    Vec::new(),
+                    prefs,
+                    Dictionary::with_id(&dict_a, 1).unwrap(),
+                )
+                .unwrap();
+                w.write_all(&src).unwrap();
+                w.finish_frame().unwrap();
+                let mut comp_buf = w.finish().unwrap();
+
+                let mut w = WriteCompressor::with_dict(
+                    Vec::new(),
+                    prefs,
+                    Dictionary::with_id(&dict_b, 2).unwrap(),
+                )
+                .unwrap();
+                w.write_all(&src).unwrap();
+                comp_buf.append(&mut w.finish().unwrap());
+
+                let expected = [&src[..], &src[..]].concat();
+
+                let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.set_dict_registry(registry.clone());
+                assert_eq!(r.read_frame_info().unwrap().dict_id(), 1);
+                let mut decomp_buf = Vec::new();
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, expected);
+
+                let mut r = BufReadDecompressor::new(comp_buf.as_slice()).unwrap();
+                r.set_dict_registry(registry.clone());
+                decomp_buf.clear();
+                r.read_to_end(&mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, expected);
+
+                let mut w = WriteDecompressor::new(Vec::new()).unwrap();
+                w.set_dict_registry(registry.clone());
+                w.write_all(&comp_buf).unwrap();
+                assert_eq!(w.get_ref(), &expected);
+            });
+    }
+
+    #[test]
+    fn explicit_dict_id() {
+        let dict = dict(0);
+        let prefs = PreferencesBuilder::new().dict_id(5).build();
+        let mut w =
+            WriteCompressor::with_dict(Vec::new(), prefs, Dictionary::with_id(&dict, 1).unwrap())
+                .unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        let comp_buf = w.finish().unwrap();
+
+        let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+        assert_eq!(r.read_frame_info().unwrap().dict_id(), 5);
+    }
+
+    #[test]
+    fn not_found() {
+        let dict = dict(0);
+        let mut w = WriteCompressor::with_dict(
+            Vec::new(),
+            Default::default(),
+            Dictionary::with_id(&dict, 3).unwrap(),
+        )
+        .unwrap();
+        w.write_all(b"Hello world!").unwrap();
+        let comp_buf = w.finish().unwrap();
+
+        let mut registry = DictionaryRegistry::new();
+        registry.insert(1, &dict);
+        let mut r = ReadDecompressor::new(comp_buf.as_slice()).unwrap();
+        r.set_dict_registry(registry);
+        let err = r.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(
+            *err.into_inner().unwrap().downcast::<lz4f::Error>().unwrap(),
+            lz4f::Error::Common(lzzzz::ErrorKind::DictionaryNotFound)
+        );
+    }
+}
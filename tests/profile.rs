@@ -0,0 +1,133 @@
+use lzzzz::{lz4, lz4_hc, lz4f, Profile};
+use rayon::{iter::ParallelBridge, prelude::*};
+
+mod common;
+use common::lz4_test_set;
+
+fn profile_set() -> impl Iterator<Item = Profile> {
+    vec![
+        Profile::Fastest,
+        Profile::Fast(i32::MIN),
+        Profile::Fast(0),
+        Profile::Fast(10),
+        Profile::Fast(i32::MAX),
+        Profile::Balanced,
+        Profile::High(i32::MIN),
+        Profile::High(lz4_hc::CLEVEL_DEFAULT),
+        Profile::High(i32::MAX),
+        Profile::Max,
+        Profile::FavorDecompression,
+    ]
+    .into_iter()
+}
+
+mod compress_block {
+    use super::*;
+
+    #[test]
+    fn default() {
+        lz4_test_set()
+            .flat_map(|(src, _)| profile_set().map(move |profile| (src.clone(), profile)))
+            .par_bridge()
+            .for_each(|(src, profile)| {
+                let mut comp_buf = vec![0; lz4::max_compressed_size(src.len())];
+                let mut decomp_buf = vec![0; src.len()];
+                let len = lzzzz::compress_block(&src, &mut comp_buf, profile).unwrap();
+                lz4::decompress(&comp_buf[..len], &mut decomp_buf).unwrap();
+                assert_eq!(decomp_buf, src);
+
+                let header = &b"HEADER"[..];
+                let mut vec_buf = Vec::from(header);
+                assert_eq!(
+                    lzzzz::compress_block_to_vec(&src, &mut vec_buf, profile).unwrap(),
+                    len
+                );
+                assert_eq!(&vec_buf[header.len()..], &comp_buf[..len]);
+            });
+    }
+
+    #[test]
+    fn backend() {
+        lz4_test_set().par_bridge().for_each(|(src, _)| {
+            let mut expected = Vec::new();
+            let mut comp_buf = Vec::new();
+
+            lz4::compress_to_vec(&src, &mut expected, 10).unwrap();
+            lzzzz::compress_block_to_vec(&src, &mut comp_buf, Profile::Fast(10)).unwrap();
+            assert_eq!(comp_buf, expected);
+
+            expected.clear();
+            comp_buf.clear();
+            lz4::compress_to_vec(&src, &mut expected, lz4::ACC_LEVEL_DEFAULT).unwrap();
+            lzzzz::compress_block_to_vec(&src, &mut comp_buf, Profile::Balanced).unwrap();
+            assert_eq!(comp_buf, expected);
+
+            expected.clear();
+            comp_buf.clear();
+            lz4_hc::compress_to_vec(&src, &mut expected, lz4_hc::CLEVEL_MAX).unwrap();
+            lzzzz::compress_block_to_vec(&src, &mut comp_buf, Profile::Max).unwrap();
+            assert_eq!(comp_buf, expected);
+        });
+    }
+
+    #[test]
+    fn favor_dec_speed() {
+        lz4_test_set().par_bridge().for_each(|(src, _)| {
+            let mut expected = vec![0; lz4::max_compressed_size(src.len())];
+            let mut comp = lz4_hc::Compressor::new().unwrap();
+            comp.set_compression_level(lz4_hc::CLEVEL_OPT_MIN);
+            comp.set_favor_dec_speed(lz4_hc::FavorDecSpeed::Enabled);
+            let len = comp.next(&src, &mut expected).unwrap();
+            expected.truncate(len);
+
+            let mut comp_buf = Vec::new();
+            lzzzz::compress_block_to_vec(&src, &mut comp_buf, Profile::FavorDecompression).unwrap();
+            assert_eq!(comp_buf, expected);
+
+            // The flag must not leak into the other functions sharing the thread-local state.
+            let mut expected = Vec::new();
+            lz4_hc::compress_to_vec(&src, &mut expected, lz4_hc::CLEVEL_OPT_MIN).unwrap();
+            comp_buf.clear();
+            lzzzz::compress_block_to_vec(&src, &mut comp_buf, Profile::High(10)).unwrap();
+            assert_eq!(comp_buf, expected);
+        });
+    }
+}
+
+mod preferences {
+    use super::*;
+
+    #[test]
+    fn profile() {
+        profile_set().for_each(|profile| {
+            let prefs = lz4f::PreferencesBuilder::new().profile(profile).build();
+            assert_eq!(prefs.compression_level(), profile.lz4f_level());
+            assert_eq!(
+                prefs.favor_dec_speed() == lz4f::FavorDecSpeed::Enabled,
+                profile.favor_dec_speed()
+            );
+
+            let src = b"The quick brown fox jumps over the lazy dog.".repeat(100);
+            let mut comp_buf = Vec::new();
+            let mut decomp_buf = Vec::new();
+            lz4f::compress_to_vec(&src, &mut comp_buf, &prefs).unwrap();
+            lz4f::decompress_to_vec(&comp_buf, &mut decomp_buf).unwrap();
+            assert_eq!(decomp_buf, src);
+        });
+    }
+
+    #[test]
+    fn levels() {
+        assert_eq!(Profile::Balanced.lz4f_level(), lz4f::CLEVEL_DEFAULT);
+        assert_eq!(Profile::Fast(1).lz4f_level(), lz4f::CLEVEL_DEFAULT);
+        assert_eq!(Profile::Fast(10).lz4f_level(), -9);
+        assert_eq!(Profile::Max.lz4f_level(), lz4f::CLEVEL_MAX);
+        assert_eq!(Profile::High(0).hc_level(), Some(lz4_hc::CLEVEL_MIN));
+        assert_eq!(Profile::High(100).hc_level(), Some(lz4_hc::CLEVEL_MAX));
+        assert_eq!(Profile::High(5).acceleration(), None);
+        assert_eq!(Profile::Fast(-5).acceleration(), Some(1));
+        assert!(Profile::FavorDecompression.is_high_compression());
+        assert!(!Profile::Fastest.is_high_compression());
+        assert_eq!(Profile::default(), Profile::Balanced);
+    }
+}
@@ -0,0 +1,95 @@
+use lzzzz::{lz4, lz4_hc, lz4f, DictionaryTrainer};
+use std::io::prelude::*;
+
+mod dictionary_trainer {
+    use super::*;
+
+    fn samples() -> Vec<String> {
+        (0..2000)
+            .map(|i| {
+                format!(
+                    r#"{{"id":{},"name":"user{}","email":"user{}@example.com","active":{},"tags":["alpha","beta"]}}"#,
+                    i,
+                    i * 31 % 1000,
+                    i * 17 % 1000,
+                    i % 3 == 0
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn train() {
+        let samples = samples();
+        let dict = DictionaryTrainer::new().max_size(2048).train(&samples);
+        assert!(!dict.as_bytes().is_empty());
+        assert!(dict.as_bytes().len() <= 2048);
+        assert_ne!(dict.dict_id(), 0);
+        assert_eq!(
+            DictionaryTrainer::new().max_size(2048).train(&samples),
+            dict
+        );
+        assert_eq!(
+            DictionaryTrainer::new()
+                .dict_id(7)
+                .train(&samples)
+                .dict_id(),
+            7
+        );
+
+        let empty = DictionaryTrainer::new().train::<&[u8]>(&[]);
+        assert!(empty.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn ratio() {
+        let samples = samples();
+        let dict = DictionaryTrainer::new().train(&samples[..1000]);
+        let (mut plain, mut with_dict) = (0, 0);
+        for sample in &samples[1000..] {
+            let mut buf = Vec::new();
+            plain += lz4::compress_to_vec(sample.as_bytes(), &mut buf, 1).unwrap();
+
+            buf.clear();
+            let mut comp = lz4::Compressor::with_dict(dict.as_bytes()).unwrap();
+            let len = comp.next_to_vec(sample.as_bytes(), &mut buf, 1).unwrap();
+            with_dict += len;
+
+            let mut decomp = vec![0; sample.len()];
+            lz4::decompress_with_dict(&buf, &mut decomp, dict.as_bytes()).unwrap();
+            assert_eq!(decomp, sample.as_bytes());
+        }
+        assert!(with_dict * 2 < plain);
+    }
+
+    #[test]
+    fn all_apis() {
+        let samples = samples();
+        let dict = DictionaryTrainer::new().train(&samples);
+        let sample = samples[0].as_bytes();
+
+        let mut buf = Vec::new();
+        let mut comp = lz4_hc::Compressor::with_dict(dict.as_bytes(), 9).unwrap();
+        comp.next_to_vec(sample, &mut buf).unwrap();
+        let mut decomp = vec![0; sample.len()];
+        lz4::decompress_with_dict(&buf, &mut decomp, dict.as_bytes()).unwrap();
+        assert_eq!(decomp, sample);
+
+        let mut w = lz4f::WriteCompressor::with_dict(
+            Vec::new(),
+            Default::default(),
+            dict.lz4f_dictionary().unwrap(),
+        )
+        .unwrap();
+        w.write_all(sample).unwrap();
+        let comp = w.finish().unwrap();
+
+        let mut registry = lz4f::DictionaryRegistry::new();
+        registry.insert(dict.dict_id(), dict.as_bytes());
+        let mut r = lz4f::ReadDecompressor::new(&comp[..]).unwrap();
+        r.set_dict_registry(registry);
+        let mut decomp = Vec::new();
+        r.read_to_end(&mut decomp).unwrap();
+        assert_eq!(decomp, sample);
+    }
+}